@@ -14,7 +14,7 @@ use core::fmt::Write;
 use embassy_executor::Spawner;
 use embassy_futures::select::{self, Either};
 use embassy_net::{DhcpConfig, Runner, Stack, StackResources};
-use embassy_time::{Duration, Ticker, WithTimeout};
+use embassy_time::{Delay, Duration, Instant, Ticker};
 use esp_alloc as _;
 use esp_backtrace as _;
 use esp_hal::{
@@ -26,7 +26,13 @@ use esp_radio::wifi::{
     self, ControllerConfig, CountryInfo, Interface, OperatingClass, WifiController,
     sta::StationConfig,
 };
-use freemdu::device::{self, Action, ActionKind, Date, Property, PropertyKind, Value};
+use freemdu::{
+    device::{
+        self, Action, ActionKind, Date, DeviceRegistry, Property, PropertyKind, Value,
+        generic::GenericConfig,
+    },
+    time::Timeouts,
+};
 use freemdu_home::OpticalPort;
 use log::{error, info};
 use mcutie::{
@@ -42,8 +48,8 @@ use static_cell::StaticCell;
 const DEVICE_PUBLISH_INTERVAL: Duration =
     Duration::from_secs(freemdu_home::num_from_env!("DEVICE_PUBLISH_INTERVAL", u64));
 
-// Timeout for device operations (e.g. connection)
-const DEVICE_TIMEOUT: Duration = Duration::from_secs(1);
+// Number of retries for corrupted diagnostic frames
+const DEVICE_MAX_RETRIES: u8 = 2;
 
 // Delay between Wi-Fi reconnection attempts
 const WIFI_RETRY_DELAY: Duration = Duration::from_secs(5);
@@ -117,29 +123,31 @@ async fn mqtt_message_task(
 async fn publish_device(port: &mut OpticalPort<'_>, hostname: &str) -> Result<()> {
     let mut dev = connect_to_device(port).await?;
     let dev_kind = dev.kind().to_string();
-    let props = dev
+    let props: Vec<_> = dev
         .properties()
         .iter()
-        .filter(|prop| prop.kind == PropertyKind::Operation);
+        .filter(|prop| prop.kind == PropertyKind::Operation)
+        .collect();
     let actions = dev
         .actions()
         .iter()
         .filter(|action| action.kind == ActionKind::Operation);
-    let mut vals = Vec::with_capacity(props.clone().count());
+    let mut vals = Vec::with_capacity(props.len());
 
     // Query properties first, as publishing them immediately might lead to timeout
-    for prop in props.clone() {
-        let val = dev
-            .query_property(prop)
-            .with_timeout(DEVICE_TIMEOUT)
-            .await
-            .map_err(|err| anyhow::anyhow!("Failed to query property: {err:?}"))??;
+    let results = dev
+        .query_properties(&props)
+        .await
+        .map_err(|err| anyhow::anyhow!("Failed to query properties: {err:?}"))?;
+
+    for (prop, res) in props.iter().zip(results) {
+        let val = res.map_err(|err| anyhow::anyhow!("Failed to query property: {err:?}"))?;
 
         info!("Queried property {prop:?} with value {val:?}");
         vals.push(val);
     }
 
-    for (prop, val) in props.zip(vals) {
+    for (prop, val) in props.into_iter().zip(vals) {
         publish_property(prop, &dev_kind, hostname).await?;
         publish_property_value(prop, &val).await?;
         info!("Published property: {prop:?}");
@@ -195,7 +203,24 @@ async fn publish_property_value(prop: &Property, val: &Value) -> Result<()> {
                 .publish()
                 .await
         }
+        Value::Integer(num) => topic.with_display(num).publish().await,
+        Value::Decimal { value, scale } => {
+            let divisor = 10_u32.pow(scale.into());
+            let (sign, abs) = (if value < 0 { "-" } else { "" }, value.unsigned_abs());
+
+            topic
+                .with_display(format!(
+                    "{sign}{}.{:0width$}",
+                    abs / divisor,
+                    abs % divisor,
+                    width = usize::from(scale)
+                ))
+                .publish()
+                .await
+        }
         Value::String(ref string) => topic.with_display(string).publish().await,
+        Value::Enum { value, .. } => topic.with_display(value).publish().await,
+        Value::Flags { ref set, .. } => topic.with_display(set.join(" | ")).publish().await,
         Value::Duration(dur) => {
             let total_mins = dur.as_secs() / 60;
             let hours = total_mins / 60;
@@ -255,20 +280,30 @@ async fn trigger_action(port: &mut OpticalPort<'_>, id: &str, param: &str) -> Re
         None
     };
 
-    Ok(dev
-        .trigger_action(action, param)
-        .with_timeout(DEVICE_TIMEOUT)
+    dev.trigger_action(action, param)
         .await
-        .map_err(|err| anyhow::anyhow!("Failed to trigger action: {err:?}"))??)
+        .map_err(|err| anyhow::anyhow!("Failed to trigger action: {err:?}"))
 }
 
 async fn connect_to_device<'a, 'b>(
     port: &'a mut OpticalPort<'b>,
 ) -> Result<Box<dyn device::Device<&'a mut OpticalPort<'b>> + 'a>> {
-    let dev = device::connect(port)
-        .with_timeout(DEVICE_TIMEOUT)
+    let mut intf = freemdu::Interface::new(port);
+
+    intf.enable_timeouts(Delay, Timeouts::default());
+    intf.set_max_retries(DEVICE_MAX_RETRIES);
+
+    // Device implementations outside the freemdu crate can be registered here,
+    // unsupported devices fall back to a generic device
+    let registry = DeviceRegistry::new().with_fallback(GenericConfig::new());
+    let mut dev = registry
+        .connect_with(intf)
         .await
-        .map_err(|err| anyhow::anyhow!("Failed to connect to device: {err:?}"))??;
+        .map_err(|err| anyhow::anyhow!("Failed to connect to device: {err:?}"))?;
+
+    // Transparently restore the session if the device locks itself
+    dev.interface()
+        .enable_session(|| core::time::Duration::from_micros(Instant::now().as_micros()));
 
     info!(
         "Connected to device with kind {} and software ID {}",
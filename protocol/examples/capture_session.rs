@@ -0,0 +1,29 @@
+use freemdu::capture::CapturePort;
+use std::{error::Error, fs, time::Instant};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let start = Instant::now();
+    let port = freemdu::serial::open("/dev/ttyACM0")?;
+    let mut port = CapturePort::new(port, move || start.elapsed());
+
+    {
+        let mut dev = freemdu::device::connect(&mut port).await?;
+
+        println!("Connected to device with software ID {}", dev.software_id());
+
+        // Query all properties to record a representative session
+        for prop in dev.properties() {
+            match dev.query_property(prop).await {
+                Ok(val) => println!("{}: {val:?}", prop.name),
+                Err(err) => eprintln!("{}: {err}", prop.name),
+            }
+        }
+    }
+
+    fs::write("capture.txt", port.capture().to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use freemdu::{
+    capture::{Capture, Direction, Record},
+    dissector::Dissector,
+};
+use std::{env, error::Error, fs, time::Duration};
+
+/// Extracts the transferred data from a `RUST_LOG=freemdu=trace` log.
+fn parse_trace_log(log: &str) -> Capture {
+    let mut capture = Capture::new();
+
+    for line in log.lines() {
+        let (direction, data) = if let Some((_, data)) = line.split_once("Write to port: ") {
+            (Direction::Tx, data)
+        } else if let Some((_, data)) = line.split_once("Read from port: ") {
+            (Direction::Rx, data)
+        } else if let Some((_, data)) = line.split_once("Timed out reading from port: ") {
+            (Direction::Rx, data)
+        } else {
+            continue;
+        };
+        let data = data
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(", ")
+            .filter_map(|byte| u8::from_str_radix(byte, 16).ok())
+            .collect();
+
+        capture.push(Record {
+            time: Duration::ZERO,
+            direction,
+            data,
+        });
+    }
+
+    capture
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = env::args()
+        .nth(1)
+        .ok_or("Usage: dissect <capture file or trace log>")?;
+    let text = fs::read_to_string(path)?;
+    // Log timestamps are not evaluated
+    let (capture, timestamps) = match text.parse::<Capture>() {
+        Ok(capture) => (capture, true),
+        Err(_) => (parse_trace_log(&text), false),
+    };
+    let mut dissector = Dissector::new();
+
+    for record in capture.records() {
+        for frame in dissector.push(record.direction, &record.data) {
+            if timestamps {
+                print!("{:>12.6} ", record.time.as_secs_f64());
+            }
+
+            println!("{frame}");
+        }
+    }
+
+    for frame in dissector.finish() {
+        println!("{frame}");
+    }
+
+    Ok(())
+}
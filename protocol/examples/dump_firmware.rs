@@ -0,0 +1,148 @@
+use freemdu::firmware::FirmwareDump;
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, ErrorKind, Write},
+};
+
+const USAGE: &str = "Usage: dump_firmware [--port <path>] [--format bin|hex|srec] \
+                     [--range <start>-<end>] [<output file>]";
+
+/// Output format of the firmware image.
+#[derive(Copy, Clone)]
+enum Format {
+    Binary,
+    IntelHex,
+    SRecord,
+}
+
+struct Args {
+    port: String,
+    format: Format,
+    range: Option<(u32, u32)>,
+    output: Option<String>,
+}
+
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut args = Args {
+        port: "/dev/ttyACM0".into(),
+        format: Format::Binary,
+        range: None,
+        output: None,
+    };
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--port" => args.port = iter.next().ok_or(USAGE)?,
+            "--format" => {
+                args.format = match iter.next().ok_or(USAGE)?.as_str() {
+                    "bin" => Format::Binary,
+                    "hex" => Format::IntelHex,
+                    "srec" => Format::SRecord,
+                    _ => return Err(USAGE.into()),
+                };
+            }
+            "--range" => {
+                // Inclusive hexadecimal address range, e.g. `8000-ffff`
+                let range = iter.next().ok_or(USAGE)?;
+                let (start, end) = range.split_once('-').ok_or(USAGE)?;
+
+                args.range = Some((
+                    u32::from_str_radix(start, 16)?,
+                    u32::from_str_radix(end, 16)?,
+                ));
+            }
+            _ if args.output.is_none() => args.output = Some(arg),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    Ok(args)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let args = parse_args()?;
+    let mut port = freemdu::serial::open(&args.port)?;
+    let mut dev = freemdu::device::connect(&mut port).await?;
+    let software_id = dev.software_id();
+    let mut dump = match args.range {
+        Some((start, end)) => FirmwareDump::new(software_id, start..end + 1),
+        None => FirmwareDump::for_software_id(software_id)
+            .ok_or("ROM range of device unknown, specify it using --range")?,
+    };
+    let ext = match args.format {
+        Format::Binary => "bin",
+        Format::IntelHex => "hex",
+        Format::SRecord => "srec",
+    };
+    let output = args
+        .output
+        .unwrap_or_else(|| format!("firmware_{software_id}.{ext}"));
+    let partial = format!("{output}.part");
+
+    // Resume an interrupted dump from the partial file
+    match fs::read(&partial) {
+        Ok(data) => {
+            dump = dump.with_data(data);
+            println!("Resuming dump at {} bytes", dump.data().len());
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    // Switch to a higher baud rate if supported by the device
+    let rate = dev.interface().negotiate_fast_mode().await?;
+
+    println!("Communicating at {} baud", rate.as_baud());
+
+    let range = dump.range();
+
+    while !dump.is_complete() {
+        dump.read_next(dev.interface()).await?;
+        fs::write(&partial, dump.data())?;
+
+        print!(
+            "\rReading {:04x}-{:04x}: {}/{} bytes",
+            range.start,
+            range.end - 1,
+            dump.data().len(),
+            dump.len()
+        );
+        io::stdout().flush().unwrap();
+    }
+
+    println!();
+
+    let image = dump
+        .verify(dev.interface(), |done, total| {
+            print!("\rVerifying: {done}/{total} bytes");
+            io::stdout().flush().unwrap();
+        })
+        .await?;
+
+    println!();
+
+    match args.format {
+        Format::Binary => fs::write(&output, &image.data)?,
+        Format::IntelHex => fs::write(&output, image.to_intel_hex())?,
+        Format::SRecord => fs::write(&output, image.to_srecord())?,
+    }
+
+    fs::remove_file(&partial)?;
+
+    println!("Saved firmware of software ID {software_id} to {output}");
+    print!("SHA-256: ");
+
+    for byte in image.fingerprint() {
+        print!("{byte:02x}");
+    }
+
+    println!();
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use std::{
     error::Error,
     fs::OpenOptions,
-    io::{Seek, SeekFrom, Write},
+    io::{self, Seek, SeekFrom, Write},
 };
 
 #[tokio::main]
@@ -19,16 +19,33 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .append(true)
         .open("memory_dump.bin")?;
 
-    // Resume dumping process if previously interrupted
-    let offset: u32 = file.seek(SeekFrom::End(0))?.try_into()?;
-
-    for addr in (START + offset..=END).step_by(0x80) {
-        println!("Reading memory address {addr:08x}");
+    // Switch to a higher baud rate if supported by the device
+    let rate = dev.interface().negotiate_fast_mode().await?;
 
-        let data: [u8; 0x80] = dev.interface().read_memory(addr).await?;
+    println!("Communicating at {} baud", rate.as_baud());
 
-        file.write_all(&data)?;
+    // Resume dumping process if previously interrupted
+    let offset: u32 = file.seek(SeekFrom::End(0))?.try_into()?;
+    let total: usize = (END - START + 1).try_into()?;
+    let mut buf = [0x00; 0x400];
+
+    for addr in (START + offset..=END).step_by(buf.len()) {
+        let dumped: usize = (addr - START).try_into()?;
+        let len = (total - dumped).min(buf.len());
+        let data = &mut buf[..len];
+
+        dev.interface()
+            .read_memory_slice(addr, data, |done, _| {
+                print!("\rReading memory: {}/{total} bytes", dumped + done);
+                io::stdout().flush().unwrap();
+            })
+            .await?;
+
+        // Each block is written immediately so that an interrupted dump can be resumed
+        file.write_all(data)?;
     }
 
+    println!();
+
     Ok(())
 }
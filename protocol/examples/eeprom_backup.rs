@@ -0,0 +1,84 @@
+use freemdu::backup::{EepromBackup, RestoreStage};
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, Write},
+};
+
+const USAGE: &str = "Usage: eeprom_backup [--port <path>] backup <file> [<hex length>]\n       \
+                     eeprom_backup [--port <path>] restore <file> [--force]";
+
+/// Default number of bytes to back up.
+const DEFAULT_LEN: usize = 0x0800;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let mut args: Vec<_> = env::args().skip(1).collect();
+    let path = match args.iter().position(|arg| arg == "--port") {
+        Some(idx) if idx + 1 < args.len() => args.drain(idx..=idx + 1).nth(1).ok_or(USAGE)?,
+        Some(_) => return Err(USAGE.into()),
+        None => "/dev/ttyACM0".into(),
+    };
+    let mut port = freemdu::serial::open(&path)?;
+    let mut dev = freemdu::device::connect(&mut port).await?;
+
+    // Switch to a higher baud rate if supported by the device
+    let rate = dev.interface().negotiate_fast_mode().await?;
+
+    println!("Communicating at {} baud", rate.as_baud());
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["backup", file] | ["backup", file, _] => {
+            let len = match args.get(2) {
+                Some(len) => usize::from_str_radix(len.trim_start_matches("0x"), 16)?,
+                None => DEFAULT_LEN,
+            };
+            let backup = EepromBackup::read(&mut *dev, len, |done, total| {
+                print!("\rReading EEPROM: {done}/{total} bytes");
+                io::stdout().flush().unwrap();
+            })
+            .await?;
+
+            println!();
+            fs::write(file, backup.to_string())?;
+            println!(
+                "Saved backup of software ID {} with CRC-32 {:08x}",
+                backup.software_id,
+                backup.crc32()
+            );
+        }
+        ["restore", file] | ["restore", file, "--force"] => {
+            let force = args.len() == 3;
+            let backup: EepromBackup = fs::read_to_string(file)?.parse()?;
+            let written = backup
+                .restore(&mut *dev, force, |stage, done, total| {
+                    let stage = match stage {
+                        RestoreStage::Read => "Reading",
+                        RestoreStage::Write => "Writing",
+                        RestoreStage::Verify => "Verifying",
+                    };
+
+                    print!("\r{stage} EEPROM: {done}/{total} bytes");
+                    io::stdout().flush().unwrap();
+                })
+                .await?;
+
+            println!();
+
+            for range in &written {
+                println!("Restored {:04x}-{:04x}", range.start, range.end - 1);
+            }
+
+            println!(
+                "Restored {} changed ranges, verification passed",
+                written.len()
+            );
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
+}
@@ -1,71 +1,118 @@
-use freemdu::{Interface, serial::Port};
-use std::{error::Error, thread, time::Duration};
-use tokio::time;
+use freemdu::{
+    Interface,
+    keysearch::{FullAccessProbe, KeySearch, Status},
+    serial::Delay,
+    time::Timeouts,
+};
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::ErrorKind,
+    thread,
+    time::{Duration, Instant},
+};
 
-const UNLOCK_TIMEOUT: Duration = Duration::from_millis(500);
+const USAGE: &str =
+    "Usage: find_keys [--port <path>] [--progress <file>] [--timeout <ms>] [--halt]";
 const ERROR_RETRY_DELAY: Duration = Duration::from_secs(4);
-const CHECK_TIMEOUT: Duration = Duration::from_millis(100);
-
-async fn find_read_access_key(intf: &mut Interface<Port>) -> Result<u16, Box<dyn Error>> {
-    for i in 0x0000..=0xffff {
-        println!("Trying read access key: {i:04x}");
-
-        while let Err(err) = time::timeout(UNLOCK_TIMEOUT, async {
-            intf.query_software_id().await?;
-            intf.unlock_read_access(i).await
-        })
-        .await
-        {
-            eprintln!("Error: {err}");
-            thread::sleep(ERROR_RETRY_DELAY);
-        }
-
-        // Check if read access was successfully unlocked
-        if let Ok(Ok(_)) = time::timeout(CHECK_TIMEOUT, intf.read_memory::<u8, _>(0x0000)).await {
-            return Ok(i);
-        }
-    }
 
-    Err("Failed to find read access key".into())
+struct Args {
+    port: String,
+    progress: String,
+    timeout: Duration,
+    probe: FullAccessProbe,
 }
 
-async fn find_full_access_key(
-    intf: &mut Interface<Port>,
-    read_key: u16,
-) -> Result<u16, Box<dyn Error>> {
-    for i in 0x0000..=0xffff {
-        println!("Trying read & full access keys: {read_key:04x}, {i:04x}");
-
-        while let Err(err) = time::timeout(UNLOCK_TIMEOUT, async {
-            intf.query_software_id().await?;
-            intf.unlock_read_access(read_key).await?;
-            intf.unlock_full_access(i).await
-        })
-        .await
-        {
-            eprintln!("Error: {err}");
-            thread::sleep(ERROR_RETRY_DELAY);
-        }
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut args = Args {
+        port: "/dev/ttyACM0".into(),
+        progress: "keysearch.txt".into(),
+        timeout: Duration::from_millis(100),
+        probe: FullAccessProbe::EmptyWrite,
+    };
+    let mut iter = env::args().skip(1);
 
-        // Check if full access was successfully unlocked
-        if let Ok(Ok(())) = time::timeout(CHECK_TIMEOUT, intf.halt()).await {
-            return Ok(i);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--port" => args.port = iter.next().ok_or(USAGE)?,
+            "--progress" => args.progress = iter.next().ok_or(USAGE)?,
+            "--timeout" => {
+                args.timeout = Duration::from_millis(iter.next().ok_or(USAGE)?.parse()?);
+            }
+            "--halt" => args.probe = FullAccessProbe::Halt,
+            _ => return Err(USAGE.into()),
         }
     }
 
-    Err("Failed to find full access key".into())
+    Ok(args)
+}
+
+fn print_status(status: &Status) {
+    let eta = status.eta.unwrap_or_default().as_secs();
+
+    println!(
+        "{} ({} remaining, ETA {:02}:{:02}:{:02})",
+        status.progress,
+        status.remaining,
+        eta / 3600,
+        eta / 60 % 60,
+        eta % 60,
+    );
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     env_logger::init();
 
-    let port = freemdu::serial::open("/dev/ttyACM0")?;
+    let args = parse_args()?;
+    let port = freemdu::serial::open(&args.port)?;
     let mut intf = Interface::new(port);
-    let read_key = find_read_access_key(&mut intf).await?;
-    let full_key = find_full_access_key(&mut intf, read_key).await?;
+    let start = Instant::now();
+    let mut search = KeySearch::new()
+        .with_probe(args.probe)
+        .with_clock(move || start.elapsed());
+
+    match fs::read_to_string(&args.progress) {
+        Ok(text) => {
+            search = search.with_progress(text.trim().parse()?);
+            println!("Resuming search from: {}", search.progress());
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    intf.enable_timeouts(
+        Delay,
+        Timeouts {
+            frame: args.timeout,
+            byte: args.timeout,
+        },
+    );
+
+    let keys = loop {
+        let res = search
+            .run(&mut intf, |status| {
+                print_status(status);
+
+                if let Err(err) = fs::write(&args.progress, status.progress.to_string()) {
+                    eprintln!("Failed to save progress: {err}");
+                }
+            })
+            .await;
+
+        match res {
+            Ok(keys) => break keys,
+            Err(freemdu::Error::KeysNotFound) => return Err("Failed to find keys".into()),
+            Err(err) => {
+                // Progress is preserved, the search continues after a short delay
+                eprintln!("Error: {err}");
+                thread::sleep(ERROR_RETRY_DELAY);
+            }
+        }
+    };
 
-    println!("Found keys: {read_key:04x}, {full_key:04x}");
+    println!("Found keys: {:04x}, {:04x}", keys.read, keys.full);
 
     Ok(())
 }
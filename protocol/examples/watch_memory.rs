@@ -0,0 +1,77 @@
+use freemdu::{serial::Delay, watcher::MemoryWatcher};
+use futures_util::StreamExt;
+use std::{
+    env,
+    error::Error,
+    ops::Range,
+    pin::pin,
+    time::{Duration, Instant},
+};
+
+const USAGE: &str = "Usage: watch_memory [--port <path>] [--interval <ms>] [--calibrate <polls>] \
+                     [--ignore <start>-<end>]... <start>-<end>...";
+
+/// Parses an inclusive hexadecimal address range, e.g. `0100-01ff`.
+fn parse_range(arg: &str) -> Result<Range<u32>, Box<dyn Error>> {
+    let (start, end) = arg.split_once('-').ok_or(USAGE)?;
+
+    Ok(u32::from_str_radix(start, 16)?..u32::from_str_radix(end, 16)? + 1)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    env_logger::init();
+
+    let mut path = "/dev/ttyACM0".to_string();
+    let mut interval = Duration::from_secs(1);
+    let mut calibration_polls = 0;
+    let mut ignored = Vec::new();
+    let mut ranges = Vec::new();
+    let mut iter = env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--port" => path = iter.next().ok_or(USAGE)?,
+            "--interval" => interval = Duration::from_millis(iter.next().ok_or(USAGE)?.parse()?),
+            "--calibrate" => calibration_polls = iter.next().ok_or(USAGE)?.parse()?,
+            "--ignore" => ignored.push(parse_range(&iter.next().ok_or(USAGE)?)?),
+            range => ranges.push(parse_range(range)?),
+        }
+    }
+
+    if ranges.is_empty() {
+        return Err(USAGE.into());
+    }
+
+    let watcher = ranges
+        .into_iter()
+        .fold(MemoryWatcher::new(interval), MemoryWatcher::watch);
+    let watcher = ignored.into_iter().fold(
+        watcher.ignore_changing(calibration_polls),
+        MemoryWatcher::ignore,
+    );
+    let mut port = freemdu::serial::open(&path)?;
+    let mut dev = freemdu::device::connect(&mut port).await?;
+
+    println!("Watching memory of software ID {}", dev.software_id());
+
+    let start = Instant::now();
+    let mut changes = pin!(watcher.stream(dev.interface(), Delay, move || start.elapsed()));
+
+    while let Some(change) = changes.next().await {
+        match change {
+            Ok(change) => println!(
+                "{:>10.3} {:08x}: {:02x} -> {:02x} ({:>3} -> {:>3})",
+                change.time.as_secs_f64(),
+                change.addr,
+                change.old,
+                change.new,
+                change.old,
+                change.new,
+            ),
+            Err(err) => eprintln!("Error: {err}"),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,546 @@
+//! EEPROM backups with verified restore.
+//!
+//! An [`EepromBackup`] contains the EEPROM contents of a device along with
+//! information identifying the device. Backups are stored in a line-based text format:
+//!
+//! ```text
+//! # freemdu eeprom backup v1
+//! software_id 2088
+//! model W3241
+//! serial_number 93140239
+//! addressing word
+//! crc32 60c4129b
+//! 0000 00 11 22 33 44 55 66 77 88 99 aa bb cc dd ee ff
+//! 0010 01 23 45 67
+//! ```
+//!
+//! The header is followed by the device's software ID, the optional model
+//! and serial number, the EEPROM addressing mode and the CRC-32 of the data.
+//! Each data line starts with the byte offset of its first byte.
+//! Lines starting with `#` are treated as comments.
+//!
+//! Restoring a backup using [`EepromBackup::restore`] only writes the cells
+//! that differ from the device's current EEPROM and verifies the result.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{EepromAddressing, backup::EepromBackup, simulator::Simulator};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), core::convert::Infallible> {
+//! let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+//!     .with_ram(0x0000, [0x00; 0x0400])
+//!     .with_eeprom([0x12; 0x10], EepromAddressing::Word);
+//! let mut dev = freemdu::device::connect(&mut sim).await?;
+//! let backup = EepromBackup::read(&mut *dev, 0x10, |_, _| {}).await?;
+//! let text = backup.to_string();
+//!
+//! assert_eq!(text.parse(), Ok(backup));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    EepromAddressing,
+    device::{Device, Error, Result, Value},
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Formatter},
+    ops::Range,
+    str::FromStr,
+};
+use embedded_io_async::{Read, Write};
+use log::debug;
+
+/// First line of every backup file.
+const HEADER: &str = "# freemdu eeprom backup v1";
+
+/// Number of bytes per data line.
+const BYTES_PER_LINE: usize = 16;
+
+/// Stage of a restore operation, reported to the progress callback.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum RestoreStage {
+    /// Reading the current EEPROM contents.
+    Read,
+    /// Writing the changed cells.
+    Write,
+    /// Reading back the EEPROM contents for verification.
+    Verify,
+}
+
+/// Backup of a device's EEPROM.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct EepromBackup {
+    /// Software ID of the device.
+    pub software_id: u16,
+    /// Model number of the device, if available.
+    pub model: Option<String>,
+    /// Serial number of the device, if available.
+    pub serial_number: Option<String>,
+    /// EEPROM addressing mode used to read the data.
+    pub addressing: EepromAddressing,
+    /// EEPROM contents, starting at address zero.
+    pub data: Vec<u8>,
+}
+
+impl EepromBackup {
+    /// Reads the first `len` bytes of the device's EEPROM.
+    ///
+    /// The model and serial number are queried if the device supports
+    /// the `model_number` and `serial_number` properties.
+    /// The `progress` callback is invoked with the number of bytes
+    /// read so far and the total number of bytes after every chunk.
+    pub async fn read<P: Read + Write, D: Device<P> + ?Sized>(
+        dev: &mut D,
+        len: usize,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<Self, P::Error> {
+        let model = query_identity(dev, "model_number").await?;
+        let serial_number = query_identity(dev, "serial_number").await?;
+        let data = dev
+            .interface()
+            .read_eeprom_vec(0x0000, len, progress)
+            .await?;
+
+        Ok(Self {
+            software_id: dev.software_id(),
+            model,
+            serial_number,
+            addressing: dev.interface().eeprom_addressing(),
+            data,
+        })
+    }
+
+    /// Returns the CRC-32 checksum of the data.
+    #[must_use]
+    pub fn crc32(&self) -> u32 {
+        compute_crc32(&self.data)
+    }
+
+    /// Restores the backup onto the device.
+    ///
+    /// Reads the current EEPROM contents, writes only the cells that differ
+    /// and reads everything back to verify the result.
+    /// The backup's addressing mode is used for all EEPROM accesses.
+    /// The `progress` callback is invoked with the current stage, the number
+    /// of bytes transferred so far and the total number of bytes.
+    ///
+    /// Returns the byte ranges that have been written.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::UnknownSoftwareId`] if the device's software ID differs from the backup,
+    ///   unless `force` is set.
+    /// - [`Error::VerificationFailed`] if the EEPROM contents do not match the backup after writing.
+    pub async fn restore<P: Read + Write, D: Device<P> + ?Sized>(
+        &self,
+        dev: &mut D,
+        force: bool,
+        mut progress: impl FnMut(RestoreStage, usize, usize),
+    ) -> Result<Vec<Range<usize>>, P::Error> {
+        let software_id = dev.software_id();
+
+        if software_id != self.software_id && !force {
+            return Err(Error::UnknownSoftwareId(software_id));
+        }
+
+        let intf = dev.interface();
+        let addressing = intf.eeprom_addressing();
+
+        intf.set_eeprom_addressing(self.addressing);
+
+        let res = async {
+            let current = intf
+                .read_eeprom_vec(0x0000, self.data.len(), |done, total| {
+                    progress(RestoreStage::Read, done, total);
+                })
+                .await?;
+            let changed = changed_ranges(&current, &self.data, self.addressing);
+            let total = changed.iter().map(ExactSizeIterator::len).sum();
+            let mut done = 0;
+
+            for range in &changed {
+                debug!("Restoring EEPROM range {range:04x?}");
+
+                intf.write_eeprom_slice(
+                    eeprom_addr(range.start, self.addressing)?,
+                    &self.data[range.clone()],
+                    |n, _| progress(RestoreStage::Write, done + n, total),
+                )
+                .await?;
+                done += range.len();
+            }
+
+            let written = intf
+                .read_eeprom_vec(0x0000, self.data.len(), |done, total| {
+                    progress(RestoreStage::Verify, done, total);
+                })
+                .await?;
+
+            if written != self.data {
+                return Err(Error::VerificationFailed);
+            }
+
+            Ok(changed)
+        }
+        .await;
+
+        intf.set_eeprom_addressing(addressing);
+
+        res
+    }
+}
+
+impl Display for EepromBackup {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "software_id {}", self.software_id)?;
+
+        if let Some(model) = &self.model {
+            writeln!(f, "model {model}")?;
+        }
+
+        if let Some(serial_number) = &self.serial_number {
+            writeln!(f, "serial_number {serial_number}")?;
+        }
+
+        let addressing = match self.addressing {
+            EepromAddressing::Byte => "byte",
+            EepromAddressing::Word => "word",
+        };
+
+        writeln!(f, "addressing {addressing}")?;
+        writeln!(f, "crc32 {:08x}", self.crc32())?;
+
+        for (idx, line) in self.data.chunks(BYTES_PER_LINE).enumerate() {
+            write!(f, "{:04x}", idx * BYTES_PER_LINE)?;
+
+            for byte in line {
+                write!(f, " {byte:02x}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when parsing an invalid backup.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ParseBackupError {
+    /// The line with the specified number, starting at 1, is invalid or missing.
+    InvalidLine(usize),
+    /// The data does not match the stored CRC-32 checksum.
+    IncorrectChecksum,
+}
+
+impl Display for ParseBackupError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::InvalidLine(line) => write!(f, "invalid backup in line {line}"),
+            Self::IncorrectChecksum => write!(f, "incorrect backup checksum"),
+        }
+    }
+}
+
+impl core::error::Error for ParseBackupError {}
+
+impl FromStr for EepromBackup {
+    type Err = ParseBackupError;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let mut lines = s.lines().zip(1..);
+
+        if lines.next().map(|(line, _)| line.trim()) != Some(HEADER) {
+            return Err(ParseBackupError::InvalidLine(1));
+        }
+
+        let mut software_id = None;
+        let mut model = None;
+        let mut serial_number = None;
+        let mut addressing = None;
+        let mut crc32 = None;
+        let mut data = Vec::new();
+        let mut last = 1;
+
+        for (line, num) in lines {
+            let line = line.trim();
+            let invalid = ParseBackupError::InvalidLine(num);
+
+            last = num;
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, val) = line.split_once(' ').ok_or(invalid)?;
+            let val = val.trim();
+
+            match key {
+                "software_id" => software_id = Some(val.parse().map_err(|_| invalid)?),
+                "model" => model = Some(val.to_string()),
+                "serial_number" => serial_number = Some(val.to_string()),
+                "addressing" => {
+                    addressing = Some(match val {
+                        "byte" => EepromAddressing::Byte,
+                        "word" => EepromAddressing::Word,
+                        _ => return Err(invalid),
+                    });
+                }
+                "crc32" => crc32 = Some(u32::from_str_radix(val, 16).map_err(|_| invalid)?),
+                offset => {
+                    if usize::from_str_radix(offset, 16) != Ok(data.len()) {
+                        return Err(invalid);
+                    }
+
+                    for byte in val.split_ascii_whitespace() {
+                        data.push(u8::from_str_radix(byte, 16).map_err(|_| invalid)?);
+                    }
+                }
+            }
+        }
+
+        let missing = ParseBackupError::InvalidLine(last + 1);
+        let backup = Self {
+            software_id: software_id.ok_or(missing)?,
+            model,
+            serial_number,
+            addressing: addressing.ok_or(missing)?,
+            data,
+        };
+
+        if crc32.ok_or(missing)? != backup.crc32() {
+            return Err(ParseBackupError::IncorrectChecksum);
+        }
+
+        Ok(backup)
+    }
+}
+
+/// Queries an identifying string property, if the device supports it.
+async fn query_identity<P: Read + Write, D: Device<P> + ?Sized>(
+    dev: &mut D,
+    id: &str,
+) -> Result<Option<String>, P::Error> {
+    let Some(prop) = dev.properties().iter().find(|prop| prop.id == id) else {
+        return Ok(None);
+    };
+
+    // Identity data is optional, so only communication errors are fatal
+    match dev.query_property(prop).await {
+        Ok(Value::String(val)) => Ok(Some(val)),
+        Ok(Value::Number(val)) => Ok(Some(val.to_string())),
+        Err(err @ Error::Protocol(_)) => Err(err),
+        Ok(_) | Err(_) => Ok(None),
+    }
+}
+
+/// Returns the byte ranges of all cells that differ between the two buffers.
+///
+/// Word-addressed EEPROMs are compared in 16-bit cells, so that every range
+/// starts and ends at a word boundary.
+fn changed_ranges(
+    current: &[u8],
+    target: &[u8],
+    addressing: EepromAddressing,
+) -> Vec<Range<usize>> {
+    let cell_len = match addressing {
+        EepromAddressing::Byte => 1,
+        EepromAddressing::Word => 2,
+    };
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+
+    for (idx, (old, new)) in current
+        .chunks(cell_len)
+        .zip(target.chunks(cell_len))
+        .enumerate()
+    {
+        if old == new {
+            continue;
+        }
+
+        let start = idx * cell_len;
+        let end = start + new.len();
+
+        match ranges.last_mut() {
+            Some(range) if range.end == start => range.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+
+    ranges
+}
+
+/// Converts a byte offset into an EEPROM address.
+fn eeprom_addr<E>(offset: usize, addressing: EepromAddressing) -> Result<u16, E> {
+    let addr = match addressing {
+        EepromAddressing::Byte => offset,
+        EepromAddressing::Word => offset / 2,
+    };
+
+    addr.try_into().map_err(|_| Error::InvalidArgument)
+}
+
+/// Computes the CRC-32 (IEEE 802.3) checksum of the provided data.
+fn compute_crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in data {
+        crc ^= u32::from(byte);
+
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{device, simulator::Simulator, tests::init_logger};
+    use alloc::vec;
+    use core::convert::Infallible;
+
+    fn washing_machine(eeprom: impl Into<Vec<u8>>) -> Simulator {
+        Simulator::new(629, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_eeprom(eeprom, EepromAddressing::Word)
+    }
+
+    #[test]
+    fn crc32() {
+        assert_eq!(
+            compute_crc32(b"123456789"),
+            0xcbf4_3926,
+            "checksum should be correct"
+        );
+    }
+
+    #[test]
+    fn format_and_parse() {
+        let backup = EepromBackup {
+            software_id: 2088,
+            model: Some("W3241".to_string()),
+            serial_number: Some("93140239".to_string()),
+            addressing: EepromAddressing::Word,
+            data: (0x00..0x14).collect(),
+        };
+        let text = backup.to_string();
+
+        assert_eq!(
+            text,
+            "# freemdu eeprom backup v1\n\
+             software_id 2088\n\
+             model W3241\n\
+             serial_number 93140239\n\
+             addressing word\n\
+             crc32 3bddffa4\n\
+             0000 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n\
+             0010 10 11 12 13\n",
+            "text should be correct"
+        );
+        assert_eq!(text.parse(), Ok(backup), "backup should be preserved");
+        assert_eq!(
+            text.replace("0010 10", "0010 11").parse::<EepromBackup>(),
+            Err(ParseBackupError::IncorrectChecksum),
+            "result should be checksum error"
+        );
+        assert_eq!(
+            text.replace("0010 10", "0012 10").parse::<EepromBackup>(),
+            Err(ParseBackupError::InvalidLine(8)),
+            "result should be parse error"
+        );
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore() -> device::Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = washing_machine([0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77]);
+        let mut backup = {
+            let mut dev = device::connect(&mut sim).await?;
+
+            EepromBackup::read(&mut *dev, 8, |_, _| {}).await?
+        };
+
+        assert_eq!(backup.software_id, 629, "software ID should be correct");
+        assert_eq!(
+            backup.data,
+            [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77],
+            "data should be correct"
+        );
+
+        backup.data[0] = 0xff;
+        backup.data[4] = 0xee;
+
+        let mut stages = Vec::new();
+
+        {
+            let mut dev = device::connect(&mut sim).await?;
+            let written = backup
+                .restore(&mut *dev, false, |stage, done, total| {
+                    stages.push((stage, done, total));
+                })
+                .await?;
+
+            assert_eq!(
+                written,
+                [0x00..0x02, 0x04..0x06],
+                "written ranges should be correct"
+            );
+        }
+
+        assert_eq!(
+            sim.eeprom(),
+            [0xff, 0x11, 0x22, 0x33, 0xee, 0x55, 0x66, 0x77],
+            "EEPROM contents should be correct"
+        );
+        assert_eq!(
+            stages.last(),
+            Some(&(RestoreStage::Verify, 8, 8)),
+            "progress should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restore_different_software_id() -> device::Result<(), Infallible> {
+        init_logger();
+
+        let backup = EepromBackup {
+            software_id: 2088,
+            model: None,
+            serial_number: None,
+            addressing: EepromAddressing::Word,
+            data: vec![0x12; 4],
+        };
+        let mut sim = washing_machine([0x00; 4]);
+        let mut dev = device::connect(&mut sim).await?;
+
+        assert_eq!(
+            backup.restore(&mut *dev, false, |_, _, _| {}).await,
+            Err(Error::UnknownSoftwareId(629)),
+            "restore should fail"
+        );
+
+        let written = backup.restore(&mut *dev, true, |_, _, _| {}).await?;
+
+        assert_eq!(
+            written.iter().map(ExactSizeIterator::len).sum::<usize>(),
+            4,
+            "written length should be correct"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,602 @@
+//! Recording and replaying of diagnostic sessions.
+//!
+//! A [`CapturePort`] wraps any port implementing [`Read`] and [`Write`]
+//! and records every transferred byte in both directions, together with a timestamp.
+//! The resulting [`Capture`] can be saved in a simple text format
+//! and later fed back to an [`Interface`](crate::Interface) or
+//! [`device::connect`](crate::device::connect) using a [`ReplayPort`].
+//! This allows reproducing problems with a specific appliance without the hardware.
+//!
+//! # File format
+//!
+//! Captures are stored as UTF-8 text. The first line must be the header `# freemdu capture v1`.
+//! Every following line contains a single record consisting of three fields separated by spaces:
+//!
+//! 1. The time since the start of the capture in seconds, with six decimal places.
+//! 2. The direction of the transfer: `tx` for data written to the device,
+//!    `rx` for data read from the device.
+//! 3. The transferred bytes as lowercase hexadecimal pairs separated by spaces.
+//!
+//! Empty lines and lines starting with `#` are ignored.
+//!
+//! ```text
+//! # freemdu capture v1
+//! 0.000000 tx 11 00 00 02
+//! 0.000112 tx 13
+//! 0.004817 rx 00
+//! 0.004931 rx 75 02 77
+//! 0.016244 tx 00
+//! ```
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{
+//!     Interface,
+//!     capture::{Capture, CapturePort, ReplayPort},
+//!     simulator::Simulator,
+//! };
+//! use std::time::Instant;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::Result<(), freemdu::capture::Divergence> {
+//! let start = Instant::now();
+//! let mut port = CapturePort::new(Simulator::new(629, 0x43ea, 0x1f02), move || start.elapsed());
+//!
+//! Interface::new(&mut port).query_software_id().await.unwrap();
+//!
+//! let text = port.capture().to_string();
+//! let capture: Capture = text.parse().unwrap();
+//! let mut replay = ReplayPort::new(capture);
+//!
+//! assert_eq!(Interface::new(&mut replay).query_software_id().await?, 629);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::time::Clock;
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::{Display, Formatter},
+    future,
+    str::FromStr,
+    time::Duration,
+};
+use embedded_io_async::{ErrorKind, ErrorType, Read, Write};
+use log::debug;
+
+/// Header line identifying the capture file format.
+const HEADER: &str = "# freemdu capture v1";
+
+/// Direction of a recorded transfer, as seen from the client.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Direction {
+    /// Data written to the device.
+    Tx,
+    /// Data read from the device.
+    Rx,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::Tx => write!(f, "tx"),
+            Self::Rx => write!(f, "rx"),
+        }
+    }
+}
+
+/// Single recorded transfer.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Record {
+    /// Time since the start of the capture.
+    pub time: Duration,
+    /// Direction of the transfer.
+    pub direction: Direction,
+    /// Transferred bytes.
+    pub data: Vec<u8>,
+}
+
+/// Recorded diagnostic session.
+///
+/// Implements [`Display`] and [`FromStr`] using the format described in the [module](self) documentation.
+#[derive(PartialEq, Eq, Clone, Default, Debug)]
+pub struct Capture {
+    records: Vec<Record>,
+}
+
+impl Capture {
+    /// Constructs an empty capture.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the recorded transfers.
+    #[must_use]
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Appends a transfer to the capture.
+    pub fn push(&mut self, record: Record) {
+        self.records.push(record);
+    }
+}
+
+impl Display for Capture {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        writeln!(f, "{HEADER}")?;
+
+        for record in &self.records {
+            write!(
+                f,
+                "{}.{:06} {}",
+                record.time.as_secs(),
+                record.time.subsec_micros(),
+                record.direction
+            )?;
+
+            for byte in &record.data {
+                write!(f, " {byte:02x}")?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned when parsing an invalid capture.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct ParseCaptureError {
+    /// Line number of the invalid line, starting at 1.
+    pub line: usize,
+}
+
+impl Display for ParseCaptureError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "invalid capture in line {}", self.line)
+    }
+}
+
+impl core::error::Error for ParseCaptureError {}
+
+impl FromStr for Capture {
+    type Err = ParseCaptureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().zip(1..);
+
+        if lines.next().map(|(line, _)| line.trim()) != Some(HEADER) {
+            return Err(ParseCaptureError { line: 1 });
+        }
+
+        let mut capture = Self::new();
+
+        for (line, num) in lines {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let record = parse_record(line).ok_or(ParseCaptureError { line: num })?;
+
+            capture.push(record);
+        }
+
+        Ok(capture)
+    }
+}
+
+/// Parses a single record line.
+fn parse_record(line: &str) -> Option<Record> {
+    let mut fields = line.split_ascii_whitespace();
+    let (secs, micros) = fields.next()?.split_once('.')?;
+
+    if micros.len() != 6 {
+        return None;
+    }
+
+    let time =
+        Duration::from_secs(secs.parse().ok()?) + Duration::from_micros(micros.parse().ok()?);
+    let direction = match fields.next()? {
+        "tx" => Direction::Tx,
+        "rx" => Direction::Rx,
+        _ => return None,
+    };
+    let data = fields
+        .map(|byte| {
+            (byte.len() == 2)
+                .then(|| u8::from_str_radix(byte, 16).ok())
+                .flatten()
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(Record {
+        time,
+        direction,
+        data,
+    })
+}
+
+/// Port adapter that records all transferred data.
+///
+/// Timestamps are taken from the provided [`Clock`] and stored relative to
+/// the time the port was created.
+#[derive(Debug)]
+pub struct CapturePort<P> {
+    port: P,
+    clock: Box<dyn Clock>,
+    start: Duration,
+    capture: Capture,
+}
+
+impl<P> CapturePort<P> {
+    /// Wraps the specified port, recording all transfers.
+    pub fn new(port: P, clock: impl Clock + 'static) -> Self {
+        Self {
+            port,
+            start: clock.now(),
+            clock: Box::new(clock),
+            capture: Capture::new(),
+        }
+    }
+
+    /// Returns the data recorded so far.
+    pub fn capture(&self) -> &Capture {
+        &self.capture
+    }
+
+    /// Returns the wrapped port and the recorded data.
+    pub fn into_parts(self) -> (P, Capture) {
+        (self.port, self.capture)
+    }
+
+    /// Records a transfer in the specified direction.
+    fn record(&mut self, direction: Direction, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        self.capture.push(Record {
+            time: self.clock.now().saturating_sub(self.start),
+            direction,
+            data: data.to_vec(),
+        });
+    }
+}
+
+impl<P: ErrorType> ErrorType for CapturePort<P> {
+    type Error = P::Error;
+}
+
+impl<P: Read> Read for CapturePort<P> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = self.port.read(buf).await?;
+
+        self.record(Direction::Rx, &buf[..len]);
+
+        Ok(len)
+    }
+}
+
+impl<P: Write> Write for CapturePort<P> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let len = self.port.write(buf).await?;
+
+        self.record(Direction::Tx, &buf[..len]);
+
+        Ok(len)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.port.flush().await
+    }
+}
+
+/// Difference between the client's behavior and a replayed capture.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Divergence {
+    /// The client wrote data that does not match the capture.
+    UnexpectedWrite {
+        /// Index of the record in the capture.
+        record: usize,
+        /// Offset of the byte within the record.
+        offset: usize,
+        /// Byte expected by the capture, or `None` if data was expected to be read instead.
+        expected: Option<u8>,
+        /// Byte written by the client.
+        actual: u8,
+    },
+    /// The client read data while the capture expected data to be written.
+    UnexpectedRead {
+        /// Index of the record in the capture.
+        record: usize,
+    },
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedWrite {
+                record,
+                offset,
+                expected: Some(expected),
+                actual,
+            } => write!(
+                f,
+                "record {record}, offset {offset}: expected write of {expected:02x}, got {actual:02x}"
+            ),
+            Self::UnexpectedWrite {
+                record,
+                offset,
+                expected: None,
+                actual,
+            } => write!(
+                f,
+                "record {record}, offset {offset}: expected read, got write of {actual:02x}"
+            ),
+            Self::UnexpectedRead { record } => {
+                write!(f, "record {record}: expected write, got read")
+            }
+        }
+    }
+}
+
+impl core::error::Error for Divergence {}
+
+impl embedded_io_async::Error for Divergence {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::InvalidData
+    }
+}
+
+/// Port that replays a recorded capture.
+///
+/// Data read from the port is taken from the capture's `rx` records.
+/// Data written to the port is compared against the capture's `tx` records.
+/// As soon as the client diverges from the capture, all further operations
+/// fail with the corresponding [`Divergence`], which is also available via [`ReplayPort::divergence`].
+/// Reading past the end of the capture returns an end-of-file condition.
+#[derive(Debug)]
+pub struct ReplayPort {
+    capture: Capture,
+    record: usize,
+    offset: usize,
+    divergence: Option<Divergence>,
+}
+
+impl ReplayPort {
+    /// Constructs a new port replaying the specified capture.
+    #[must_use]
+    pub fn new(capture: Capture) -> Self {
+        Self {
+            capture,
+            record: 0,
+            offset: 0,
+            divergence: None,
+        }
+    }
+
+    /// Returns the first divergence from the capture, if any.
+    #[must_use]
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence
+    }
+
+    /// Returns whether the entire capture has been replayed.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.capture
+            .records
+            .iter()
+            .skip(self.record)
+            .map(|record| record.data.len())
+            .sum::<usize>()
+            <= self.offset
+    }
+
+    /// Returns the current record if it has the specified direction.
+    fn current(&mut self, direction: Direction) -> Option<&[u8]> {
+        // Skip exhausted records
+        while self
+            .capture
+            .records
+            .get(self.record)
+            .is_some_and(|record| self.offset >= record.data.len())
+        {
+            self.record += 1;
+            self.offset = 0;
+        }
+
+        self.capture
+            .records
+            .get(self.record)
+            .filter(|record| record.direction == direction)
+            .map(|record| &record.data[self.offset..])
+    }
+
+    /// Records a divergence from the capture.
+    fn diverge(&mut self, divergence: Divergence) -> Divergence {
+        debug!("Replay diverged from capture: {divergence}");
+
+        *self.divergence.get_or_insert(divergence)
+    }
+
+    fn read_sync(&mut self, buf: &mut [u8]) -> Result<usize, Divergence> {
+        if let Some(divergence) = self.divergence {
+            return Err(divergence);
+        }
+
+        // End of capture is reported as end of file
+        if buf.is_empty() || self.is_finished() {
+            return Ok(0);
+        }
+
+        let Some(data) = self.current(Direction::Rx) else {
+            return Err(self.diverge(Divergence::UnexpectedRead {
+                record: self.record,
+            }));
+        };
+        let len = buf.len().min(data.len());
+
+        buf[..len].copy_from_slice(&data[..len]);
+        self.offset += len;
+
+        Ok(len)
+    }
+
+    fn write_sync(&mut self, buf: &[u8]) -> Result<usize, Divergence> {
+        if let Some(divergence) = self.divergence {
+            return Err(divergence);
+        }
+
+        for &byte in buf {
+            let expected = self.current(Direction::Tx).map(|data| data[0]);
+
+            if expected != Some(byte) {
+                return Err(self.diverge(Divergence::UnexpectedWrite {
+                    record: self.record,
+                    offset: self.offset,
+                    expected,
+                    actual: byte,
+                }));
+            }
+
+            self.offset += 1;
+        }
+
+        Ok(buf.len())
+    }
+}
+
+impl ErrorType for ReplayPort {
+    type Error = Divergence;
+}
+
+impl Read for ReplayPort {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>> {
+        future::ready(self.read_sync(buf))
+    }
+}
+
+impl Write for ReplayPort {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = Result<usize, Self::Error>> {
+        future::ready(self.write_sync(buf))
+    }
+
+    fn flush(&mut self) -> impl Future<Output = Result<(), Self::Error>> {
+        future::ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Error, Interface,
+        device::{self, Result},
+        simulator::Simulator,
+        tests::init_logger,
+    };
+    use alloc::string::ToString;
+
+    async fn read_data<P: Read + Write>(port: P) -> Result<[u8; 16], P::Error> {
+        let mut dev = device::connect(port).await?;
+
+        Ok(dev.interface().read_memory(0x0010).await?)
+    }
+
+    #[tokio::test]
+    async fn capture_and_replay() -> Result<(), Divergence> {
+        init_logger();
+
+        let data: Vec<_> = (0..=u8::MAX).collect();
+        let sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, data);
+        let mut port = CapturePort::new(sim, || Duration::from_millis(1500));
+        let expected = read_data(&mut port).await.unwrap();
+        let text = port.capture().to_string();
+
+        assert!(
+            text.starts_with("# freemdu capture v1\n0.000000 tx 11 00 00 02\n0.000000 tx 13\n"),
+            "capture text should be correct"
+        );
+
+        let capture = text.parse().unwrap();
+
+        assert_eq!(&capture, port.capture(), "parsed capture should be correct");
+
+        let mut replay = ReplayPort::new(capture);
+        let data = read_data(&mut replay).await?;
+
+        assert_eq!(data, expected, "memory data should be correct");
+        assert!(replay.is_finished(), "replay should be finished");
+        assert_eq!(replay.divergence(), None, "replay should not diverge");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replay_divergence() -> Result<(), Divergence> {
+        init_logger();
+
+        let capture = "# freemdu capture v1\n\
+                       0.000000 tx 11 00 00 02 13\n\
+                       0.004817 rx 00\n\
+                       0.004931 rx 75 02 77\n\
+                       0.016244 tx 00\n"
+            .parse()
+            .unwrap();
+        let mut replay = ReplayPort::new(capture);
+        let mut intf = Interface::new(&mut replay);
+
+        assert_eq!(
+            intf.query_software_id().await?,
+            629,
+            "software ID should be correct"
+        );
+
+        let res = intf.lock().await;
+        let divergence = Divergence::UnexpectedWrite {
+            record: 4,
+            offset: 0,
+            expected: None,
+            actual: 0x10,
+        };
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::Io(divergence),
+            "result should be divergence error"
+        );
+        assert_eq!(
+            replay.divergence(),
+            Some(divergence),
+            "divergence should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_capture_error() {
+        let res = "0.000000 tx 11".parse::<Capture>();
+
+        assert_eq!(
+            res.unwrap_err(),
+            ParseCaptureError { line: 1 },
+            "result should be parse error"
+        );
+
+        let res = "# freemdu capture v1\n\n0.000000 tx 11\n0.1 rx 00".parse::<Capture>();
+
+        assert_eq!(
+            res.unwrap_err(),
+            ParseCaptureError { line: 4 },
+            "result should be parse error"
+        );
+    }
+}
@@ -7,7 +7,52 @@
 //!
 //! Use the [`connect`] function to automatically select the correct device
 //! implementation based on the devices's software ID.
+//!
+//! External crates can add support for further devices by implementing
+//! the [`Device`] and [`DeviceFactory`] traits and registering the factory
+//! in a [`DeviceRegistry`].
+//!
+//! # Serialization
+//!
+//! If the `serde` feature is enabled, the device metadata types implement
+//! `Serialize`. [`Value`], [`Date`], [`Fault`], [`FaultInfo`], [`DeviceKind`], [`PropertyKind`]
+//! and [`ActionKind`] also implement `Deserialize`. [`Property`], [`Action`] and
+//! [`ActionParameters`] refer to static strings provided by the device implementation
+//! and can only be serialized. For the same reason, deserializing [`Value::Enum`]
+//! and [`Value::Flags`] fails, as their names refer to the device's static tables.
+//!
+//! The JSON representation is stable. Enumeration variants and field names are
+//! written in `snake_case`. A [`Value`] is an object with a `type` and a `value` field,
+//! followed by fields specific to the type:
+//!
+//! | Variant                 | Representation                                                |
+//! |-------------------------|---------------------------------------------------------------|
+//! | [`Value::Bool`]         | `{"type": "bool", "value": true}`                             |
+//! | [`Value::Number`]       | `{"type": "number", "value": 42}`                             |
+//! | [`Value::Integer`]      | `{"type": "integer", "value": -42}`                           |
+//! | [`Value::Decimal`]      | `{"type": "decimal", "value": -425, "scale": 1}`              |
+//! | [`Value::Sensor`]       | `{"type": "sensor", "value": 30, "target": 50}`               |
+//! | [`Value::String`]       | `{"type": "string", "value": "W1234"}`                        |
+//! | [`Value::Duration`]     | `{"type": "duration", "value": {"secs": 3600, "nanos": 500000000}}` |
+//! | [`Value::Date`]         | `{"type": "date", "value": {"year": 2024, "month": 1, "day": 31}}` |
+//! | [`Value::Fault`]        | `{"type": "fault", "value": {"status": "stored", "info": null}}` |
+//! | [`Value::Enum`]         | `{"type": "enum", "value": "MainWash", "variants": ["Idle", "MainWash"]}` |
+//! | [`Value::Flags`]        | `{"type": "flags", "value": ["Door"], "all": ["Door", "Float"]}` |
+//!
+//! [`Fault::Ok`] has no `info` field and is written as `{"status": "ok"}`.
+//! Use [`PropertyValue`] to serialize a value together with its property:
+//!
+//! ```json
+//! {"kind": "io", "id": "water_level", "name": "Water Level", "unit": "mm",
+//!  "value": {"type": "sensor", "value": 30, "target": 50}}
+//! ```
 
+pub mod cycles;
+#[cfg(feature = "definitions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "definitions")))]
+pub mod definition;
+pub mod faults;
+pub mod generic;
 pub mod id2088;
 pub mod id218;
 pub mod id2895;
@@ -19,14 +64,22 @@ pub mod id517;
 pub mod id605;
 pub mod id629;
 pub(super) mod utils;
+pub mod watch;
 
 use crate::{Error as ProtocolError, Interface, Read, Write};
-use alloc::{boxed::Box, string::String};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use bitflags::{Flag, Flags};
 use core::{
     fmt::{Display, Formatter},
+    future::Future,
     num::TryFromIntError,
+    ops::Range,
+    pin::Pin,
     time::Duration,
 };
+use embedded_io_async::ErrorType;
+use generic::{GenericConfig, GenericDevice};
+use strum::VariantNames;
 
 /// A specialized [`Result`] type for [`Device`] operations.
 ///
@@ -53,6 +106,12 @@ pub enum Error<E> {
     UnknownProperty,
     /// An unrecognized device action was requested.
     UnknownAction,
+    /// Data read back from the device does not match the written data.
+    VerificationFailed,
+    /// The device was connected in read-only mode, see [`ConnectOptions::with_read_only`].
+    ReadOnly,
+    /// The property or data is unavailable, e.g. in the current connection mode.
+    Unavailable,
     /// Generic diagnostic protocol error.
     Protocol(ProtocolError<E>),
 }
@@ -66,6 +125,9 @@ impl<E: core::error::Error> Display for Error<E> {
             Self::UnexpectedMemoryValue => write!(f, "unexpected memory value"),
             Self::UnknownProperty => write!(f, "unknown property"),
             Self::UnknownAction => write!(f, "unknown action"),
+            Self::VerificationFailed => write!(f, "verification failed"),
+            Self::ReadOnly => write!(f, "device is read-only"),
+            Self::Unavailable => write!(f, "property unavailable"),
             Self::Protocol(err) => write!(f, "protocol error: {err}"),
         }
     }
@@ -75,7 +137,10 @@ impl<E: core::error::Error> core::error::Error for Error<E> {}
 
 impl<E> From<ProtocolError<E>> for Error<E> {
     fn from(err: ProtocolError<E>) -> Self {
-        Self::Protocol(err)
+        match err {
+            ProtocolError::ReadOnly => Self::ReadOnly,
+            err => Self::Protocol(err),
+        }
     }
 }
 
@@ -104,6 +169,8 @@ impl<E> From<strum::ParseError> for Error<E> {
 /// This enum is marked `#[non_exhaustive]` to allow for future variants.
 #[non_exhaustive]
 #[derive(strum::Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 #[strum(serialize_all = "title_case")]
 pub enum DeviceKind {
     /// Washing machine.
@@ -118,10 +185,14 @@ pub enum DeviceKind {
     Glasswasher,
     /// Coffee machine.
     CoffeeMachine,
+    /// Device of unknown kind, e.g. a [`generic::GenericDevice`].
+    Unknown,
 }
 
 /// Device property kind.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum PropertyKind {
     /// General properties, e.g. model number.
     General,
@@ -137,6 +208,7 @@ pub enum PropertyKind {
 ///
 /// Properties can be queried using [`Device::query_property`].
 #[derive(PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Property {
     /// Property kind.
     pub kind: PropertyKind,
@@ -150,6 +222,8 @@ pub struct Property {
 
 /// Device action kind.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ActionKind {
     /// Operation action, e.g. setting the program options.
     Operation,
@@ -162,6 +236,11 @@ pub enum ActionKind {
 /// Each variant specifies which kind of [`Value`] must be supplied
 /// when invoking [`Device::trigger_action`].
 #[derive(PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "type", content = "options", rename_all = "snake_case")
+)]
 pub enum ActionParameters {
     /// Action accepts a single value chosen from an enumeration.
     ///
@@ -177,6 +256,7 @@ pub enum ActionParameters {
 ///
 /// Triggered via [`Device::trigger_action`].
 #[derive(PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Action {
     /// Action kind.
     pub kind: ActionKind,
@@ -192,7 +272,7 @@ pub struct Action {
 ///
 /// Returned by [`Device::query_property`] or passed to [`Device::trigger_action`].
 /// The type depends on the queried property or triggered action.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
 pub enum Value {
     /// Boolean value.
     Bool(bool),
@@ -208,10 +288,59 @@ pub enum Value {
     Date(Date),
     /// Fault value.
     Fault(Fault),
+    /// Value chosen from an enumeration.
+    Enum {
+        /// Name of the current value.
+        value: &'static str,
+        /// Names of all possible values.
+        variants: &'static [&'static str],
+    },
+    /// Combination of flags.
+    Flags {
+        /// Names of the set flags.
+        set: Vec<&'static str>,
+        /// Names of all possible flags.
+        all: Vec<&'static str>,
+    },
+    /// Signed integer value.
+    Integer(i32),
+    /// Fixed-point decimal value, representing `value / 10^scale`.
+    Decimal {
+        /// Unscaled value.
+        value: i32,
+        /// Number of decimal places.
+        scale: u8,
+    },
+}
+
+impl Value {
+    /// Constructs an enumeration value from a variant of an enum.
+    #[must_use]
+    pub fn from_enum<T: VariantNames>(val: T) -> Self
+    where
+        &'static str: From<T>,
+    {
+        Self::Enum {
+            value: val.into(),
+            variants: T::VARIANTS,
+        }
+    }
+
+    /// Constructs a flags value from a set of flags.
+    ///
+    /// Bits without a name are ignored.
+    #[must_use]
+    pub fn from_flags<T: Flags + Copy>(flags: T) -> Self {
+        Self::Flags {
+            set: flags.iter_names().map(|(name, _)| name).collect(),
+            all: T::FLAGS.iter().map(Flag::name).collect(),
+        }
+    }
 }
 
 /// A simple date, consisting of year, month and day.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Date {
     /// Year value.
     pub year: u16,
@@ -230,7 +359,8 @@ impl Date {
 }
 
 /// Additional information about a fault.
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FaultInfo {
     /// Last time of occurrence.
     pub operating_hours: u32,
@@ -241,7 +371,12 @@ pub struct FaultInfo {
 /// The status of a device fault.
 ///
 /// Some devices provide additional metadata for active or stored faults using [`FaultInfo`].
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "status", content = "info", rename_all = "snake_case")
+)]
 pub enum Fault {
     /// Fault is not asserted.
     Ok,
@@ -279,6 +414,24 @@ impl From<u32> for Value {
     }
 }
 
+impl From<i8> for Value {
+    fn from(val: i8) -> Self {
+        Self::Integer(val.into())
+    }
+}
+
+impl From<i16> for Value {
+    fn from(val: i16) -> Self {
+        Self::Integer(val.into())
+    }
+}
+
+impl From<i32> for Value {
+    fn from(val: i32) -> Self {
+        Self::Integer(val)
+    }
+}
+
 impl From<(u8, u8)> for Value {
     fn from(vals: (u8, u8)) -> Self {
         Self::Sensor(vals.0.into(), vals.1.into())
@@ -321,12 +474,132 @@ impl From<Fault> for Value {
     }
 }
 
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl serde::Serialize for Value {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let len = match self {
+            Self::Sensor(..) | Self::Enum { .. } | Self::Flags { .. } | Self::Decimal { .. } => 3,
+            _ => 2,
+        };
+        let mut map = serializer.serialize_map(Some(len))?;
+
+        match self {
+            Self::Bool(val) => {
+                map.serialize_entry("type", "bool")?;
+                map.serialize_entry("value", val)?;
+            }
+            Self::Number(num) => {
+                map.serialize_entry("type", "number")?;
+                map.serialize_entry("value", num)?;
+            }
+            Self::Sensor(current, target) => {
+                map.serialize_entry("type", "sensor")?;
+                map.serialize_entry("value", current)?;
+                map.serialize_entry("target", target)?;
+            }
+            Self::String(string) => {
+                map.serialize_entry("type", "string")?;
+                map.serialize_entry("value", string)?;
+            }
+            Self::Duration(dur) => {
+                map.serialize_entry("type", "duration")?;
+                map.serialize_entry("value", dur)?;
+            }
+            Self::Date(date) => {
+                map.serialize_entry("type", "date")?;
+                map.serialize_entry("value", date)?;
+            }
+            Self::Fault(fault) => {
+                map.serialize_entry("type", "fault")?;
+                map.serialize_entry("value", fault)?;
+            }
+            Self::Enum { value, variants } => {
+                map.serialize_entry("type", "enum")?;
+                map.serialize_entry("value", value)?;
+                map.serialize_entry("variants", variants)?;
+            }
+            Self::Flags { set, all } => {
+                map.serialize_entry("type", "flags")?;
+                map.serialize_entry("value", set)?;
+                map.serialize_entry("all", all)?;
+            }
+            Self::Integer(num) => {
+                map.serialize_entry("type", "integer")?;
+                map.serialize_entry("value", num)?;
+            }
+            Self::Decimal { value, scale } => {
+                map.serialize_entry("type", "decimal")?;
+                map.serialize_entry("value", value)?;
+                map.serialize_entry("scale", scale)?;
+            }
+        }
+
+        map.end()
+    }
+}
+
+/// Owned representation of a [`Value`] used for deserialization.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ValueRepr {
+    Bool { value: bool },
+    Number { value: u32 },
+    Sensor { value: u32, target: u32 },
+    String { value: String },
+    Duration { value: Duration },
+    Date { value: Date },
+    Fault { value: Fault },
+    Integer { value: i32 },
+    Decimal { value: i32, scale: u8 },
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> serde::Deserialize<'de> for Value {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> core::result::Result<Self, D::Error> {
+        Ok(match ValueRepr::deserialize(deserializer)? {
+            ValueRepr::Bool { value } => Self::Bool(value),
+            ValueRepr::Number { value } => Self::Number(value),
+            ValueRepr::Sensor { value, target } => Self::Sensor(value, target),
+            ValueRepr::String { value } => Self::String(value),
+            ValueRepr::Duration { value } => Self::Duration(value),
+            ValueRepr::Date { value } => Self::Date(value),
+            ValueRepr::Fault { value } => Self::Fault(value),
+            ValueRepr::Integer { value } => Self::Integer(value),
+            ValueRepr::Decimal { value, scale } => Self::Decimal { value, scale },
+        })
+    }
+}
+
+/// A property together with its queried value.
+///
+/// When serialized, the value is accompanied by the property's kind and unit.
+#[derive(PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PropertyValue<'a> {
+    /// Queried property.
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub property: &'a Property,
+    /// Value of the property.
+    pub value: Value,
+}
+
 /// Trait implemented by all supported devices.
 ///
 /// Provides asynchronous access to device properties and actions
 /// over a diagnostic port that implements [`Read`] and [`Write`].
 ///
-/// This trait is sealed and cannot be implemented outside this crate.
+/// External crates can implement this trait to support further devices,
+/// see [`DeviceRegistry`] on how to connect to them.
 ///
 /// # Errors
 ///
@@ -348,7 +621,7 @@ impl From<Fault> for Value {
 /// # }
 /// ```
 #[async_trait::async_trait(?Send)]
-pub trait Device<P: Read + Write>: private::Sealed {
+pub trait Device<P: Read + Write> {
     /// Connects to the device via the specified port.
     ///
     /// This is an associated function and does not require an existing device instance.
@@ -387,10 +660,83 @@ pub trait Device<P: Read + Write>: private::Sealed {
     /// # Errors
     ///
     /// - [`Error::UnknownProperty`] if the device does not support the specified property.
+    /// - [`Error::Unavailable`] if the property is unavailable in the current connection mode.
     ///
     /// See the [`Device`] documentation for other errors.
     async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error>;
 
+    /// Queries multiple properties at once.
+    ///
+    /// The memory spans of all properties, see [`Device::memory_spans`], are read
+    /// in advance using as few frames as possible, which is considerably faster
+    /// than querying each property separately.
+    ///
+    /// Returns the result of each property in the order of `props`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Protocol`] if the communication with the device fails.
+    ///   Errors specific to a single property, e.g. [`Error::Unavailable`],
+    ///   are returned as the property's result instead.
+    async fn query_properties(
+        &mut self,
+        props: &[&Property],
+    ) -> Result<Vec<Result<Value, P::Error>>, P::Error> {
+        let spans: Vec<_> = props
+            .iter()
+            .flat_map(|prop| self.memory_spans(prop))
+            .cloned()
+            .collect();
+        let mut vals = Vec::with_capacity(props.len());
+
+        self.interface().prefetch_memory(spans).await?;
+
+        for prop in props {
+            match self.query_property(prop).await {
+                Err(Error::Protocol(err)) => {
+                    self.interface().clear_prefetched();
+
+                    return Err(Error::Protocol(err));
+                }
+                res => vals.push(res),
+            }
+        }
+
+        self.interface().clear_prefetched();
+
+        Ok(vals)
+    }
+
+    /// Returns the memory ranges read when querying the specified property.
+    ///
+    /// Used by [`Device::query_properties`] to combine the memory reads of multiple properties.
+    /// Properties stored in the EEPROM or computed without reading memory return an empty slice.
+    fn memory_spans(&self, _prop: &Property) -> &[Range<u32>] {
+        &[]
+    }
+
+    /// Queries the fault history kept by the device's firmware.
+    ///
+    /// Returns the stored faults in the order the firmware keeps them, which doesn't
+    /// necessarily correspond to the time of occurrence. The firmware doesn't record
+    /// when or how often a fault occurred, so the entries don't include a timestamp or operating time.
+    ///
+    /// Faults queried from the supported devices don't include [`FaultInfo`] either. To provide it, combine the history with
+    /// the fault transitions recorded on the host using [`faults::FaultLog::extend_history`],
+    /// then add the derived information to the queried faults using [`faults::FaultLog::annotate`].
+    ///
+    /// The default implementation returns [`Error::Unavailable`], as most devices only store
+    /// whether a fault occurred.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Unavailable`] if the device does not keep a fault history.
+    ///
+    /// See the [`Device`] documentation for other errors.
+    async fn query_fault_history(&mut self) -> Result<Vec<faults::FaultEntry>, P::Error> {
+        Err(Error::Unavailable)
+    }
+
     /// Triggers a specified action.
     ///
     /// The action must be from the set returned by [`Device::actions`].
@@ -402,6 +748,7 @@ pub trait Device<P: Read + Write>: private::Sealed {
     ///
     /// - [`Error::InvalidArgument`] if `param` does not match the expected type.
     /// - [`Error::UnknownAction`] if the device does not support the specified action.
+    /// - [`Error::ReadOnly`] if the device was connected in read-only mode.
     ///
     /// See the [`Device`] documentation for other errors.
     async fn trigger_action(
@@ -438,9 +785,303 @@ pub trait Device<P: Read + Write>: private::Sealed {
 pub async fn connect<'a, P: 'a + Read + Write>(
     port: P,
 ) -> Result<Box<dyn Device<P> + 'a>, P::Error> {
-    let mut intf = Interface::new(port);
-    let id = intf.query_software_id().await?;
+    connect_with(Interface::new(port)).await
+}
+
+/// Connects to a device asynchronously using a preconfigured diagnostic interface.
+///
+/// In contrast to [`connect`], this allows configuring the interface beforehand,
+/// e.g. to enable timeouts using [`Interface::enable_timeouts`].
+///
+/// Returns a boxed [`Device`] implementation on success.
+///
+/// # Errors
+///
+/// See [`connect`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use freemdu::{Interface, serial::Delay, time::Timeouts};
+///
+/// # async fn example() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+/// let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+/// let mut intf = Interface::new(&mut port);
+///
+/// intf.enable_timeouts(Delay, Timeouts::default());
+///
+/// let mut dev = freemdu::device::connect_with(intf).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connect_with<'a, P: 'a + Read + Write>(
+    intf: Interface<P>,
+) -> Result<Box<dyn Device<P> + 'a>, P::Error> {
+    DeviceRegistry::new().connect_with(intf).await
+}
+
+/// Connects to a device asynchronously using the specified options.
+///
+/// Returns a boxed [`Device`] implementation on success.
+///
+/// # Errors
+///
+/// See [`connect`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use freemdu::{Interface, device::ConnectOptions};
+///
+/// # async fn example() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+/// let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+/// let options = ConnectOptions::new().with_read_only(true);
+/// let mut dev = freemdu::device::connect_with_options(Interface::new(&mut port), options).await?;
+///
+/// for prop in dev.properties() {
+///     println!("{}: {:?}", prop.name, dev.query_property(prop).await);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connect_with_options<'a, P: 'a + Read + Write>(
+    intf: Interface<P>,
+    options: ConnectOptions,
+) -> Result<Box<dyn Device<P> + 'a>, P::Error> {
+    DeviceRegistry::new()
+        .with_options(options)
+        .connect_with(intf)
+        .await
+}
+
+/// Connects to a device asynchronously, falling back to a [`GenericDevice`]
+/// if the software ID is not recognized by any supported implementation.
+///
+/// The generic device is unlocked using the keys from the provided configuration.
+///
+/// # Errors
+///
+/// - [`Error::Protocol`] with [`crate::Error::KeysNotFound`] if the software ID is not
+///   recognized and the generic device cannot be unlocked.
+/// - [`Error::Protocol`] for any other errors during diagnostic communication.
+///
+/// # Examples
+///
+/// ```no_run
+/// use freemdu::{Interface, device::generic::GenericConfig};
+///
+/// # async fn example() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+/// let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+/// let mut dev =
+///     freemdu::device::connect_with_fallback(Interface::new(&mut port), GenericConfig::new())
+///         .await?;
+///
+/// println!("{}, software ID {}", dev.kind(), dev.software_id());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connect_with_fallback<'a, P: 'a + Read + Write>(
+    intf: Interface<P>,
+    config: GenericConfig,
+) -> Result<Box<dyn Device<P> + 'a>, P::Error> {
+    DeviceRegistry::new()
+        .with_fallback(config)
+        .connect_with(intf)
+        .await
+}
+
+/// Options for connecting to a device.
+#[derive(Default, Clone, Debug)]
+pub struct ConnectOptions {
+    read_only: bool,
+}
+
+impl ConnectOptions {
+    /// Constructs the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Connects in read-only mode, which never writes to the device.
+    ///
+    /// Only read access is unlocked, skipping the full access key and the write
+    /// disabling the ROM readout protection, see [`Interface::set_read_only`].
+    /// Properties stored in protected ROM return [`Error::Unavailable`],
+    /// and actions fail with [`Error::ReadOnly`].
+    #[must_use]
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
 
+    /// Returns `true` if read-only mode is enabled.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+/// Factory for device implementations registered in a [`DeviceRegistry`].
+///
+/// Implemented by [`GenericConfig`] and, if the `definitions` feature is enabled,
+/// by `definition::Definition`.
+pub trait DeviceFactory<'a, P: 'a + Read + Write> {
+    /// Initializes a device using an interface whose software ID has already been queried.
+    ///
+    /// This is where the device is usually unlocked.
+    ///
+    /// # Errors
+    ///
+    /// See the [`Device`] documentation.
+    fn initialize<'f>(&'f self, intf: Interface<P>, software_id: u16) -> FactoryFuture<'f, 'a, P>
+    where
+        'a: 'f;
+}
+
+/// Future returned by [`DeviceFactory::initialize`].
+pub type FactoryFuture<'f, 'a, P> =
+    Pin<Box<dyn Future<Output = Result<Box<dyn Device<P> + 'a>, <P as ErrorType>::Error>> + 'f>>;
+
+impl<'a, P: 'a + Read + Write> DeviceFactory<'a, P> for GenericConfig {
+    fn initialize<'f>(&'f self, intf: Interface<P>, software_id: u16) -> FactoryFuture<'f, 'a, P>
+    where
+        'a: 'f,
+    {
+        Box::pin(async move {
+            Ok(
+                Box::new(GenericDevice::initialize(intf, software_id, self.clone()).await?)
+                    as Box<dyn Device<P>>,
+            )
+        })
+    }
+}
+
+/// Registry of device implementations consulted when connecting to a device.
+///
+/// Registered implementations take precedence over the built-in ones,
+/// allowing external crates to support further devices or to replace
+/// the implementation of a supported device.
+///
+/// # Examples
+///
+/// ```
+/// use freemdu::{
+///     Interface, KeyPair,
+///     device::{DeviceKind, DeviceRegistry, generic::GenericConfig},
+///     simulator::Simulator,
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() -> freemdu::device::Result<(), core::convert::Infallible> {
+/// let mut sim = Simulator::new(1234, 0x1111, 0x2222);
+/// let mut registry = DeviceRegistry::new();
+///
+/// registry.register([1234], GenericConfig::new().with_keys(KeyPair::new(0x1111, 0x2222)));
+///
+/// let dev = registry.connect_with(Interface::new(&mut sim)).await?;
+///
+/// assert_eq!(dev.kind(), DeviceKind::Unknown);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeviceRegistry<'a, P> {
+    entries: Vec<(Vec<u16>, Box<dyn DeviceFactory<'a, P> + 'a>)>,
+    options: ConnectOptions,
+    fallback: Option<GenericConfig>,
+}
+
+impl<'a, P: 'a + Read + Write> DeviceRegistry<'a, P> {
+    /// Constructs a registry containing only the built-in device implementations.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            options: ConnectOptions::new(),
+            fallback: None,
+        }
+    }
+
+    /// Registers a device implementation for the specified software IDs.
+    ///
+    /// Implementations registered later take precedence over earlier ones.
+    pub fn register(
+        &mut self,
+        software_ids: impl IntoIterator<Item = u16>,
+        factory: impl DeviceFactory<'a, P> + 'a,
+    ) {
+        self.entries
+            .push((software_ids.into_iter().collect(), Box::new(factory)));
+    }
+
+    /// Uses the specified options when connecting to a device.
+    #[must_use]
+    pub fn with_options(mut self, options: ConnectOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Falls back to a [`GenericDevice`] if no implementation supports the software ID.
+    #[must_use]
+    pub fn with_fallback(mut self, config: GenericConfig) -> Self {
+        self.fallback = Some(config);
+        self
+    }
+
+    /// Connects to a device using a preconfigured diagnostic interface.
+    ///
+    /// Returns a boxed [`Device`] implementation on success.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::UnknownSoftwareId`] if the device's software ID is not recognized
+    ///   by any registered or built-in implementation and no fallback is configured.
+    /// - [`Error::Protocol`] for any other errors during diagnostic communication.
+    pub async fn connect_with(
+        &self,
+        mut intf: Interface<P>,
+    ) -> Result<Box<dyn Device<P> + 'a>, P::Error> {
+        if self.options.read_only {
+            intf.set_read_only(true);
+        }
+
+        let id = intf.query_software_id().await?;
+        let registered = self.entries.iter().rev().find(|(ids, _)| ids.contains(&id));
+
+        match registered {
+            Some((_, factory)) => factory.initialize(intf, id).await,
+            None => initialize(intf, id, self.fallback.clone()).await,
+        }
+    }
+}
+
+impl<'a, P: 'a + Read + Write> Default for DeviceRegistry<'a, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P> core::fmt::Debug for DeviceRegistry<'_, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DeviceRegistry")
+            .field(
+                "software_ids",
+                &self.entries.iter().map(|(ids, _)| ids).collect::<Vec<_>>(),
+            )
+            .field("options", &self.options)
+            .field("fallback", &self.fallback)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Initializes the device implementation compatible with the software ID.
+///
+/// Unknown software IDs result in a generic device if a configuration is provided.
+async fn initialize<'a, P: 'a + Read + Write>(
+    intf: Interface<P>,
+    id: u16,
+    generic: Option<GenericConfig>,
+) -> Result<Box<dyn Device<P> + 'a>, P::Error> {
     match id {
         id218::compatible_software_ids!() => {
             Ok(Box::new(id218::WashingMachine::initialize(intf, id).await?) as Box<dyn Device<P>>)
@@ -472,19 +1113,21 @@ pub async fn connect<'a, P: 'a + Read + Write>(
         id2895::compatible_software_ids!() => {
             Ok(Box::new(id2895::WashingMachine::initialize(intf, id).await?) as Box<dyn Device<P>>)
         }
-        _ => Err(Error::UnknownSoftwareId(id)),
+        _ => {
+            match generic {
+                Some(config) => Ok(Box::new(GenericDevice::initialize(intf, id, config).await?)
+                    as Box<dyn Device<P>>),
+                None => Err(Error::UnknownSoftwareId(id)),
+            }
+        }
     }
 }
 
-mod private {
-    pub trait Sealed {}
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::init_logger;
-    use alloc::collections::vec_deque::VecDeque;
+    use crate::{simulator::Simulator, tests::init_logger};
+    use alloc::{collections::vec_deque::VecDeque, vec};
     use core::convert::Infallible;
 
     #[tokio::test]
@@ -516,6 +1159,81 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn connect_generic_device() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = Simulator::new(1234, 0x43ea, 0x1f02);
+        let dev = connect_with_fallback(Interface::new(&mut sim), GenericConfig::new()).await?;
+
+        assert_eq!(dev.software_id(), 1234, "software ID should be correct");
+        assert_eq!(
+            dev.kind(),
+            DeviceKind::Unknown,
+            "device kind should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn connect_read_only() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x0400]);
+        let options = ConnectOptions::new().with_read_only(true);
+
+        {
+            let mut dev = connect_with_options(Interface::new(&mut sim), options).await?;
+            let rom_code = dev.properties().iter().find(|prop| prop.id == "rom_code");
+            let set_opts = dev
+                .actions()
+                .iter()
+                .find(|action| action.id == "set_program_options");
+
+            assert_eq!(
+                dev.query_property(rom_code.unwrap()).await,
+                Err(Error::Unavailable),
+                "ROM code should be unavailable"
+            );
+            assert_eq!(
+                dev.trigger_action(set_opts.unwrap(), Some("Soak")).await,
+                Err(Error::ReadOnly),
+                "action should fail with read-only error"
+            );
+        }
+
+        let mut mem = [0x00; 0x0400];
+
+        sim.read_memory(0x0000, &mut mem);
+
+        assert_eq!(mem, [0x00; 0x0400], "memory should be unchanged");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn registry_precedence() -> Result<(), Infallible> {
+        init_logger();
+
+        // Registered implementations replace the built-in one
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x0400]);
+        let mut registry = DeviceRegistry::new();
+
+        registry.register([605, 629], GenericConfig::new());
+
+        let dev = registry.connect_with(Interface::new(&mut sim)).await?;
+
+        assert_eq!(dev.software_id(), 629, "software ID should be correct");
+        assert_eq!(
+            dev.kind(),
+            DeviceKind::Unknown,
+            "device kind should be correct"
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn error_unknown_software_id() -> Result<(), Infallible> {
         init_logger();
@@ -530,4 +1248,143 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn query_properties() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut ram = [0x00; 0x0400];
+
+        ram[0x00cd] = 0x02;
+        ram[0x007f..0x0081].copy_from_slice(&[0x1e, 0x32]);
+
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+            .with_ram(0x0000, ram)
+            .with_rom(0xffdb, [0x2a]);
+        let mut dev = connect(&mut sim).await?;
+        let props: Vec<_> = dev.properties().iter().collect();
+        let mut expected = Vec::new();
+
+        for prop in &props {
+            expected.push(dev.query_property(prop).await);
+        }
+
+        assert_eq!(
+            dev.query_properties(&props).await?,
+            expected,
+            "property values should be correct"
+        );
+
+        // The memory spans must cover all memory read by a property
+        let spans: Vec<_> = props
+            .iter()
+            .flat_map(|prop| dev.memory_spans(prop))
+            .cloned()
+            .collect();
+        let prefetched: Vec<_> = props
+            .iter()
+            .filter(|prop| !dev.memory_spans(prop).is_empty())
+            .collect();
+
+        dev.interface().prefetch_memory(spans).await?;
+        dev.interface().lock().await?;
+
+        for prop in prefetched {
+            assert!(
+                !matches!(dev.query_property(prop).await, Err(Error::Protocol(_))),
+                "{} should be decoded from prefetched memory",
+                prop.id
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_values() {
+        let prop = Property {
+            kind: PropertyKind::Io,
+            id: "water_level",
+            name: "Water Level",
+            unit: Some("mm"),
+        };
+        let val = PropertyValue {
+            property: &prop,
+            value: Value::Sensor(30, 50),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&val).unwrap(),
+            r#"{"kind":"io","id":"water_level","name":"Water Level","unit":"mm","value":{"type":"sensor","value":30,"target":50}}"#,
+            "property value JSON should be correct"
+        );
+        assert_eq!(
+            serde_json::to_value([
+                Value::Duration(Duration::from_secs(3600)),
+                Value::Decimal {
+                    value: -425,
+                    scale: 1
+                },
+                Value::Fault(Fault::Ok),
+                Value::Fault(Fault::Stored(Some(FaultInfo {
+                    operating_hours: 12,
+                    count: 3
+                }))),
+                Value::Flags {
+                    set: vec!["Door"],
+                    all: vec!["Door", "Float"]
+                },
+            ])
+            .unwrap(),
+            serde_json::json!([
+                {"type": "duration", "value": {"secs": 3600, "nanos": 0}},
+                {"type": "decimal", "value": -425, "scale": 1},
+                {"type": "fault", "value": {"status": "ok"}},
+                {"type": "fault", "value": {"status": "stored", "info": {"operating_hours": 12, "count": 3}}},
+                {"type": "flags", "value": ["Door"], "all": ["Door", "Float"]},
+            ]),
+            "value JSON should be correct"
+        );
+        assert_eq!(
+            serde_json::from_str::<Fault>(r#"{"status":"active","info":null}"#).unwrap(),
+            Fault::Active(None),
+            "deserialized fault should be correct"
+        );
+        assert_eq!(
+            serde_json::from_value::<Vec<Value>>(serde_json::json!([
+                {"type": "sensor", "value": 30, "target": 50},
+                {"scale": 1, "value": -425, "type": "decimal"},
+                {"type": "fault", "value": {"status": "ok"}},
+            ]))
+            .unwrap(),
+            [
+                Value::Sensor(30, 50),
+                Value::Decimal {
+                    value: -425,
+                    scale: 1
+                },
+                Value::Fault(Fault::Ok),
+            ],
+            "deserialized values should be correct"
+        );
+
+        let dur = Value::Duration(Duration::from_millis(90_250));
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&serde_json::to_string(&dur).unwrap()).unwrap(),
+            dur,
+            "fractional duration should be preserved"
+        );
+        assert!(
+            serde_json::from_str::<Value>(r#"{"type":"enum","value":"Idle","variants":["Idle"]}"#)
+                .is_err(),
+            "enumeration value should not be deserialized"
+        );
+        assert_eq!(
+            serde_json::from_str::<DeviceKind>(r#""washing_machine""#).unwrap(),
+            DeviceKind::WashingMachine,
+            "deserialized device kind should be correct"
+        );
+    }
 }
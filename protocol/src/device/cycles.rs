@@ -0,0 +1,1097 @@
+//! Program cycle tracking based on property changes.
+//!
+//! A [`CycleTracker`] consumes the changes reported by a [`PropertyWatcher`](super::watch::PropertyWatcher)
+//! and detects when a program starts, moves between phases and steps and ends.
+//! For each completed run, a [`CycleSummary`] is emitted, allowing applications
+//! to keep a history of every program instead of only the current state.
+//!
+//! On washing machines, a run starts when the `operating_state` property changes to
+//! `ProgramRunning` and ends when it changes to any other state. Devices without an
+//! operating state, such as dishwashers, are tracked using the `program_phase` property:
+//! a run starts when the phase leaves `Idle` and ends when it returns to `Idle` or reaches `Finish`.
+//! Runs already in progress when tracking starts are ignored, as their start is unknown.
+//!
+//! # Estimation
+//!
+//! The tracker learns the duration of each program and its phases from completed runs,
+//! separately for each combination of program and options. While a program is running,
+//! the remaining time is estimated from the learned durations of the current and following phases.
+//! If the current phase is unknown, the elapsed time is subtracted from the learned program duration
+//! instead, using the `program_elapsed_time` property if provided by the device, e.g. a glasswasher.
+//! The estimates are exposed as the `remaining_time` and `estimated_end` properties,
+//! see [`CycleTracker::estimate_properties`].
+//!
+//! No estimate is available until a run of the same program has completed.
+//! Previously recorded runs can be provided using [`CycleTracker::learn`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use freemdu::{
+//!     device::{cycles::CycleTracker, watch::PropertyWatcher},
+//!     serial::Delay,
+//! };
+//! use futures_util::StreamExt;
+//! use std::{pin::pin, time::{Duration, Instant}};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+//! let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+//! let mut dev = freemdu::device::connect(&mut port).await?;
+//! let start = Instant::now();
+//! let tracker = CycleTracker::new(dev.properties());
+//! let watcher = PropertyWatcher::new(Duration::from_secs(1))
+//!     .watch(tracker.properties().iter().copied());
+//! let changes = watcher.stream(&mut *dev, Delay, move || start.elapsed());
+//! let mut summaries = pin!(tracker.track(changes));
+//!
+//! while let Some(summary) = summaries.next().await {
+//!     let summary = summary?;
+//!
+//!     println!("{:?} ran for {:?}", summary.program, summary.duration());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::device::{Fault, Property, PropertyKind, Value, watch::PropertyChange};
+use alloc::vec::Vec;
+use core::{future, time::Duration};
+use futures_util::{Stream, StreamExt};
+
+/// Identifiers of the properties used to track program cycles.
+const CYCLE_PROPERTIES: [&str; 9] = [
+    "operating_state",
+    "program_phase",
+    "program_step",
+    "selected_program",
+    "program_options",
+    "program_elapsed_time",
+    "temperature",
+    "tachometer_speed",
+    "flow_meter_pulses",
+];
+
+/// Volume of water corresponding to a single flow meter pulse in `ml` (milliliters).
+const FLOW_METER_PULSE_VOLUME: u32 = 5;
+
+const PROP_REMAINING_TIME: Property = Property {
+    kind: PropertyKind::Operation,
+    id: "remaining_time",
+    name: "Remaining Time",
+    unit: None,
+};
+const PROP_ESTIMATED_END: Property = Property {
+    kind: PropertyKind::Operation,
+    id: "estimated_end",
+    name: "Estimated End",
+    unit: None,
+};
+
+/// Time spent in a single program phase.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PhaseTime {
+    /// Name of the phase, as reported by the `program_phase` property.
+    pub phase: &'static str,
+    /// Time spent in the phase.
+    pub duration: Duration,
+}
+
+/// Time spent in a single program step.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StepTime {
+    /// Program step, as reported by the `program_step` property.
+    pub step: u32,
+    /// Time spent in the step.
+    pub duration: Duration,
+}
+
+/// Summary of a completed program run.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CycleSummary {
+    /// Selected program, if known.
+    pub program: Option<Value>,
+    /// Selected program options, if known.
+    pub options: Option<Value>,
+    /// Start time of the run, as returned by the watcher's clock.
+    pub start: Duration,
+    /// End time of the run, as returned by the watcher's clock.
+    pub end: Duration,
+    /// Time spent in each phase, in order of execution.
+    ///
+    /// Phases that are executed multiple times are listed once per execution.
+    pub phases: Vec<PhaseTime>,
+    /// Time spent in each program step, in order of execution.
+    ///
+    /// Empty if the device doesn't provide a `program_step` property.
+    pub steps: Vec<StepTime>,
+    /// Highest temperature sensed during the run in `°C` (degrees Celsius), if known.
+    pub peak_temperature: Option<u32>,
+    /// Number of flow meter pulses counted during the run, if the device has a flow meter.
+    pub water_pulses: Option<u32>,
+    /// Highest speed sensed by the tachometer during the run in `rpm`, if known.
+    pub max_spin_speed: Option<u32>,
+    /// Fault properties that were raised during the run.
+    pub faults: Vec<&'static Property>,
+}
+
+impl CycleSummary {
+    /// Returns the total duration of the run.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Returns the amount of water that entered the device during the run in `ml` (milliliters).
+    ///
+    /// The amount is estimated from the flow meter pulses, assuming that one liter of water
+    /// corresponds to `200` pulses under normal operating conditions.
+    #[must_use]
+    pub fn water_amount(&self) -> Option<u32> {
+        self.water_pulses
+            .map(|pulses| pulses.saturating_mul(FLOW_METER_PULSE_VOLUME))
+    }
+}
+
+/// State of a program run in progress.
+#[derive(Clone, Debug)]
+struct Cycle {
+    summary: CycleSummary,
+    phase: Option<&'static str>,
+    phase_start: Duration,
+    step: Option<u32>,
+    step_start: Duration,
+}
+
+impl Cycle {
+    /// Records the time spent in the current phase and switches to the specified phase.
+    fn enter_phase(&mut self, phase: Option<&'static str>, time: Duration) {
+        if phase == self.phase {
+            return;
+        }
+
+        self.close_phase(time);
+        self.phase = phase;
+        self.phase_start = time;
+    }
+
+    /// Records the time spent in the current phase, ignoring phases without a duration.
+    fn close_phase(&mut self, time: Duration) {
+        let duration = time.saturating_sub(self.phase_start);
+
+        match self.phase {
+            Some(phase) if !duration.is_zero() => {
+                self.summary.phases.push(PhaseTime { phase, duration });
+            }
+            _ => {}
+        }
+    }
+
+    /// Records the time spent in the current step and switches to the specified step.
+    fn enter_step(&mut self, step: Option<u32>, time: Duration) {
+        if step == self.step {
+            return;
+        }
+
+        self.close_step(time);
+        self.step = step;
+        self.step_start = time;
+    }
+
+    /// Records the time spent in the current step, ignoring steps without a duration.
+    fn close_step(&mut self, time: Duration) {
+        let duration = time.saturating_sub(self.step_start);
+
+        match self.step {
+            Some(step) if !duration.is_zero() => {
+                self.summary.steps.push(StepTime { step, duration });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Durations learned from completed runs of a program.
+#[derive(Clone, Debug)]
+struct Profile {
+    program: Option<Value>,
+    options: Option<Value>,
+    runs: u32,
+    duration: Duration,
+    phases: Vec<PhaseTime>,
+}
+
+impl Profile {
+    /// Averages the durations with the specified run.
+    ///
+    /// The phase durations are replaced if the run executed a different sequence of phases.
+    fn learn(&mut self, summary: &CycleSummary) {
+        let same_phases = self.phases.len() == summary.phases.len()
+            && self
+                .phases
+                .iter()
+                .zip(&summary.phases)
+                .all(|(a, b)| a.phase == b.phase);
+
+        if same_phases {
+            for (phase, run) in self.phases.iter_mut().zip(&summary.phases) {
+                phase.duration = average(phase.duration, run.duration, self.runs);
+            }
+        } else {
+            self.phases.clone_from(&summary.phases);
+        }
+
+        self.duration = average(self.duration, summary.duration(), self.runs);
+        self.runs = self.runs.saturating_add(1);
+    }
+
+    /// Estimates the remaining time of a run.
+    ///
+    /// The current phase is searched in the learned phases, starting at the
+    /// number of phases the run has already completed, as phases may be repeated.
+    fn remaining_time(
+        &self,
+        phase: Option<(&'static str, Duration, usize)>,
+        elapsed: Option<Duration>,
+    ) -> Option<Duration> {
+        let phase = phase.and_then(|(name, in_phase, completed)| {
+            let position = |skip| {
+                self.phases
+                    .iter()
+                    .skip(skip)
+                    .position(|phase| phase.phase == name)
+                    .map(|idx| idx + skip)
+            };
+
+            position(completed)
+                .or_else(|| position(0))
+                .map(|idx| (idx, in_phase))
+        });
+
+        match (phase, elapsed) {
+            (Some((idx, in_phase)), _) => Some(
+                self.phases[idx].duration.saturating_sub(in_phase)
+                    + self.phases[idx + 1..]
+                        .iter()
+                        .map(|phase| phase.duration)
+                        .sum::<Duration>(),
+            ),
+            (None, Some(elapsed)) => Some(self.duration.saturating_sub(elapsed)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Tracker that detects program runs from property changes.
+#[derive(Clone, Debug)]
+pub struct CycleTracker {
+    props: Vec<&'static Property>,
+    by_state: bool,
+    running: bool,
+    program: Option<Value>,
+    options: Option<Value>,
+    elapsed: Option<Duration>,
+    phase: Option<&'static str>,
+    step: Option<u32>,
+    temperature: Option<u32>,
+    spin_speed: Option<u32>,
+    pulses: Option<u32>,
+    cycle: Option<Cycle>,
+    profiles: Vec<Profile>,
+}
+
+impl CycleTracker {
+    /// Constructs a new tracker for a device with the specified properties.
+    ///
+    /// Only the properties relevant for tracking program runs are used,
+    /// see [`CycleTracker::properties`].
+    #[must_use]
+    pub fn new(props: &'static [Property]) -> Self {
+        let props: Vec<_> = props
+            .iter()
+            .filter(|prop| prop.kind == PropertyKind::Fault || CYCLE_PROPERTIES.contains(&prop.id))
+            .collect();
+        let by_state = props.iter().any(|prop| prop.id == "operating_state");
+
+        Self {
+            props,
+            by_state,
+            running: false,
+            program: None,
+            options: None,
+            elapsed: None,
+            phase: None,
+            step: None,
+            temperature: None,
+            spin_speed: None,
+            pulses: None,
+            cycle: None,
+            profiles: Vec::new(),
+        }
+    }
+
+    /// Returns the properties that need to be watched to track program runs.
+    ///
+    /// This includes the operating state, program, phase, step and elapsed time properties,
+    /// the temperature, tachometer and flow meter sensors as well as all faults.
+    #[must_use]
+    pub fn properties(&self) -> &[&'static Property] {
+        &self.props
+    }
+
+    /// Returns whether a program run is currently in progress.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Returns the properties providing estimates for the current run.
+    ///
+    /// The `remaining_time` property contains the estimated remaining time of the run,
+    /// while the `estimated_end` property contains the estimated end time,
+    /// using the same time base as the watcher's clock.
+    /// If the clock returns the time since the Unix epoch, e.g. based on `SystemTime`,
+    /// the estimated end is a Unix timestamp that can be converted into a calendar time.
+    /// Use [`CycleTracker::query_estimate`] to query their values.
+    #[must_use]
+    pub fn estimate_properties() -> &'static [Property] {
+        &[PROP_REMAINING_TIME, PROP_ESTIMATED_END]
+    }
+
+    /// Queries the value of an estimate property at the specified time.
+    ///
+    /// Returns `None` if no program is running, no completed run of the
+    /// current program has been learned yet or the property is unknown.
+    #[must_use]
+    pub fn query_estimate(&self, prop: &Property, now: Duration) -> Option<Value> {
+        let remaining = self.remaining_time(now)?;
+
+        match *prop {
+            PROP_REMAINING_TIME => Some(Value::Duration(remaining)),
+            PROP_ESTIMATED_END => Some(Value::Duration(now + remaining)),
+            _ => None,
+        }
+    }
+
+    /// Estimates the remaining time of the current run at the specified time.
+    ///
+    /// Returns `None` if no program is running or no completed run
+    /// of the current program has been learned yet.
+    #[must_use]
+    pub fn remaining_time(&self, now: Duration) -> Option<Duration> {
+        if !self.running {
+            return None;
+        }
+
+        // Prefer the learned durations of the exact program options
+        let profile = self
+            .profiles
+            .iter()
+            .find(|profile| profile.program == self.program && profile.options == self.options)
+            .or_else(|| {
+                self.profiles
+                    .iter()
+                    .find(|profile| profile.program == self.program)
+            })?;
+        let phase = self.cycle.as_ref().and_then(|cycle| {
+            let name = cycle.phase?;
+
+            Some((
+                name,
+                now.saturating_sub(cycle.phase_start),
+                cycle.summary.phases.len(),
+            ))
+        });
+        let elapsed = self.elapsed.or_else(|| {
+            self.cycle
+                .as_ref()
+                .map(|cycle| now.saturating_sub(cycle.summary.start))
+        });
+
+        profile.remaining_time(phase, elapsed)
+    }
+
+    /// Learns the program and phase durations from a completed run.
+    ///
+    /// Runs detected by the tracker are learned automatically.
+    pub fn learn(&mut self, summary: &CycleSummary) {
+        match self.profiles.iter_mut().find(|profile| {
+            profile.program == summary.program && profile.options == summary.options
+        }) {
+            Some(profile) => profile.learn(summary),
+            None => self.profiles.push(Profile {
+                program: summary.program.clone(),
+                options: summary.options.clone(),
+                runs: 1,
+                duration: summary.duration(),
+                phases: summary.phases.clone(),
+            }),
+        }
+    }
+
+    /// Updates the tracker with a property change, returning a summary if a run has ended.
+    ///
+    /// Changes of properties that are not relevant for tracking are ignored.
+    pub fn update(&mut self, change: &PropertyChange) -> Option<CycleSummary> {
+        let time = change.time;
+        let initial = change.old.is_none();
+
+        match change.property.id {
+            "operating_state" if self.by_state => {
+                let running = enum_value(&change.new) == Some("ProgramRunning");
+
+                return self.set_running(running, initial, time);
+            }
+            "program_phase" => {
+                let phase = enum_value(&change.new);
+
+                if let Some(cycle) = &mut self.cycle {
+                    cycle.enter_phase(phase, time);
+                }
+
+                self.phase = phase;
+
+                if !self.by_state {
+                    let running = phase.is_some_and(|phase| phase != "Idle" && phase != "Finish");
+
+                    return self.set_running(running, initial, time);
+                }
+            }
+            "program_step" => {
+                let step = current_value(&change.new);
+
+                if let Some(cycle) = &mut self.cycle {
+                    cycle.enter_step(step, time);
+                }
+
+                self.step = step;
+            }
+            "selected_program" => {
+                self.program = Some(change.new.clone());
+
+                if let Some(cycle) = &mut self.cycle {
+                    cycle.summary.program.clone_from(&self.program);
+                }
+            }
+            "program_options" => {
+                self.options = Some(change.new.clone());
+
+                if let Some(cycle) = &mut self.cycle {
+                    cycle.summary.options.clone_from(&self.options);
+                }
+            }
+            "program_elapsed_time" => {
+                self.elapsed = match change.new {
+                    Value::Duration(elapsed) => Some(elapsed),
+                    _ => None,
+                };
+            }
+            "temperature" => {
+                self.temperature = current_value(&change.new);
+
+                if let Some(cycle) = &mut self.cycle {
+                    cycle.summary.peak_temperature =
+                        cycle.summary.peak_temperature.max(self.temperature);
+                }
+            }
+            "tachometer_speed" => {
+                self.spin_speed = current_value(&change.new);
+
+                if let Some(cycle) = &mut self.cycle {
+                    cycle.summary.max_spin_speed =
+                        cycle.summary.max_spin_speed.max(self.spin_speed);
+                }
+            }
+            "flow_meter_pulses" => {
+                let pulses = current_value(&change.new);
+
+                let total = self
+                    .cycle
+                    .as_mut()
+                    .and_then(|cycle| cycle.summary.water_pulses.as_mut());
+
+                if let (Some(total), Some(prev), Some(cur)) = (total, self.pulses, pulses) {
+                    // The counter is reset for each water intake
+                    *total += if cur >= prev { cur - prev } else { cur };
+                }
+
+                self.pulses = pulses;
+            }
+            _ if change.property.kind == PropertyKind::Fault => {
+                let raised = matches!(
+                    change.new,
+                    Value::Fault(Fault::Active(_) | Fault::Stored(_))
+                ) && matches!(change.old, Some(Value::Fault(Fault::Ok)));
+
+                match &mut self.cycle {
+                    Some(cycle) if raised && !cycle.summary.faults.contains(&change.property) => {
+                        cycle.summary.faults.push(change.property);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Returns a stream of summaries of the runs detected in a stream of property changes.
+    ///
+    /// Errors of the property change stream are passed through.
+    /// As the tracker is consumed, use [`CycleTracker::update`] instead to query estimates.
+    pub fn track<E>(
+        mut self,
+        changes: impl Stream<Item = Result<PropertyChange, E>>,
+    ) -> impl Stream<Item = Result<CycleSummary, E>> {
+        changes.filter_map(move |change| {
+            future::ready(match change {
+                Ok(change) => self.update(&change).map(Ok),
+                Err(err) => Some(Err(err)),
+            })
+        })
+    }
+
+    /// Starts or ends a run, returning a summary if a run has ended.
+    fn set_running(
+        &mut self,
+        running: bool,
+        initial: bool,
+        time: Duration,
+    ) -> Option<CycleSummary> {
+        self.running = running;
+
+        match (running, self.cycle.take()) {
+            // Ignore runs already in progress, as their start is unknown
+            (true, None) if !initial => {
+                let has_flow_meter = self.props.iter().any(|prop| prop.id == "flow_meter_pulses");
+
+                self.cycle = Some(Cycle {
+                    summary: CycleSummary {
+                        program: self.program.clone(),
+                        options: self.options.clone(),
+                        start: time,
+                        end: time,
+                        phases: Vec::new(),
+                        steps: Vec::new(),
+                        peak_temperature: self.temperature,
+                        water_pulses: has_flow_meter.then_some(0),
+                        max_spin_speed: self.spin_speed,
+                        faults: Vec::new(),
+                    },
+                    phase: self.phase,
+                    phase_start: time,
+                    step: self.step,
+                    step_start: time,
+                });
+
+                None
+            }
+            (true, cycle) => {
+                self.cycle = cycle;
+
+                None
+            }
+            (false, Some(mut cycle)) => {
+                cycle.close_phase(time);
+                cycle.close_step(time);
+                cycle.summary.end = time;
+                self.learn(&cycle.summary);
+
+                Some(cycle.summary)
+            }
+            (false, None) => None,
+        }
+    }
+}
+
+/// Returns the running average of a duration after adding a sample.
+fn average(avg: Duration, sample: Duration, count: u32) -> Duration {
+    (avg * count + sample) / (count + 1)
+}
+
+/// Returns the variant of an enumeration value.
+fn enum_value(val: &Value) -> Option<&'static str> {
+    match val {
+        Value::Enum { value, .. } => Some(value),
+        _ => None,
+    }
+}
+
+/// Returns the current value of a number or sensor value.
+fn current_value(val: &Value) -> Option<u32> {
+    match val {
+        Value::Number(val) | Value::Sensor(val, _) => Some(*val),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        device::{self, id517, id605, id629, watch::PropertyWatcher},
+        simulator::Simulator,
+        tests::init_logger,
+    };
+    use alloc::{rc::Rc, vec};
+    use core::{cell::Cell, convert::Infallible, pin::pin};
+    use embedded_hal_async::delay::DelayNs;
+    use embedded_io_async::{ErrorType, Read, Write};
+    use futures_util::stream;
+
+    const fn property(kind: PropertyKind, id: &'static str) -> Property {
+        Property {
+            kind,
+            id,
+            name: id,
+            unit: None,
+        }
+    }
+
+    static WASHING_MACHINE: [Property; 7] = [
+        property(PropertyKind::Fault, "fault_f1"),
+        property(PropertyKind::Operation, "operating_state"),
+        property(PropertyKind::Operation, "selected_program"),
+        property(PropertyKind::Operation, "program_phase"),
+        property(PropertyKind::Operation, "load_level"),
+        property(PropertyKind::Io, "temperature"),
+        property(PropertyKind::Io, "tachometer_speed"),
+    ];
+    static DISHWASHER: [Property; 3] = [
+        property(PropertyKind::Operation, "program_phase"),
+        property(PropertyKind::Operation, "program_elapsed_time"),
+        property(PropertyKind::Io, "flow_meter_pulses"),
+    ];
+
+    /// Constructs property changes from a sequence of values and their time in seconds.
+    fn changes(
+        props: &'static [Property],
+        vals: impl IntoIterator<Item = (&'static str, Value, u64)>,
+    ) -> Vec<PropertyChange> {
+        let mut changes: Vec<PropertyChange> = Vec::new();
+
+        for (id, new, secs) in vals {
+            let property = props.iter().find(|prop| prop.id == id).unwrap();
+            let old = changes
+                .iter()
+                .rev()
+                .find(|change| change.property == property)
+                .map(|change| change.new.clone());
+
+            changes.push(PropertyChange {
+                property,
+                old,
+                new,
+                time: Duration::from_secs(secs),
+            });
+        }
+
+        changes
+    }
+
+    /// Port that updates the simulated memory according to a script of timed writes.
+    struct ScriptedPort {
+        sim: Simulator,
+        ticks: Rc<Cell<u8>>,
+        script: &'static [(u8, u32, &'static [u8])],
+    }
+
+    impl ErrorType for ScriptedPort {
+        type Error = Infallible;
+    }
+
+    impl Read for ScriptedPort {
+        async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Infallible> {
+            self.sim.read(buf).await
+        }
+    }
+
+    impl Write for ScriptedPort {
+        async fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Infallible> {
+            for (tick, addr, data) in self.script {
+                if *tick <= self.ticks.get() {
+                    self.sim.write_memory(*addr, data);
+                }
+            }
+
+            self.sim.write(buf).await
+        }
+
+        async fn flush(&mut self) -> core::result::Result<(), Infallible> {
+            self.sim.flush().await
+        }
+    }
+
+    /// Delay that advances the ticks instead of waiting.
+    struct TickingDelay(Rc<Cell<u8>>);
+
+    impl DelayNs for TickingDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn washing_machine_cycle() {
+        init_logger();
+
+        let state = |state: id629::OperatingState| Value::from_enum(state);
+        let phase = |phase: id629::ProgramPhase| Value::from_enum(phase);
+        let mut tracker = CycleTracker::new(&WASHING_MACHINE);
+        let mut summaries = Vec::new();
+
+        for change in changes(
+            &WASHING_MACHINE,
+            [
+                ("fault_f1", Value::Fault(Fault::Ok), 0),
+                (
+                    "operating_state",
+                    state(id629::OperatingState::ProgramIdle),
+                    0,
+                ),
+                (
+                    "selected_program",
+                    Value::from_enum(id629::Program::Cottons95),
+                    0,
+                ),
+                ("program_phase", phase(id629::ProgramPhase::Idle), 0),
+                ("temperature", Value::Sensor(15, 0), 0),
+                (
+                    "operating_state",
+                    state(id629::OperatingState::ProgramRunning),
+                    10,
+                ),
+                ("program_phase", phase(id629::ProgramPhase::MainWash), 10),
+                ("temperature", Value::Sensor(40, 40), 20),
+                ("fault_f1", Value::Fault(Fault::Active(None)), 30),
+                ("program_phase", phase(id629::ProgramPhase::Rinse1), 40),
+                ("temperature", Value::Sensor(25, 0), 40),
+                ("program_phase", phase(id629::ProgramPhase::FinalSpin), 50),
+                ("tachometer_speed", Value::Sensor(1200, 1400), 50),
+                ("tachometer_speed", Value::Sensor(1400, 1400), 60),
+                (
+                    "operating_state",
+                    state(id629::OperatingState::ProgramFinished),
+                    70,
+                ),
+                ("temperature", Value::Sensor(60, 0), 80),
+            ],
+        ) {
+            summaries.extend(tracker.update(&change));
+        }
+
+        assert_eq!(
+            tracker.properties().len(),
+            6,
+            "only relevant properties should be used"
+        );
+        assert_eq!(
+            summaries,
+            [CycleSummary {
+                program: Some(Value::from_enum(id629::Program::Cottons95)),
+                options: None,
+                start: Duration::from_secs(10),
+                end: Duration::from_secs(70),
+                phases: vec![
+                    PhaseTime {
+                        phase: "MainWash",
+                        duration: Duration::from_secs(30),
+                    },
+                    PhaseTime {
+                        phase: "Rinse1",
+                        duration: Duration::from_secs(10),
+                    },
+                    PhaseTime {
+                        phase: "FinalSpin",
+                        duration: Duration::from_secs(20),
+                    },
+                ],
+                steps: Vec::new(),
+                peak_temperature: Some(40),
+                water_pulses: None,
+                max_spin_speed: Some(1400),
+                faults: vec![&WASHING_MACHINE[0]],
+            }],
+            "cycle summary should be correct"
+        );
+    }
+
+    #[tokio::test]
+    async fn dishwasher_cycle() -> Result<(), Infallible> {
+        init_logger();
+
+        let phase = |phase: id517::ProgramPhase| Value::from_enum(phase);
+        let tracker = CycleTracker::new(&DISHWASHER);
+        let changes = changes(
+            &DISHWASHER,
+            [
+                // The run in progress when tracking starts is ignored
+                ("program_phase", phase(id517::ProgramPhase::MainWash), 0),
+                ("flow_meter_pulses", Value::Sensor(120, 600), 0),
+                ("program_phase", phase(id517::ProgramPhase::Finish), 5),
+                ("program_phase", phase(id517::ProgramPhase::Idle), 10),
+                ("program_phase", phase(id517::ProgramPhase::PreWash), 20),
+                ("flow_meter_pulses", Value::Sensor(300, 600), 25),
+                // The counter is reset for the next water intake
+                ("flow_meter_pulses", Value::Sensor(50, 800), 30),
+                ("flow_meter_pulses", Value::Sensor(400, 800), 35),
+                ("program_phase", phase(id517::ProgramPhase::MainWash), 40),
+                ("program_phase", phase(id517::ProgramPhase::Finish), 60),
+            ],
+        );
+        let summaries: Vec<_> = tracker
+            .track(stream::iter(changes.into_iter().map(Ok::<_, Infallible>)))
+            .collect()
+            .await;
+        let summary = summaries[0].clone()?;
+
+        assert_eq!(summaries.len(), 1, "one cycle should be detected");
+        assert_eq!(
+            (summary.start, summary.duration()),
+            (Duration::from_secs(20), Duration::from_secs(40)),
+            "cycle time should be correct"
+        );
+        assert_eq!(
+            summary.phases,
+            [
+                PhaseTime {
+                    phase: "PreWash",
+                    duration: Duration::from_secs(20),
+                },
+                PhaseTime {
+                    phase: "MainWash",
+                    duration: Duration::from_secs(20),
+                },
+            ],
+            "phase times should be correct"
+        );
+        assert_eq!(
+            (summary.water_pulses, summary.water_amount()),
+            (Some(580), Some(2900)),
+            "water amount should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn estimate_remaining_time() {
+        init_logger();
+
+        let state = |state: id629::OperatingState| Value::from_enum(state);
+        let phase = |phase: id629::ProgramPhase| Value::from_enum(phase);
+        let run = |start| {
+            [
+                (
+                    "operating_state",
+                    state(id629::OperatingState::ProgramRunning),
+                    start,
+                ),
+                ("program_phase", phase(id629::ProgramPhase::MainWash), start),
+                (
+                    "program_phase",
+                    phase(id629::ProgramPhase::FinalSpin),
+                    start + 40,
+                ),
+                (
+                    "operating_state",
+                    state(id629::OperatingState::ProgramFinished),
+                    start + 60,
+                ),
+                (
+                    "program_phase",
+                    phase(id629::ProgramPhase::Idle),
+                    start + 60,
+                ),
+            ]
+        };
+        let mut tracker = CycleTracker::new(&WASHING_MACHINE);
+        let mut remaining = Vec::new();
+
+        for change in changes(
+            &WASHING_MACHINE,
+            [
+                (
+                    "operating_state",
+                    state(id629::OperatingState::ProgramIdle),
+                    0,
+                ),
+                (
+                    "selected_program",
+                    Value::from_enum(id629::Program::Cottons95),
+                    0,
+                ),
+                ("program_phase", phase(id629::ProgramPhase::Idle), 0),
+            ]
+            .into_iter()
+            .chain(run(10))
+            .chain(run(100)),
+        ) {
+            let now = change.time + Duration::from_secs(10);
+
+            tracker.update(&change);
+            remaining.push(tracker.remaining_time(now).map(|time| time.as_secs()));
+
+            if change.time == Duration::from_secs(140) {
+                assert_eq!(
+                    tracker.query_estimate(&PROP_ESTIMATED_END, now),
+                    Some(Value::Duration(Duration::from_secs(160))),
+                    "estimated end should be correct"
+                );
+            }
+        }
+
+        // The second run is estimated from the first one
+        assert_eq!(
+            remaining,
+            [
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(50),
+                Some(50),
+                Some(10),
+                None,
+                None,
+            ],
+            "remaining times should be correct"
+        );
+    }
+
+    #[test]
+    fn estimate_from_elapsed_time() {
+        init_logger();
+
+        let mut tracker = CycleTracker::new(&DISHWASHER);
+
+        tracker.learn(&CycleSummary {
+            program: None,
+            options: None,
+            start: Duration::ZERO,
+            end: Duration::from_secs(3600),
+            phases: Vec::new(),
+            steps: Vec::new(),
+            peak_temperature: None,
+            water_pulses: None,
+            max_spin_speed: None,
+            faults: Vec::new(),
+        });
+
+        // The run in progress is estimated using the elapsed time reported by the device
+        for change in changes(
+            &DISHWASHER,
+            [
+                (
+                    "program_elapsed_time",
+                    Value::Duration(Duration::from_secs(1200)),
+                    0,
+                ),
+                (
+                    "program_phase",
+                    Value::from_enum(id517::ProgramPhase::MainWash),
+                    0,
+                ),
+            ],
+        ) {
+            tracker.update(&change);
+        }
+
+        assert!(tracker.is_running(), "program should be running");
+        assert_eq!(
+            tracker.query_estimate(&PROP_REMAINING_TIME, Duration::ZERO),
+            Some(Value::Duration(Duration::from_secs(2400))),
+            "remaining time should be correct"
+        );
+    }
+
+    #[tokio::test]
+    async fn simulated_dishwasher_cycle() -> device::Result<(), Infallible> {
+        init_logger();
+
+        // Program phase at 0x006a, program step at 0x020d, flow meter pulses at 0x0088
+        let ticks = Rc::new(Cell::new(0));
+        let port = ScriptedPort {
+            sim: Simulator::new(605, 0x1234, 0x5678).with_ram(0x0000, [0x00; 0x0300]),
+            ticks: ticks.clone(),
+            script: &[
+                (0, 0x00af, &[0x01]),
+                (2, 0x006a, &[0x02]),
+                (2, 0x020d, &[0x01]),
+                (4, 0x020d, &[0x02]),
+                (4, 0x0088, &[0x64, 0x00]),
+                (6, 0x006a, &[0x04]),
+                (6, 0x020d, &[0x03]),
+                (9, 0x006a, &[0x09]),
+                (9, 0x020d, &[0x00]),
+            ],
+        };
+        let mut dev = device::connect(port).await?;
+        let tracker = CycleTracker::new(dev.properties());
+        let watcher = PropertyWatcher::new(Duration::from_secs(1))
+            .watch(tracker.properties().iter().copied());
+        let clock_ticks = ticks.clone();
+        let changes = watcher.stream(&mut *dev, TickingDelay(ticks), move || {
+            Duration::from_secs(clock_ticks.get().into())
+        });
+        let summary = pin!(tracker.track(changes)).next().await.unwrap()?;
+
+        assert_eq!(
+            summary.program,
+            Some(Value::from_enum(id605::Program::Universal55)),
+            "program should be correct"
+        );
+        assert_eq!(
+            (summary.start, summary.duration()),
+            (Duration::from_secs(2), Duration::from_secs(7)),
+            "cycle time should be correct"
+        );
+        assert_eq!(
+            summary.phases,
+            [
+                PhaseTime {
+                    phase: "PreWash1",
+                    duration: Duration::from_secs(4),
+                },
+                PhaseTime {
+                    phase: "MainWash",
+                    duration: Duration::from_secs(3),
+                },
+            ],
+            "phase times should be correct"
+        );
+        assert_eq!(
+            summary.steps,
+            [
+                StepTime {
+                    step: 1,
+                    duration: Duration::from_secs(2),
+                },
+                StepTime {
+                    step: 2,
+                    duration: Duration::from_secs(2),
+                },
+                StepTime {
+                    step: 3,
+                    duration: Duration::from_secs(3),
+                },
+            ],
+            "step times should be correct"
+        );
+        assert_eq!(
+            summary.water_pulses,
+            Some(100),
+            "water pulses should be correct"
+        );
+
+        Ok(())
+    }
+}
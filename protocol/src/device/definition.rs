@@ -0,0 +1,1284 @@
+//! Data-driven device support using definitions loaded at runtime.
+//!
+//! A [`Definition`] describes a device in the TOML format: the supported software IDs,
+//! the diagnostic keys, the ROM unlock write, and the memory locations of its
+//! properties, faults and actions. A [`DefinedDevice`] interprets a definition
+//! and implements [`Device`], which allows adding support for new software IDs
+//! without recompiling the crate.
+//! Definitions can also be registered in a [`DeviceRegistry`](crate::device::DeviceRegistry)
+//! to be used by [`DeviceRegistry::connect_with`](crate::device::DeviceRegistry::connect_with).
+//!
+//! ```toml
+//! software_ids = [605]
+//! kind = "dishwasher"
+//!
+//! [keys]
+//! read = 0x1234
+//! full = 0x5678
+//!
+//! [rom_unlock]
+//! addr = 0x00f4
+//! val = 0x02
+//!
+//! [[properties]]
+//! kind = "operation"
+//! id = "program_phase"
+//! name = "Program Phase"
+//! addr = 0x006a
+//! values = { 0 = "Idle", 1 = "Reactivation", 2 = "PreWash1" }
+//!
+//! [[faults]]
+//! id = "fault_f4"
+//! name = "F4: Heater"
+//! active = { addr = 0x0053, mask = 0x02 }
+//! stored = { addr = 0x0082, mask = 0x08 }
+//!
+//! [[actions]]
+//! id = "start_program"
+//! name = "Start Program"
+//! addr = 0x0084
+//! value = 0x05
+//! require = { addr = 0x0084, value = 0x02 }
+//! ```
+//!
+//! # Properties
+//!
+//! Each property reads `len` bytes (default `1`) from `addr` and supports the following fields:
+//!
+//! - `memory`: `ram` (default) or `eeprom`.
+//! - `endian`: byte order of multi-byte values, `little` (default) or `big`.
+//! - `mask` and `shift`: applied to the raw value, in this order.
+//! - `decoder`: `none` (default), `bcd`, `ntc_resistance`, `bool` or `ascii`.
+//! - `scale`: factor the decoded value is multiplied with.
+//! - `duration`: unit of a duration value, `seconds`, `minutes` or `hours`.
+//! - `values`: names of enumeration values, returned as an enumeration.
+//! - `flags`: names of individual bits, returned as a set of flags.
+//! - `signed`: interprets the `len` bytes as a two's complement number,
+//!   returned as a signed integer. Cannot be combined with `mask`, `shift` or a `decoder`.
+//! - `decimals`: number of decimal places (at most 9), returned as a fixed-point decimal.
+//! - `target`: address of a target value with the same layout, returned as a sensor reading.
+//!
+//! # Faults
+//!
+//! Faults are reported as active if any bit of the `active` mask is set,
+//! otherwise as stored if any bit of the `stored` mask is set.
+//! Both locations are optional and support the `memory` field.
+//!
+//! # Actions
+//!
+//! Actions write `len` bytes (default `1`) to `addr`, using the `memory` and `endian` fields.
+//! Either a fixed `value` is written or the user chooses from the `options`,
+//! an array of tables with a `name` and a `value` each.
+//! The optional `require` condition checks a byte (`addr`, `mask` and `value`)
+//! before writing and fails with [`Error::InvalidState`] if it does not match.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{
+//!     device::{Device, Value, definition::{DefinedDevice, Definition}},
+//!     simulator::Simulator,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), core::convert::Infallible> {
+//! let def: Definition = r#"
+//!     software_ids = [1234]
+//!     kind = "washing_machine"
+//!     keys = { read = 0x1111, full = 0x2222 }
+//!
+//!     [[properties]]
+//!     kind = "operation"
+//!     id = "program_step"
+//!     name = "Program Step"
+//!     addr = 0x0010
+//! "#
+//! .parse()
+//! .unwrap();
+//! let mut sim = Simulator::new(1234, 0x1111, 0x2222).with_ram(0x0000, [0x07; 0x20]);
+//! let mut dev = DefinedDevice::connect_with(freemdu::Interface::new(&mut sim), def).await?;
+//! let prop = &dev.properties()[0];
+//!
+//! assert_eq!(dev.query_property(prop).await?, Value::Number(7));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::device::{
+    Action, ActionKind, ActionParameters, Device, DeviceFactory, DeviceKind, Error, FactoryFuture,
+    Fault, Interface, Property, PropertyKind, Result, Value, utils,
+};
+use crate::{KeyPair, RomUnlock, UnlockSequence};
+use alloc::{
+    boxed::Box,
+    collections::btree_map::BTreeMap,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Formatter},
+    ops::Range,
+    str::{self, FromStr},
+    time::Duration,
+};
+use embedded_io_async::{Read, Write};
+use serde::Deserialize;
+
+/// Device definition loaded at runtime.
+///
+/// Definitions are parsed from the TOML format described in the [module documentation](self).
+/// As [`Device`] hands out static property and action tables,
+/// the memory of a parsed definition is never freed.
+/// Definitions should therefore be parsed once, e.g. at startup.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    software_ids: &'static [u16],
+    kind: DeviceKind,
+    unlock_seq: UnlockSequence,
+    properties: &'static [Property],
+    actions: &'static [Action],
+    property_specs: &'static [PropertySpec],
+    property_spans: &'static [&'static [Range<u32>]],
+    action_specs: &'static [ActionSpec],
+}
+
+impl Definition {
+    /// Returns the software IDs supported by the definition.
+    #[must_use]
+    pub fn software_ids(&self) -> &'static [u16] {
+        self.software_ids
+    }
+
+    /// Returns `true` if the definition supports the specified software ID.
+    #[must_use]
+    pub fn is_compatible(&self, software_id: u16) -> bool {
+        self.software_ids.contains(&software_id)
+    }
+
+    /// Returns the kind of the defined device.
+    #[must_use]
+    pub fn kind(&self) -> DeviceKind {
+        self.kind
+    }
+
+    /// Returns the sequence used to unlock the device's diagnostic interface.
+    #[must_use]
+    pub fn unlock_sequence(&self) -> UnlockSequence {
+        self.unlock_seq
+    }
+
+    /// Returns the properties of the defined device, including its faults.
+    #[must_use]
+    pub fn properties(&self) -> &'static [Property] {
+        self.properties
+    }
+
+    /// Returns the actions of the defined device.
+    #[must_use]
+    pub fn actions(&self) -> &'static [Action] {
+        self.actions
+    }
+}
+
+/// Error returned when parsing an invalid definition.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ParseDefinitionError {
+    /// The definition is not valid TOML or does not match the expected structure.
+    Syntax(String),
+    /// The property or fault with the specified ID is invalid.
+    InvalidProperty(String),
+    /// The action with the specified ID is invalid.
+    InvalidAction(String),
+}
+
+impl Display for ParseDefinitionError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::Syntax(msg) => write!(f, "invalid definition: {msg}"),
+            Self::InvalidProperty(id) => write!(f, "invalid definition of property {id}"),
+            Self::InvalidAction(id) => write!(f, "invalid definition of action {id}"),
+        }
+    }
+}
+
+impl core::error::Error for ParseDefinitionError {}
+
+impl FromStr for Definition {
+    type Err = ParseDefinitionError;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let raw: RawDefinition =
+            toml::from_str(s).map_err(|err| ParseDefinitionError::Syntax(err.to_string()))?;
+        let mut properties = Vec::new();
+        let mut property_specs = Vec::new();
+        let mut actions = Vec::new();
+        let mut action_specs = Vec::new();
+
+        for prop in raw.properties {
+            let spec = PropertySpec::Value(prop.spec()?);
+
+            properties.push(Property {
+                kind: prop.kind.into(),
+                id: leak(prop.id),
+                name: leak(prop.name),
+                unit: prop.unit.map(leak),
+            });
+            property_specs.push(spec);
+        }
+
+        for fault in raw.faults {
+            if fault.active.is_none() && fault.stored.is_none() {
+                return Err(ParseDefinitionError::InvalidProperty(fault.id));
+            }
+
+            properties.push(Property {
+                kind: PropertyKind::Fault,
+                id: leak(fault.id),
+                name: leak(fault.name),
+                unit: None,
+            });
+            property_specs.push(PropertySpec::Fault {
+                active: fault.active,
+                stored: fault.stored,
+            });
+        }
+
+        for action in raw.actions {
+            let (spec, params) = action.spec()?;
+
+            actions.push(Action {
+                kind: action.kind.into(),
+                id: leak(action.id),
+                name: leak(action.name),
+                params,
+            });
+            action_specs.push(spec);
+        }
+
+        if let Some(id) = find_duplicate(properties.iter().map(|prop| prop.id)) {
+            return Err(ParseDefinitionError::InvalidProperty(id.into()));
+        }
+
+        if let Some(id) = find_duplicate(actions.iter().map(|action| action.id)) {
+            return Err(ParseDefinitionError::InvalidAction(id.into()));
+        }
+
+        Ok(Self {
+            software_ids: raw.software_ids.leak(),
+            kind: raw.kind.into(),
+            unlock_seq: UnlockSequence {
+                keys: KeyPair::new(raw.keys.read, raw.keys.full),
+                rom_unlock: raw.rom_unlock.map(|unlock| RomUnlock {
+                    addr: unlock.addr,
+                    val: unlock.val,
+                }),
+            },
+            properties: properties.leak(),
+            actions: actions.leak(),
+            property_spans: property_specs
+                .iter()
+                .map(|spec| &*spec.memory_spans().leak())
+                .collect::<Vec<_>>()
+                .leak(),
+            property_specs: property_specs.leak(),
+            action_specs: action_specs.leak(),
+        })
+    }
+}
+
+impl<'a, P: 'a + Read + Write> DeviceFactory<'a, P> for Definition {
+    fn initialize<'f>(&'f self, intf: Interface<P>, software_id: u16) -> FactoryFuture<'f, 'a, P>
+    where
+        'a: 'f,
+    {
+        Box::pin(async move {
+            Ok(
+                Box::new(DefinedDevice::initialize(intf, software_id, self.clone()).await?)
+                    as Box<dyn Device<P>>,
+            )
+        })
+    }
+}
+
+/// Device implementation interpreting a [`Definition`].
+#[derive(Debug)]
+pub struct DefinedDevice<P> {
+    intf: Interface<P>,
+    software_id: u16,
+    def: Definition,
+}
+
+impl<P: Read + Write> DefinedDevice<P> {
+    /// Connects to a device using the specified definition.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::UnknownSoftwareId`] if the definition does not support the device's software ID.
+    pub async fn connect_with(mut intf: Interface<P>, def: Definition) -> Result<Self, P::Error> {
+        let id = intf.query_software_id().await?;
+
+        if !def.is_compatible(id) {
+            return Err(Error::UnknownSoftwareId(id));
+        }
+
+        Self::initialize(intf, id, def).await
+    }
+
+    async fn initialize(
+        mut intf: Interface<P>,
+        software_id: u16,
+        def: Definition,
+    ) -> Result<Self, P::Error> {
+        intf.unlock(def.unlock_seq).await?;
+
+        Ok(Self {
+            intf,
+            software_id,
+            def,
+        })
+    }
+
+    /// Returns the device's definition.
+    #[must_use]
+    pub fn definition(&self) -> &Definition {
+        &self.def
+    }
+
+    /// Reads bytes from the specified memory.
+    async fn read(&mut self, memory: Memory, addr: u32, len: usize) -> Result<Vec<u8>, P::Error> {
+        let mut data = vec![0x00; len];
+
+        match memory {
+            Memory::Ram => {
+                self.intf
+                    .read_memory_slice(addr, &mut data, |_, _| {})
+                    .await?;
+            }
+            Memory::Eeprom => {
+                let addr = addr.try_into().map_err(|_| Error::InvalidArgument)?;
+
+                self.intf
+                    .read_eeprom_slice(addr, &mut data, |_, _| {})
+                    .await?;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Writes bytes to the specified memory.
+    async fn write(&mut self, memory: Memory, addr: u32, data: &[u8]) -> Result<(), P::Error> {
+        match memory {
+            Memory::Ram => self.intf.write_memory_slice(addr, data, |_, _| {}).await?,
+            Memory::Eeprom => {
+                let addr = addr.try_into().map_err(|_| Error::InvalidArgument)?;
+
+                self.intf.write_eeprom_slice(addr, data, |_, _| {}).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads and decodes a numeric value.
+    async fn query_number(&mut self, spec: &ValueSpec, addr: u32) -> Result<u32, P::Error> {
+        let data = self.read(spec.memory, addr, spec.len).await?;
+        let raw = (spec.endian.decode(&data) & spec.mask) >> spec.shift;
+        let val = match spec.decoder {
+            Decoder::Bcd => utils::decode_bcd_value(raw),
+            Decoder::NtcResistance => utils::ntc_resistance_from_adc(raw.try_into()?),
+            Decoder::None | Decoder::Bool | Decoder::Ascii => raw,
+        };
+
+        Ok(val.saturating_mul(spec.scale))
+    }
+
+    /// Reads a signed two's complement value.
+    async fn query_integer(&mut self, spec: &ValueSpec) -> Result<i32, P::Error> {
+        let data = self.read(spec.memory, spec.addr, spec.len).await?;
+        let unused = 32 - 8 * u32::try_from(spec.len)?;
+        // Shift the sign bit into the most significant position and back to extend it
+        let val = i32::from_ne_bytes((spec.endian.decode(&data) << unused).to_ne_bytes()) >> unused;
+
+        Ok(val.saturating_mul(spec.scale.try_into()?))
+    }
+
+    /// Queries a property described by a value specification.
+    async fn query_value(&mut self, spec: &ValueSpec) -> Result<Value, P::Error> {
+        if spec.decoder == Decoder::Ascii {
+            let data = self.read(spec.memory, spec.addr, spec.len).await?;
+            let string = str::from_utf8(&data).map_err(|_| Error::UnexpectedMemoryValue)?;
+
+            return Ok(string.trim_end_matches(['\0', ' ']).to_string().into());
+        }
+
+        if spec.signed || spec.decimals > 0 {
+            let val = if spec.signed {
+                self.query_integer(spec).await?
+            } else {
+                self.query_number(spec, spec.addr).await?.try_into()?
+            };
+
+            return match spec.decimals {
+                0 => Ok(val.into()),
+                scale => Ok(Value::Decimal { value: val, scale }),
+            };
+        }
+
+        let val = self.query_number(spec, spec.addr).await?;
+
+        if spec.decoder == Decoder::Bool {
+            return Ok((val != 0).into());
+        }
+
+        if !spec.values.is_empty() {
+            let (_, name) = spec
+                .values
+                .iter()
+                .find(|(repr, _)| *repr == val)
+                .ok_or(Error::UnexpectedMemoryValue)?;
+
+            return Ok(Value::Enum {
+                value: name,
+                variants: spec.variants,
+            });
+        }
+
+        if !spec.flags.is_empty() {
+            let known = spec.flags.iter().fold(0, |bits, (bit, _)| bits | bit);
+
+            if val & !known != 0 {
+                return Err(Error::UnexpectedMemoryValue);
+            }
+
+            return Ok(Value::Flags {
+                set: spec
+                    .flags
+                    .iter()
+                    .filter(|(bit, _)| val & bit != 0)
+                    .map(|(_, name)| *name)
+                    .collect(),
+                all: spec.flags.iter().map(|(_, name)| *name).collect(),
+            });
+        }
+
+        if let Some(unit) = spec.duration {
+            return Ok(Duration::from_secs(u64::from(val) * unit.as_secs()).into());
+        }
+
+        match spec.target {
+            Some(target) => Ok((val, self.query_number(spec, target).await?).into()),
+            None => Ok(val.into()),
+        }
+    }
+
+    /// Queries a fault described by its active and stored locations.
+    async fn query_fault(
+        &mut self,
+        active: Option<&Bit>,
+        stored: Option<&Bit>,
+    ) -> Result<Fault, P::Error> {
+        if let Some(bit) = active {
+            if self.read(bit.memory, bit.addr, 1).await?[0] & bit.mask != 0x00 {
+                return Ok(Fault::Active(None));
+            }
+        }
+
+        if let Some(bit) = stored {
+            if self.read(bit.memory, bit.addr, 1).await?[0] & bit.mask != 0x00 {
+                return Ok(Fault::Stored(None));
+            }
+        }
+
+        Ok(Fault::Ok)
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<P: Read + Write> Device<P> for DefinedDevice<P> {
+    /// Always fails, as no definition is available.
+    ///
+    /// Use [`DefinedDevice::connect_with`] instead.
+    async fn connect(port: P) -> Result<Self, P::Error> {
+        let mut intf = Interface::new(port);
+        let id = intf.query_software_id().await?;
+
+        Err(Error::UnknownSoftwareId(id))
+    }
+
+    fn interface(&mut self) -> &mut Interface<P> {
+        &mut self.intf
+    }
+
+    fn software_id(&self) -> u16 {
+        self.software_id
+    }
+
+    fn kind(&self) -> DeviceKind {
+        self.def.kind
+    }
+
+    fn properties(&self) -> &'static [Property] {
+        self.def.properties
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        self.def.actions
+    }
+
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        self.def
+            .properties
+            .iter()
+            .position(|p| p == prop)
+            .map_or(&[], |idx| self.def.property_spans[idx])
+    }
+
+    async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
+        let idx = self
+            .def
+            .properties
+            .iter()
+            .position(|p| p == prop)
+            .ok_or(Error::UnknownProperty)?;
+
+        match &self.def.property_specs[idx] {
+            PropertySpec::Value(spec) => self.query_value(spec).await,
+            PropertySpec::Fault { active, stored } => Ok(self
+                .query_fault(active.as_ref(), stored.as_ref())
+                .await?
+                .into()),
+        }
+    }
+
+    async fn trigger_action(
+        &mut self,
+        action: &Action,
+        param: Option<&str>,
+    ) -> Result<(), P::Error> {
+        let idx = self
+            .def
+            .actions
+            .iter()
+            .position(|a| a == action)
+            .ok_or(Error::UnknownAction)?;
+        let spec = &self.def.action_specs[idx];
+        let val = match (spec.value, param) {
+            (Some(val), None) => val,
+            (None, Some(param)) => {
+                spec.options
+                    .iter()
+                    .find(|(name, _)| *name == param)
+                    .ok_or(Error::InvalidArgument)?
+                    .1
+            }
+            _ => return Err(Error::InvalidArgument),
+        };
+
+        if let Some(cond) = &spec.require {
+            if self.read(cond.memory, cond.addr, 1).await?[0] & cond.mask != cond.value {
+                return Err(Error::InvalidState);
+            }
+        }
+
+        let data = spec.endian.encode(val, spec.len);
+
+        self.write(spec.memory, spec.addr, &data).await
+    }
+}
+
+/// Interpretation of a property.
+#[derive(Debug)]
+enum PropertySpec {
+    Value(ValueSpec),
+    Fault {
+        active: Option<Bit>,
+        stored: Option<Bit>,
+    },
+}
+
+impl PropertySpec {
+    /// Returns the RAM ranges read when querying the property.
+    fn memory_spans(&self) -> Vec<Range<u32>> {
+        let span = |memory, addr: u32, len: usize| {
+            let len = u32::try_from(len).unwrap_or(u32::MAX);
+
+            (memory == Memory::Ram).then(|| addr..addr.saturating_add(len))
+        };
+
+        match self {
+            Self::Value(spec) => [Some(spec.addr), spec.target]
+                .into_iter()
+                .flatten()
+                .filter_map(|addr| span(spec.memory, addr, spec.len))
+                .collect(),
+            Self::Fault { active, stored } => [active, stored]
+                .into_iter()
+                .flatten()
+                .filter_map(|bit| span(bit.memory, bit.addr, 1))
+                .collect(),
+        }
+    }
+}
+
+/// Location and decoding of a property value.
+#[derive(Debug)]
+struct ValueSpec {
+    memory: Memory,
+    addr: u32,
+    len: usize,
+    endian: Endian,
+    mask: u32,
+    shift: u32,
+    decoder: Decoder,
+    scale: u32,
+    signed: bool,
+    decimals: u8,
+    duration: Option<DurationUnit>,
+    values: Vec<(u32, &'static str)>,
+    variants: &'static [&'static str],
+    flags: Vec<(u32, &'static str)>,
+    target: Option<u32>,
+}
+
+/// Location and values of an action.
+#[derive(Debug)]
+struct ActionSpec {
+    memory: Memory,
+    addr: u32,
+    len: usize,
+    endian: Endian,
+    value: Option<u32>,
+    options: Vec<(&'static str, u32)>,
+    require: Option<Condition>,
+}
+
+/// Memory containing a value.
+#[derive(Deserialize, PartialEq, Eq, Copy, Clone, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Memory {
+    #[default]
+    Ram,
+    Eeprom,
+}
+
+/// Byte order of a multi-byte value.
+#[derive(Deserialize, PartialEq, Eq, Copy, Clone, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Decodes up to four bytes into a number.
+    fn decode(self, data: &[u8]) -> u32 {
+        let fold = |val, byte: &u8| (val << 8) | u32::from(*byte);
+
+        match self {
+            Self::Little => data.iter().rev().fold(0, fold),
+            Self::Big => data.iter().fold(0, fold),
+        }
+    }
+
+    /// Encodes a number into the specified number of bytes.
+    fn encode(self, val: u32, len: usize) -> Vec<u8> {
+        match self {
+            Self::Little => val.to_le_bytes()[..len].to_vec(),
+            Self::Big => val.to_be_bytes()[4 - len..].to_vec(),
+        }
+    }
+}
+
+/// Decoder applied to a raw property value.
+#[derive(Deserialize, PartialEq, Eq, Copy, Clone, Default, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Decoder {
+    #[default]
+    None,
+    Bcd,
+    NtcResistance,
+    Bool,
+    Ascii,
+}
+
+/// Unit of a duration value.
+#[derive(Deserialize, PartialEq, Eq, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+enum DurationUnit {
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+impl DurationUnit {
+    /// Returns the number of seconds per unit.
+    fn as_secs(self) -> u64 {
+        match self {
+            Self::Seconds => 1,
+            Self::Minutes => 60,
+            Self::Hours => 3600,
+        }
+    }
+}
+
+/// Bit mask of a byte in memory.
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+struct Bit {
+    #[serde(default)]
+    memory: Memory,
+    addr: u32,
+    mask: u8,
+}
+
+/// Condition on the value of a byte in memory.
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+struct Condition {
+    #[serde(default)]
+    memory: Memory,
+    addr: u32,
+    #[serde(default = "full_mask")]
+    mask: u8,
+    value: u8,
+}
+
+/// Definition as represented in the TOML format.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawDefinition {
+    software_ids: Vec<u16>,
+    kind: RawDeviceKind,
+    keys: RawKeys,
+    rom_unlock: Option<RawRomUnlock>,
+    #[serde(default)]
+    properties: Vec<RawProperty>,
+    #[serde(default)]
+    faults: Vec<RawFault>,
+    #[serde(default)]
+    actions: Vec<RawAction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RawDeviceKind {
+    WashingMachine,
+    TumbleDryer,
+    WasherDryer,
+    Dishwasher,
+    Glasswasher,
+    CoffeeMachine,
+}
+
+impl From<RawDeviceKind> for DeviceKind {
+    fn from(kind: RawDeviceKind) -> Self {
+        match kind {
+            RawDeviceKind::WashingMachine => Self::WashingMachine,
+            RawDeviceKind::TumbleDryer => Self::TumbleDryer,
+            RawDeviceKind::WasherDryer => Self::WasherDryer,
+            RawDeviceKind::Dishwasher => Self::Dishwasher,
+            RawDeviceKind::Glasswasher => Self::Glasswasher,
+            RawDeviceKind::CoffeeMachine => Self::CoffeeMachine,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawKeys {
+    read: u16,
+    full: u16,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRomUnlock {
+    addr: u32,
+    val: u8,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RawPropertyKind {
+    General,
+    Operation,
+    Io,
+}
+
+impl From<RawPropertyKind> for PropertyKind {
+    fn from(kind: RawPropertyKind) -> Self {
+        match kind {
+            RawPropertyKind::General => Self::General,
+            RawPropertyKind::Operation => Self::Operation,
+            RawPropertyKind::Io => Self::Io,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProperty {
+    kind: RawPropertyKind,
+    id: String,
+    name: String,
+    unit: Option<String>,
+    #[serde(default)]
+    memory: Memory,
+    addr: u32,
+    #[serde(default = "single_byte")]
+    len: usize,
+    #[serde(default)]
+    endian: Endian,
+    mask: Option<u32>,
+    #[serde(default)]
+    shift: u32,
+    #[serde(default)]
+    decoder: Decoder,
+    scale: Option<u32>,
+    #[serde(default)]
+    signed: bool,
+    #[serde(default)]
+    decimals: u8,
+    duration: Option<DurationUnit>,
+    #[serde(default)]
+    values: BTreeMap<String, String>,
+    #[serde(default)]
+    flags: BTreeMap<String, String>,
+    target: Option<u32>,
+}
+
+impl RawProperty {
+    /// Validates the property and converts it into a value specification.
+    fn spec(&self) -> core::result::Result<ValueSpec, ParseDefinitionError> {
+        let invalid = || ParseDefinitionError::InvalidProperty(self.id.clone());
+        let len_valid = match self.decoder {
+            Decoder::Ascii => self.len > 0,
+            Decoder::NtcResistance => self.len == 1,
+            _ => (1..=4).contains(&self.len),
+        };
+        let numeric = matches!(
+            self.decoder,
+            Decoder::None | Decoder::Bcd | Decoder::NtcResistance
+        );
+        let conversions = usize::from(!self.values.is_empty())
+            + usize::from(!self.flags.is_empty())
+            + usize::from(self.duration.is_some())
+            + usize::from(self.target.is_some())
+            + usize::from(self.signed || self.decimals > 0);
+        let transformed = self.mask.is_some() || self.shift != 0 || self.scale.is_some();
+        let values = parse_names(&self.values).ok_or_else(invalid)?;
+
+        if !len_valid
+            || conversions > usize::from(numeric)
+            || (self.decoder == Decoder::Ascii && transformed)
+            || self.shift >= 32
+            || self.decimals > 9
+            || (self.signed
+                && (self.decoder != Decoder::None || self.mask.is_some() || self.shift != 0))
+            || (self.memory == Memory::Eeprom && self.addr > 0xffff)
+        {
+            return Err(invalid());
+        }
+
+        Ok(ValueSpec {
+            memory: self.memory,
+            addr: self.addr,
+            len: self.len,
+            endian: self.endian,
+            mask: self.mask.unwrap_or(u32::MAX),
+            shift: self.shift,
+            decoder: self.decoder,
+            scale: self.scale.unwrap_or(1),
+            signed: self.signed,
+            decimals: self.decimals,
+            duration: self.duration,
+            variants: values
+                .iter()
+                .map(|(_, name)| *name)
+                .collect::<Vec<_>>()
+                .leak(),
+            values,
+            flags: parse_names(&self.flags).ok_or_else(invalid)?,
+            target: self.target,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawFault {
+    id: String,
+    name: String,
+    active: Option<Bit>,
+    stored: Option<Bit>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+enum RawActionKind {
+    #[default]
+    Operation,
+    Calibration,
+}
+
+impl From<RawActionKind> for ActionKind {
+    fn from(kind: RawActionKind) -> Self {
+        match kind {
+            RawActionKind::Operation => Self::Operation,
+            RawActionKind::Calibration => Self::Calibration,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOption {
+    name: String,
+    value: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawAction {
+    #[serde(default)]
+    kind: RawActionKind,
+    id: String,
+    name: String,
+    #[serde(default)]
+    memory: Memory,
+    addr: u32,
+    #[serde(default = "single_byte")]
+    len: usize,
+    #[serde(default)]
+    endian: Endian,
+    value: Option<u32>,
+    #[serde(default)]
+    options: Vec<RawOption>,
+    require: Option<Condition>,
+}
+
+impl RawAction {
+    /// Validates the action and converts it into an action specification and its parameters.
+    fn spec(
+        &self,
+    ) -> core::result::Result<(ActionSpec, Option<ActionParameters>), ParseDefinitionError> {
+        let fits = |val: u32| self.len == 4 || val >> (self.len * 8) == 0;
+
+        if !(1..=4).contains(&self.len)
+            || self.value.is_some() != self.options.is_empty()
+            || !self.value.is_none_or(fits)
+            || !self.options.iter().all(|opt| fits(opt.value))
+            || (self.memory == Memory::Eeprom && self.addr > 0xffff)
+        {
+            return Err(ParseDefinitionError::InvalidAction(self.id.clone()));
+        }
+
+        let options: Vec<_> = self
+            .options
+            .iter()
+            .map(|opt| (leak(opt.name.clone()), opt.value))
+            .collect();
+        let params = (!options.is_empty()).then(|| {
+            let names: Vec<_> = options.iter().map(|(name, _)| *name).collect();
+
+            ActionParameters::Enumeration(names.leak())
+        });
+        let spec = ActionSpec {
+            memory: self.memory,
+            addr: self.addr,
+            len: self.len,
+            endian: self.endian,
+            value: self.value,
+            options,
+            require: self.require,
+        };
+
+        Ok((spec, params))
+    }
+}
+
+/// Default length of properties and actions.
+fn single_byte() -> usize {
+    1
+}
+
+/// Default mask of conditions.
+fn full_mask() -> u8 {
+    0xff
+}
+
+/// Parses a table of names keyed by decimal or hexadecimal numbers, sorted by number.
+fn parse_names(table: &BTreeMap<String, String>) -> Option<Vec<(u32, &'static str)>> {
+    let mut names = table
+        .iter()
+        .map(|(key, name)| {
+            let num = match key.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => key.parse().ok()?,
+            };
+
+            Some((num, leak(name.clone())))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    names.sort_unstable_by_key(|(num, _)| *num);
+
+    Some(names)
+}
+
+/// Returns the first identifier that occurs more than once.
+fn find_duplicate(ids: impl Iterator<Item = &'static str>) -> Option<&'static str> {
+    let mut seen = Vec::new();
+
+    ids.into_iter().find(|id| {
+        let dup = seen.contains(id);
+
+        seen.push(*id);
+        dup
+    })
+}
+
+/// Leaks a string to obtain a static reference.
+fn leak(string: String) -> &'static str {
+    Box::leak(string.into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EepromAddressing, device::id605, simulator::Simulator, tests::init_logger};
+    use core::convert::Infallible;
+
+    const DEFINITION: &str = r#"
+        software_ids = [1234]
+        kind = "dishwasher"
+        keys = { read = 0x1111, full = 0x2222 }
+
+        [[properties]]
+        kind = "general"
+        id = "model_number"
+        name = "Model Number"
+        memory = "eeprom"
+        addr = 0x0000
+        len = 6
+        decoder = "ascii"
+
+        [[properties]]
+        kind = "general"
+        id = "operating_time"
+        name = "Operating Time"
+        addr = 0x0010
+        len = 2
+        endian = "big"
+        decoder = "bcd"
+        duration = "hours"
+
+        [[properties]]
+        kind = "operation"
+        id = "program_phase"
+        name = "Program Phase"
+        addr = 0x0012
+        mask = 0xf0
+        shift = 4
+        values = { 0 = "Idle", 0x2 = "MainWash" }
+
+        [[properties]]
+        kind = "io"
+        id = "closed_switches"
+        name = "Closed Switches"
+        addr = 0x0013
+        flags = { 1 = "Door", 4 = "Float" }
+
+        [[properties]]
+        kind = "io"
+        id = "water_amount"
+        name = "Water Amount"
+        unit = "ml"
+        addr = 0x0014
+        len = 2
+        scale = 10
+        target = 0x0016
+
+        [[properties]]
+        kind = "io"
+        id = "temperature_offset"
+        name = "Temperature Offset"
+        unit = "°C"
+        addr = 0x001c
+        len = 2
+        signed = true
+        decimals = 1
+
+        [[faults]]
+        id = "fault_f1"
+        name = "F1: Heater"
+        active = { addr = 0x0018, mask = 0x01 }
+        stored = { addr = 0x0019, mask = 0x01 }
+
+        [[actions]]
+        id = "start_program"
+        name = "Start Program"
+        addr = 0x001a
+        value = 0x05
+        require = { addr = 0x001a, value = 0x02 }
+
+        [[actions]]
+        id = "set_spin_speed"
+        name = "Set Spin Speed"
+        addr = 0x001b
+        options = [{ name = "400", value = 0x08 }, { name = "800", value = 0x10 }]
+    "#;
+
+    #[test]
+    fn parse_errors() {
+        assert!(
+            matches!(
+                "software_ids = [".parse::<Definition>(),
+                Err(ParseDefinitionError::Syntax(_))
+            ),
+            "result should be syntax error"
+        );
+        assert_eq!(
+            DEFINITION
+                .replace("len = 6", "len = 6\nscale = 2")
+                .parse::<Definition>()
+                .err(),
+            Some(ParseDefinitionError::InvalidProperty("model_number".into())),
+            "result should be property error"
+        );
+        assert_eq!(
+            DEFINITION
+                .replace("\"fault_f1\"", "\"water_amount\"")
+                .parse::<Definition>()
+                .err(),
+            Some(ParseDefinitionError::InvalidProperty("water_amount".into())),
+            "result should be property error"
+        );
+        assert_eq!(
+            DEFINITION
+                .replace("signed = true", "signed = true\nmask = 0x7fff")
+                .parse::<Definition>()
+                .err(),
+            Some(ParseDefinitionError::InvalidProperty(
+                "temperature_offset".into()
+            )),
+            "result should be property error"
+        );
+        assert_eq!(
+            DEFINITION
+                .replace("value = 0x05", "value = 0x0105")
+                .parse::<Definition>()
+                .err(),
+            Some(ParseDefinitionError::InvalidAction("start_program".into())),
+            "result should be action error"
+        );
+    }
+
+    #[tokio::test]
+    async fn query_properties() -> Result<(), Infallible> {
+        init_logger();
+
+        let def: Definition = DEFINITION.parse().unwrap();
+        let mut ram = [0x00; 0x20];
+
+        ram[0x10..0x1a]
+            .copy_from_slice(&[0x12, 0x34, 0x2a, 0x05, 0x2c, 0x01, 0xf4, 0x01, 0x00, 0x01]);
+        ram[0x1c..0x1e].copy_from_slice(&[0xe7, 0xff]);
+
+        let mut sim = Simulator::new(1234, 0x1111, 0x2222)
+            .with_ram(0x0000, ram)
+            .with_eeprom(*b"W1234\0", EepromAddressing::Byte);
+        let mut dev = DefinedDevice::connect_with(Interface::new(&mut sim), def).await?;
+        let mut vals = Vec::new();
+
+        for prop in dev.properties() {
+            vals.push(dev.query_property(prop).await?);
+        }
+
+        assert_eq!(
+            vals,
+            [
+                Value::String("W1234".into()),
+                Value::Duration(Duration::from_secs(1234 * 3600)),
+                Value::Enum {
+                    value: "MainWash",
+                    variants: &["Idle", "MainWash"],
+                },
+                Value::Flags {
+                    set: vec!["Door", "Float"],
+                    all: vec!["Door", "Float"],
+                },
+                Value::Sensor(3000, 5000),
+                Value::Decimal {
+                    value: -25,
+                    scale: 1,
+                },
+                Value::Fault(Fault::Stored(None)),
+            ],
+            "property values should be correct"
+        );
+        assert_eq!(
+            dev.trigger_action(&dev.actions()[0], None).await,
+            Err(Error::InvalidState),
+            "result should be state error"
+        );
+        assert_eq!(
+            dev.trigger_action(&dev.actions()[1], Some("1200")).await,
+            Err(Error::InvalidArgument),
+            "result should be argument error"
+        );
+
+        dev.trigger_action(&dev.actions()[1], Some("800")).await?;
+
+        let mut data = [0x00; 2];
+
+        sim.read_memory(0x001a, &mut data);
+
+        assert_eq!(data, [0x00, 0x10], "memory contents should be correct");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reference_definition() -> Result<(), Infallible> {
+        init_logger();
+
+        let def: Definition = include_str!("../../definitions/id605.toml")
+            .parse()
+            .unwrap();
+        let mut ram = [0x00; 0x0300];
+
+        ram[0x006a] = 0x04;
+        ram[0x006f] = 0x03;
+        ram[0x0082] = 0x08;
+        ram[0x022a..0x022c].copy_from_slice(&[0x20, 0x80]);
+
+        let sim = || Simulator::new(605, 0x1234, 0x5678).with_ram(0x0000, ram);
+        let mut native = id605::Dishwasher::connect(sim()).await?;
+        let mut defined = DefinedDevice::connect_with(Interface::new(sim()), def).await?;
+
+        assert_eq!(
+            defined.definition().unlock_sequence(),
+            id605::UNLOCK_SEQUENCE,
+            "unlock sequence should be correct"
+        );
+
+        for prop in defined.properties() {
+            let Some(native_prop) = native.properties().iter().find(|p| p.id == prop.id) else {
+                panic!("property {} should exist", prop.id);
+            };
+
+            assert_eq!(
+                defined.query_property(prop).await?,
+                native.query_property(native_prop).await?,
+                "value of property {} should be correct",
+                prop.id
+            );
+        }
+
+        Ok(())
+    }
+}
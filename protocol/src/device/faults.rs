@@ -0,0 +1,353 @@
+//! Host-side fault history based on property changes.
+//!
+//! Most supported devices only report whether a fault is currently active or stored,
+//! without any information about when or how often it occurred. Devices that keep
+//! an ordered fault history return it from [`Device::query_fault_history`](super::Device::query_fault_history),
+//! which can be added to a log using [`FaultLog::extend_history`].
+//!
+//! A [`FaultLog`] consumes the changes reported by a [`PropertyWatcher`](super::watch::PropertyWatcher)
+//! and records each transition of a fault property, e.g. from active to stored,
+//! together with its timestamp and the device's total operating time.
+//!
+//! The recorded entries are used to derive the number of occurrences and the operating hours
+//! of the last occurrence, see [`FaultLog::info`]. The operating hours are only available
+//! if the device provides an `operating_time` property.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use freemdu::{
+//!     device::{faults::FaultLog, watch::PropertyWatcher},
+//!     serial::Delay,
+//! };
+//! use futures_util::StreamExt;
+//! use std::{pin::pin, time::{Duration, Instant}};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+//! let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+//! let mut dev = freemdu::device::connect(&mut port).await?;
+//! let start = Instant::now();
+//! let mut log = FaultLog::new(dev.properties());
+//! let watcher = PropertyWatcher::new(Duration::from_secs(5))
+//!     .watch(log.properties().iter().copied());
+//! let mut changes = pin!(watcher.stream(&mut *dev, Delay, move || start.elapsed()));
+//!
+//! while let Some(change) = changes.next().await {
+//!     if let Some(entry) = log.update(&change?) {
+//!         println!("{}: {:?} at {:?}", entry.property.name, entry.status, entry.time);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::device::{Fault, FaultInfo, Property, PropertyKind, Value, watch::PropertyChange};
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// Entry of a fault history.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FaultEntry {
+    /// Fault property.
+    pub property: &'static Property,
+    /// Status of the fault after the transition.
+    pub status: Fault,
+    /// Time of the transition, as returned by the watcher's clock.
+    ///
+    /// `None` for entries of the device's fault history, which doesn't record when faults occurred.
+    pub time: Option<Duration>,
+    /// Total operating time of the device at the transition, if known.
+    pub operating_time: Option<Duration>,
+}
+
+/// Log that records fault transitions from property changes.
+#[derive(Clone, Debug)]
+pub struct FaultLog {
+    props: Vec<&'static Property>,
+    operating_time: Option<Duration>,
+    entries: Vec<FaultEntry>,
+}
+
+impl FaultLog {
+    /// Constructs a new log for a device with the specified properties.
+    ///
+    /// Only fault properties and the `operating_time` property are used,
+    /// see [`FaultLog::properties`].
+    #[must_use]
+    pub fn new(props: &'static [Property]) -> Self {
+        Self {
+            props: props
+                .iter()
+                .filter(|prop| prop.kind == PropertyKind::Fault || prop.id == "operating_time")
+                .collect(),
+            operating_time: None,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the properties that need to be watched to record fault transitions.
+    #[must_use]
+    pub fn properties(&self) -> &[&'static Property] {
+        &self.props
+    }
+
+    /// Returns the recorded entries, ordered by time.
+    ///
+    /// Entries of the device's fault history come first, in the order provided by the device.
+    #[must_use]
+    pub fn entries(&self) -> &[FaultEntry] {
+        &self.entries
+    }
+
+    /// Adds the entries of the device's fault history, see [`Device::query_fault_history`](super::Device::query_fault_history).
+    ///
+    /// The entries are placed before the transitions recorded from property changes,
+    /// keeping their order. Entries of properties that are not relevant for the log are ignored.
+    pub fn extend_history(&mut self, entries: impl IntoIterator<Item = FaultEntry>) {
+        for entry in entries {
+            if self.props.contains(&entry.property) {
+                let idx = self.entries.partition_point(|e| e.time <= entry.time);
+
+                self.entries.insert(idx, entry);
+            }
+        }
+    }
+
+    /// Updates the log with a property change, returning the recorded entry if the fault status changed.
+    ///
+    /// The initial value of a fault is only recorded if the fault is not [`Fault::Ok`].
+    /// Changes of properties that are not relevant for the log are ignored.
+    pub fn update(&mut self, change: &PropertyChange) -> Option<&FaultEntry> {
+        match (&change.new, change.property.kind) {
+            (Value::Duration(time), _) if change.property.id == "operating_time" => {
+                self.operating_time = Some(*time);
+
+                None
+            }
+            (Value::Fault(status), PropertyKind::Fault) => {
+                if change.old.is_none() && *status == Fault::Ok {
+                    return None;
+                }
+
+                // Debounced changes may be reported with an earlier time
+                let time = Some(change.time);
+                let idx = self.entries.partition_point(|entry| entry.time <= time);
+
+                self.entries.insert(
+                    idx,
+                    FaultEntry {
+                        property: change.property,
+                        status: status.clone(),
+                        time,
+                        operating_time: self.operating_time,
+                    },
+                );
+
+                self.entries.get(idx)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the occurrence information of a fault derived from the recorded entries.
+    ///
+    /// Each transition to [`Fault::Active`] and each entry of the device's fault history
+    /// is counted as an occurrence.
+    /// Returns `None` if the fault has not occurred or the operating time
+    /// of its last occurrence is unknown.
+    #[must_use]
+    pub fn info(&self, prop: &Property) -> Option<FaultInfo> {
+        let mut occurrences = self.entries.iter().filter(|entry| {
+            entry.property == prop
+                && (entry.time.is_none() || matches!(entry.status, Fault::Active(_)))
+        });
+        let count = occurrences.clone().count();
+        let last = occurrences.next_back()?;
+        let hours = last.operating_time?.as_secs() / 3600;
+
+        Some(FaultInfo {
+            operating_hours: hours.try_into().unwrap_or(u32::MAX),
+            count: count.try_into().unwrap_or(u32::MAX),
+        })
+    }
+
+    /// Adds the occurrence information to a fault status reported by the device.
+    ///
+    /// Information already provided by the device is kept.
+    #[must_use]
+    pub fn annotate(&self, prop: &Property, status: Fault) -> Fault {
+        match status {
+            Fault::Active(None) => Fault::Active(self.info(prop)),
+            Fault::Stored(None) => Fault::Stored(self.info(prop)),
+            status => status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        device::{self, Result},
+        simulator::Simulator,
+        tests::init_logger,
+    };
+    use core::convert::Infallible;
+
+    static PROPERTIES: [Property; 3] = [
+        Property {
+            kind: PropertyKind::Fault,
+            id: "fault_f1",
+            name: "F1",
+            unit: None,
+        },
+        Property {
+            kind: PropertyKind::Fault,
+            id: "fault_f2",
+            name: "F2",
+            unit: None,
+        },
+        Property {
+            kind: PropertyKind::General,
+            id: "operating_time",
+            name: "Operating Time",
+            unit: None,
+        },
+    ];
+
+    fn change(idx: usize, old: Option<Value>, new: Value, secs: u64) -> PropertyChange {
+        PropertyChange {
+            property: &PROPERTIES[idx],
+            old,
+            new,
+            time: Duration::from_secs(secs),
+        }
+    }
+
+    #[test]
+    fn record_transitions() {
+        init_logger();
+
+        let hours = |hours: u64| Value::Duration(Duration::from_secs(hours * 3600));
+        let ok = Value::Fault(Fault::Ok);
+        let active = Value::Fault(Fault::Active(None));
+        let stored = Value::Fault(Fault::Stored(None));
+        let mut log = FaultLog::new(&PROPERTIES);
+
+        for change in [
+            change(2, None, hours(120), 0),
+            change(0, None, ok.clone(), 0),
+            change(1, None, stored.clone(), 0),
+            change(0, Some(ok.clone()), active.clone(), 10),
+            change(2, Some(hours(120)), hours(121), 20),
+            change(0, Some(active.clone()), stored.clone(), 30),
+            // Debounced change reported with the time it was first seen
+            change(1, Some(stored.clone()), ok.clone(), 25),
+            change(0, Some(stored.clone()), active.clone(), 40),
+        ] {
+            log.update(&change);
+        }
+
+        assert_eq!(
+            log.entries()
+                .iter()
+                .map(|entry| (entry.property.id, entry.time.unwrap().as_secs()))
+                .collect::<Vec<_>>(),
+            [
+                ("fault_f2", 0),
+                ("fault_f1", 10),
+                ("fault_f2", 25),
+                ("fault_f1", 30),
+                ("fault_f1", 40),
+            ],
+            "entries should be ordered by time"
+        );
+        assert_eq!(
+            log.annotate(&PROPERTIES[0], Fault::Active(None)),
+            Fault::Active(Some(FaultInfo {
+                operating_hours: 121,
+                count: 2,
+            })),
+            "fault information should be correct"
+        );
+        assert_eq!(
+            log.info(&PROPERTIES[1]),
+            None,
+            "fault without occurrences should have no information"
+        );
+    }
+
+    #[tokio::test]
+    async fn device_history() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut ram = [0x00; 0x0400];
+
+        // Operating time of 42:30 hours, F23 and F1 stored with an unused fault code 3 between them
+        ram[0x00c7..0x00cb].copy_from_slice(&[0x42, 0x00, 0x00, 0x30]);
+        ram[0x0213..0x0216].copy_from_slice(&[0x17, 0x03, 0x01]);
+
+        let sim = Simulator::new(517, 0x8542, 0x6567).with_ram(0x0000, ram);
+        let mut dev = device::connect(sim).await?;
+        let props = dev.properties();
+        let find = |id| props.iter().find(|prop| prop.id == id).unwrap();
+        let history = dev.query_fault_history().await?;
+
+        assert_eq!(
+            history
+                .iter()
+                .map(|entry| (entry.property.id, entry.time))
+                .collect::<Vec<_>>(),
+            [("fault_f23", None), ("fault_f1", None)],
+            "device history should follow the fault stack without unknown codes"
+        );
+
+        let mut log = FaultLog::new(props);
+        let stored = Value::Fault(Fault::Stored(None));
+
+        log.extend_history(history);
+
+        for change in [
+            PropertyChange {
+                property: find("operating_time"),
+                old: None,
+                new: dev.query_property(find("operating_time")).await?,
+                time: Duration::ZERO,
+            },
+            PropertyChange {
+                property: find("fault_f1"),
+                old: Some(stored.clone()),
+                new: Value::Fault(Fault::Active(None)),
+                time: Duration::from_secs(10),
+            },
+        ] {
+            log.update(&change);
+        }
+
+        assert_eq!(
+            log.entries()
+                .iter()
+                .map(|entry| entry.property.id)
+                .collect::<Vec<_>>(),
+            ["fault_f23", "fault_f1", "fault_f1"],
+            "device history should precede recorded transitions"
+        );
+        assert_eq!(
+            log.info(find("fault_f1")),
+            Some(FaultInfo {
+                operating_hours: 42,
+                count: 2,
+            }),
+            "fault information should be correct"
+        );
+        assert_eq!(
+            log.info(find("fault_f23")),
+            None,
+            "fault without known operating time should have no information"
+        );
+
+        Ok(())
+    }
+}
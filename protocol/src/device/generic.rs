@@ -0,0 +1,395 @@
+//! Generic device support for unknown software IDs.
+//!
+//! A [`GenericDevice`] unlocks the diagnostic interface of any device whose keys
+//! are known, either from a [`KeyDatabase`] or provided explicitly, and exposes
+//! raw properties useful as a starting point for supporting new appliances:
+//! the software ID, the detected microcontroller family, the EEPROM identity area
+//! and a configurable RAM window.
+//!
+//! Use [`device::connect_with_fallback`](crate::device::connect_with_fallback)
+//! to fall back to a generic device if the software ID is not supported.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{
+//!     KeyPair,
+//!     device::{Device, Value, generic::{GenericConfig, GenericDevice}},
+//!     simulator::Simulator,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), core::convert::Infallible> {
+//! let mut sim = Simulator::new(1234, 0x1111, 0x2222).with_ram(0x0000, [0x42; 0x0100]);
+//! let config = GenericConfig::new()
+//!     .with_keys(KeyPair::new(0x1111, 0x2222))
+//!     .with_ram_window(0x0000..0x0004);
+//! let mut dev = GenericDevice::connect_with(freemdu::Interface::new(&mut sim), config).await?;
+//!
+//! assert_eq!(dev.query_ram_window().await?, [0x42; 4]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::device::{
+    Action, Device, DeviceKind, Error, Interface, Property, PropertyKind, Result, Value,
+};
+use crate::{KeyPair, UnlockSequence, keys::KeyDatabase};
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Formatter, Write as _},
+    ops::Range,
+    slice,
+};
+use embedded_io_async::{Read, Write};
+
+/// Address of the reset vector of Mitsubishi 740 family microcontrollers.
+const RESET_VECTOR_ADDR: u32 = 0xfffc;
+
+const PROP_SOFTWARE_ID: Property = Property {
+    kind: PropertyKind::General,
+    id: "software_id",
+    name: "Software ID",
+    unit: None,
+};
+const PROP_MCU_FAMILY: Property = Property {
+    kind: PropertyKind::General,
+    id: "mcu_family",
+    name: "Microcontroller Family",
+    unit: None,
+};
+const PROP_EEPROM_IDENTITY: Property = Property {
+    kind: PropertyKind::General,
+    id: "eeprom_identity",
+    name: "EEPROM Identity Area",
+    unit: None,
+};
+const PROP_RAM_WINDOW: Property = Property {
+    kind: PropertyKind::Io,
+    id: "ram_window",
+    name: "RAM Window",
+    unit: None,
+};
+
+/// Microcontroller family of a device.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum McuFamily {
+    /// Mitsubishi 740 family, e.g. M37451 or M38079.
+    ///
+    /// Contains the reset vector read from ROM.
+    Mitsubishi740 {
+        /// Address of the reset routine.
+        reset_vector: u16,
+    },
+    /// The microcontroller family could not be detected.
+    ///
+    /// This is typically caused by a readout-protected ROM.
+    Unknown,
+}
+
+impl Display for McuFamily {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::Mitsubishi740 { reset_vector } => {
+                write!(f, "Mitsubishi 740 (reset vector {reset_vector:04x})")
+            }
+            Self::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Configuration of a generic device.
+#[derive(Clone, Debug)]
+pub struct GenericConfig {
+    keys: Option<KeyPair>,
+    db: KeyDatabase,
+    ram_window: Range<u32>,
+    eeprom_identity: Range<u16>,
+}
+
+impl GenericConfig {
+    /// Constructs a new configuration.
+    ///
+    /// By default, the keys are looked up in the [built-in key database](KeyDatabase::builtin),
+    /// the RAM window covers the zero page from `0x0000` to `0x00ff`
+    /// and the EEPROM identity area covers addresses `0x01ba` to `0x01cf`,
+    /// which contain the serial and model number on many boards.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            keys: None,
+            db: KeyDatabase::builtin(),
+            ram_window: 0x0000..0x0100,
+            eeprom_identity: 0x01ba..0x01d0,
+        }
+    }
+
+    /// Unlocks the device using the specified keys instead of a key database.
+    #[must_use]
+    pub fn with_keys(mut self, keys: KeyPair) -> Self {
+        self.keys = Some(keys);
+        self
+    }
+
+    /// Looks up the keys in the specified database.
+    #[must_use]
+    pub fn with_database(mut self, db: KeyDatabase) -> Self {
+        self.db = db;
+        self
+    }
+
+    /// Sets the memory range returned by the RAM window property.
+    #[must_use]
+    pub fn with_ram_window(mut self, range: Range<u32>) -> Self {
+        self.ram_window = range;
+        self
+    }
+
+    /// Sets the EEPROM range returned by the EEPROM identity property.
+    ///
+    /// Addresses are specified in the interface's EEPROM addressing mode.
+    #[must_use]
+    pub fn with_eeprom_identity(mut self, range: Range<u16>) -> Self {
+        self.eeprom_identity = range;
+        self
+    }
+}
+
+impl Default for GenericConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generic device implementation.
+#[derive(Debug)]
+pub struct GenericDevice<P> {
+    intf: Interface<P>,
+    software_id: u16,
+    keys: KeyPair,
+    config: GenericConfig,
+}
+
+impl<P: Read + Write> GenericDevice<P> {
+    pub(crate) async fn initialize(
+        mut intf: Interface<P>,
+        software_id: u16,
+        config: GenericConfig,
+    ) -> Result<Self, P::Error> {
+        let keys = match config.keys {
+            Some(keys) => {
+                intf.unlock(UnlockSequence {
+                    keys,
+                    rom_unlock: None,
+                })
+                .await?;
+
+                keys
+            }
+            None => intf.unlock_with_known_keys(&config.db).await?,
+        };
+
+        Ok(Self {
+            intf,
+            software_id,
+            keys,
+            config,
+        })
+    }
+
+    /// Connects to a device with any software ID using the specified configuration.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::Protocol`] with [`crate::Error::KeysNotFound`] if no key
+    ///   in the database unlocks the device.
+    pub async fn connect_with(
+        mut intf: Interface<P>,
+        config: GenericConfig,
+    ) -> Result<Self, P::Error> {
+        let id = intf.query_software_id().await?;
+
+        Self::initialize(intf, id, config).await
+    }
+
+    /// Returns the keys used to unlock the device.
+    #[must_use]
+    pub fn keys(&self) -> KeyPair {
+        self.keys
+    }
+
+    /// Detects the microcontroller family of the device.
+    ///
+    /// The detection is based on the reset vector, which is only readable
+    /// if the ROM is not readout-protected.
+    pub async fn query_mcu_family(&mut self) -> Result<McuFamily, P::Error> {
+        let reset_vector: u16 = self.intf.read_memory(RESET_VECTOR_ADDR).await?;
+
+        // All known ROMs start at or above 0x1000
+        match reset_vector {
+            0x0000..0x1000 | 0xffff => Ok(McuFamily::Unknown),
+            _ => Ok(McuFamily::Mitsubishi740 { reset_vector }),
+        }
+    }
+
+    /// Queries the contents of the EEPROM identity area.
+    pub async fn query_eeprom_identity(&mut self) -> Result<Vec<u8>, P::Error> {
+        let range = self.config.eeprom_identity.clone();
+        let len = range.end.saturating_sub(range.start).into();
+
+        Ok(self
+            .intf
+            .read_eeprom_vec(range.start, len, |_, _| {})
+            .await?)
+    }
+
+    /// Queries the contents of the RAM window.
+    pub async fn query_ram_window(&mut self) -> Result<Vec<u8>, P::Error> {
+        let range = self.config.ram_window.clone();
+        let len = range.end.saturating_sub(range.start) as usize;
+        let mut data = vec![0x00; len];
+
+        self.intf
+            .read_memory_slice(range.start, &mut data, |_, _| {})
+            .await?;
+
+        Ok(data)
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<P: Read + Write> Device<P> for GenericDevice<P> {
+    async fn connect(port: P) -> Result<Self, P::Error> {
+        Self::connect_with(Interface::new(port), GenericConfig::new()).await
+    }
+
+    fn interface(&mut self) -> &mut Interface<P> {
+        &mut self.intf
+    }
+
+    fn software_id(&self) -> u16 {
+        self.software_id
+    }
+
+    fn kind(&self) -> DeviceKind {
+        DeviceKind::Unknown
+    }
+
+    fn properties(&self) -> &'static [Property] {
+        &[
+            PROP_SOFTWARE_ID,
+            PROP_MCU_FAMILY,
+            PROP_EEPROM_IDENTITY,
+            PROP_RAM_WINDOW,
+        ]
+    }
+
+    fn actions(&self) -> &'static [Action] {
+        &[]
+    }
+
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        match *prop {
+            PROP_MCU_FAMILY => &[RESET_VECTOR_ADDR..RESET_VECTOR_ADDR + 2],
+            PROP_RAM_WINDOW => slice::from_ref(&self.config.ram_window),
+            _ => &[],
+        }
+    }
+
+    async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
+        match *prop {
+            // General
+            PROP_SOFTWARE_ID => Ok(self.software_id.into()),
+            PROP_MCU_FAMILY => Ok(self.query_mcu_family().await?.to_string().into()),
+            PROP_EEPROM_IDENTITY => Ok(format_hex(&self.query_eeprom_identity().await?).into()),
+            // Input/output
+            PROP_RAM_WINDOW => Ok(format_hex(&self.query_ram_window().await?).into()),
+            _ => Err(Error::UnknownProperty),
+        }
+    }
+
+    async fn trigger_action(
+        &mut self,
+        _action: &Action,
+        _param: Option<&str>,
+    ) -> Result<(), P::Error> {
+        Err(Error::UnknownAction)
+    }
+}
+
+/// Formats bytes as space-separated hexadecimal values.
+fn format_hex(data: &[u8]) -> String {
+    let mut string = String::new();
+
+    for (idx, byte) in data.iter().enumerate() {
+        if idx > 0 {
+            string.push(' ');
+        }
+
+        let _ = write!(string, "{byte:02x}");
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EepromAddressing, simulator::Simulator, tests::init_logger};
+    use core::convert::Infallible;
+
+    #[tokio::test]
+    async fn known_keys() -> Result<(), Infallible> {
+        init_logger();
+
+        // Unknown software ID using the keys of software ID 629
+        let mut sim = Simulator::new(1234, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0100])
+            .with_rom(RESET_VECTOR_ADDR, [0x3c, 0x8a])
+            .with_eeprom((0x00..0x20).collect::<Vec<_>>(), EepromAddressing::Byte);
+        let config = GenericConfig::new().with_eeprom_identity(0x0004..0x0008);
+        let mut dev = GenericDevice::connect_with(Interface::new(&mut sim), config).await?;
+        let mut vals = Vec::new();
+
+        for prop in dev.properties() {
+            vals.push(dev.query_property(prop).await?);
+        }
+
+        assert_eq!(
+            dev.keys(),
+            KeyPair::new(0x43ea, 0x1f02),
+            "keys should be correct"
+        );
+        assert_eq!(
+            vals[..3],
+            [
+                Value::Number(1234),
+                Value::String("Mitsubishi 740 (reset vector 8a3c)".into()),
+                Value::String("04 05 06 07".into()),
+            ],
+            "property values should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_keys() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = Simulator::new(1234, 0x1111, 0x2222);
+        let res = GenericDevice::connect_with(Interface::new(&mut sim), GenericConfig::new()).await;
+
+        assert!(
+            matches!(res, Err(Error::Protocol(crate::Error::KeysNotFound))),
+            "result should be keys not found error"
+        );
+
+        Ok(())
+    }
+}
@@ -10,16 +10,20 @@
 
 use crate::device::{
     Action, ActionKind, ActionParameters, Date, Device, DeviceKind, Error, Fault, Interface,
-    Property, PropertyKind, Result, Value, private, utils,
+    Property, PropertyKind, Result, Value, utils,
 };
+use crate::{KeyPair, RomUnlock, UnlockSequence};
 use alloc::{
     boxed::Box,
     string::{String, ToString},
+    vec,
 };
 use bitflags_derive::{FlagsDebug, FlagsDisplay, FlagsFromStr};
-use core::{str, time::Duration};
+use core::{ops::Range, str, time::Duration};
 use embedded_io_async::{Read, Write};
-use strum::{Display, EnumString, FromRepr, VariantNames};
+use strum::{
+    Display, EnumIter, EnumString, FromRepr, IntoEnumIterator, IntoStaticStr, VariantNames,
+};
 
 macro_rules! compatible_software_ids {
     () => {
@@ -28,6 +32,15 @@ macro_rules! compatible_software_ids {
 }
 pub(super) use compatible_software_ids;
 
+pub(crate) const UNLOCK_SEQUENCE: UnlockSequence = UnlockSequence {
+    keys: KeyPair::new(0x43ea, 0x1f02),
+    // Disable ROM readout protection to access memory above 0x5000
+    rom_unlock: Some(RomUnlock {
+        addr: 0x02b6,
+        val: 0x01,
+    }),
+};
+
 const PROP_SERIAL_NUMBER: Property = Property {
     kind: PropertyKind::General,
     id: "serial_number",
@@ -268,11 +281,23 @@ const ACTION_START_PROGRAM: Action = Action {
     name: "Start Program",
     params: None,
 };
+const ACTION_CLEAR_FAULTS: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_faults",
+    name: "Clear Stored Faults (Until Power Off)",
+    params: None,
+};
+const ACTION_CLEAR_FAULT: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_fault",
+    name: "Clear Stored Fault (Until Power Off)",
+    params: Some(ActionParameters::Enumeration(FaultCode::VARIANTS)),
+};
 
 /// Washing machine fault code.
 ///
 /// Each code represents a specific fault condition that can occur in the machine.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(EnumIter, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum FaultCode {
     /// NTC thermistor (temperature sensor) fault.
     NtcThermistor = 8,
@@ -298,7 +323,7 @@ pub enum FaultCode {
 ///
 /// Different modes can be entered by pressing specific button combinations
 /// when turning on the machine.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum OperatingState {
     /// The door is open. Washing programs cannot be started.
@@ -334,7 +359,7 @@ pub enum OperatingState {
 /// Washing machine program.
 ///
 /// Each variant represents a position of the machine's program selector knob.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum Program {
     /// Finish position (no program selected).
@@ -386,7 +411,7 @@ pub enum Program {
 /// Washing program type.
 ///
 /// Represents the general category of a washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramType {
     /// No program.
@@ -437,7 +462,9 @@ bitflags::bitflags! {
 /// Washing program spin setting.
 ///
 /// The actual spin speed depends on the machine's programming configuration.
-#[derive(FromRepr, Display, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(
+    FromRepr, Display, EnumString, VariantNames, IntoStaticStr, PartialEq, Eq, Copy, Clone, Debug,
+)]
 #[repr(u8)]
 pub enum SpinSetting {
     /// No spin.
@@ -460,7 +487,7 @@ pub enum SpinSetting {
 ///
 /// Phases may not always execute in the defined order and some phases
 /// may be skipped depending on the selected washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramPhase {
     /// Program has not started yet.
@@ -523,7 +550,7 @@ bitflags::bitflags! {
 ///
 /// The water diverter changes its position when the
 /// [`Actuator::WaterDiverter`] is activated.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum WaterDiverterPosition {
     /// Unknown position (diverter is moving).
@@ -550,11 +577,7 @@ impl<P: Read + Write> WashingMachine<P> {
         mut intf: Interface<P>,
         software_id: u16,
     ) -> Result<Self, P::Error> {
-        intf.unlock_read_access(0x43ea).await?;
-        intf.unlock_full_access(0x1f02).await?;
-
-        // Disable ROM readout protection to access memory above 0x5000
-        intf.write_memory(0x02b6, 0x01u8).await?;
+        intf.unlock(UNLOCK_SEQUENCE).await?;
 
         Ok(Self { intf, software_id })
     }
@@ -606,7 +629,14 @@ impl<P: Read + Write> WashingMachine<P> {
     /// Queries the ROM code of the machine's microcontroller.
     ///
     /// The ROM code is typically a small number, e.g. `4`.
+    ///
+    /// Returns [`Error::Unavailable`] in read-only mode,
+    /// as the ROM readout protection is not disabled.
     pub async fn query_rom_code(&mut self) -> Result<u8, P::Error> {
+        if self.intf.is_read_only() {
+            return Err(Error::Unavailable);
+        }
+
         Ok(self.intf.read_memory(0xffdb).await?)
     }
 
@@ -628,13 +658,9 @@ impl<P: Read + Write> WashingMachine<P> {
         ))
     }
 
-    /// Queries the status of a fault identified by its fault code.
-    ///
-    /// Faults may be either currently active or stored persistently in EEPROM
-    /// from a previous occurrence when the machine was powered off.
-    /// Returned faults do not include operating hours or occurrence count information.
-    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
-        let ((active_addr, active_mask), (stored_addr, stored_mask)) = match code {
+    /// Returns the active and stored bits of a fault as pairs of address and mask.
+    fn fault_bits(code: FaultCode) -> ((u32, u8), (u32, u8)) {
+        match code {
             FaultCode::PressureSensor => ((0x0072, 0x01), (0x004e, 0x01)),
             FaultCode::NtcThermistor => ((0x00cb, 0x20), (0x004e, 0x02)),
             FaultCode::Heater => ((0x007c, 0x40), (0x004e, 0x04)),
@@ -644,7 +670,16 @@ impl<P: Read + Write> WashingMachine<P> {
             FaultCode::Drainage => ((0x0044, 0x40), (0x004e, 0x40)),
             FaultCode::FinalSpinSpeed => ((0x0078, 0x01), (0x004e, 0x80)),
             FaultCode::Eeprom => ((0x004f, 0xd0), (0x004f, 0x01)),
-        };
+        }
+    }
+
+    /// Queries the status of a fault identified by its fault code.
+    ///
+    /// Faults may be either currently active or stored persistently in EEPROM
+    /// from a previous occurrence when the machine was powered off.
+    /// Returned faults do not include operating hours or occurrence count information.
+    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
+        let ((active_addr, active_mask), (stored_addr, stored_mask)) = Self::fault_bits(code);
 
         let active: u8 = self.intf.read_memory(active_addr).await?;
 
@@ -661,6 +696,21 @@ impl<P: Read + Write> WashingMachine<P> {
         }
     }
 
+    /// Clears stored faults, either all faults or only the specified one.
+    ///
+    /// Only the bits of the stored faults are cleared, leaving other bits in the same locations untouched.
+    /// The memory is read back afterwards to confirm that the faults have been cleared.
+    /// Faults that are still active may be stored again by the machine.
+    ///
+    /// Only the fault bits in RAM are cleared, as the location of their copy in EEPROM is unknown.
+    /// Cleared faults are therefore likely to reappear once the machine is powered on again.
+    pub async fn clear_faults(&mut self, code: Option<FaultCode>) -> Result<(), P::Error> {
+        let codes = code.map_or_else(|| FaultCode::iter().collect(), |code| vec![code]);
+        let bits = codes.into_iter().map(|code| Self::fault_bits(code).1);
+
+        utils::clear_memory_bits(&mut self.intf, bits).await
+    }
+
     /// Queries the operating state.
     pub async fn query_operating_state(&mut self) -> Result<OperatingState, P::Error> {
         OperatingState::from_repr(self.intf.read_memory(0x00d0).await?)
@@ -855,13 +905,20 @@ impl<P: Read + Write> WashingMachine<P> {
     ///
     /// The duty cycle ranges from `0 %` to `100 %`.
     pub async fn query_motor_pwm_duty_cycle(&mut self) -> Result<u8, P::Error> {
-        // The duty cycle determines the value of the timer registers TXL and TXH.
-        // However, this timer is only active if bit 5 at address 0x007a is set.
-        let duty: u8 = self.intf.read_memory(0x02b1).await?;
+        let duty = self.query_motor_pwm_duty().await?;
 
         Ok((u16::from(duty) * 100 / 0xff).try_into()?)
     }
 
+    /// Queries the raw PWM duty value of the drum motor.
+    ///
+    /// The value ranges from `0x00` to `0xff`, corresponding to a duty cycle of `0 %` to `100 %`.
+    pub async fn query_motor_pwm_duty(&mut self) -> Result<u8, P::Error> {
+        // The duty cycle determines the value of the timer registers TXL and TXH.
+        // However, this timer is only active if bit 5 at address 0x007a is set.
+        Ok(self.intf.read_memory(0x02b1).await?)
+    }
+
     /// Queries the target speed of the drum motor.
     ///
     /// The speed is provided in `rpm` (revolutions per minute).
@@ -977,9 +1034,48 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             ACTION_SET_PROGRAM_OPTIONS,
             ACTION_SET_PROGRAM_SPIN_SETTING,
             ACTION_START_PROGRAM,
+            ACTION_CLEAR_FAULTS,
+            ACTION_CLEAR_FAULT,
         ]
     }
 
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        match *prop {
+            // The ROM is not readable in read-only mode
+            PROP_ROM_CODE if !self.intf.is_read_only() => &[0xffdb..0xffdc],
+            PROP_OPERATING_TIME => &[0x0052..0x0056],
+            PROP_FAULT_F8 => &[0x00cb..0x00cc, 0x004e..0x004f],
+            PROP_FAULT_F10 | PROP_FAULT_F11 | PROP_FAULT_F63 => &[0x0044..0x0045, 0x004e..0x004f],
+            PROP_FAULT_F20 => &[0x007c..0x007d, 0x004e..0x004f],
+            PROP_FAULT_F41 => &[0x004f..0x0050],
+            PROP_FAULT_F50 => &[0x0073..0x0074, 0x004e..0x004f],
+            PROP_FAULT_F51 => &[0x0072..0x0073, 0x004e..0x004f],
+            PROP_FAULT_F56 => &[0x0078..0x0079, 0x004e..0x004f],
+            PROP_OPERATING_STATE => &[0x00d0..0x00d1],
+            PROP_SELECTED_PROGRAM => &[0x00b8..0x00b9],
+            PROP_PROGRAM_TYPE => &[0x00de..0x00df],
+            PROP_PROGRAM_TEMPERATURE => &[0x00df..0x00e0],
+            PROP_PROGRAM_OPTIONS => &[0x0058..0x0059],
+            PROP_PROGRAM_SPIN_SETTING => &[0x0057..0x0058],
+            PROP_PROGRAM_SPIN_SPEED => &[0x00e9..0x00ea],
+            PROP_PROGRAM_PHASE => &[0x00a5..0x00a6],
+            PROP_PROGRAM_LOCKED => &[0x0045..0x0046],
+            PROP_LOAD_LEVEL => &[0x004a..0x004b],
+            PROP_IMBALANCE_SPIN_SPEED_LIMIT => &[0x0200..0x0201],
+            PROP_DISPLAY_CONTENTS => &[0x00a1..0x00a5],
+            PROP_ACTIVE_ACTUATORS => &[0x0080..0x0082],
+            PROP_WATER_DIVERTER_POSITION => &[0x024b..0x024c],
+            PROP_NTC_RESISTANCE => &[0x01be..0x01bf],
+            PROP_TEMPERATURE => &[0x0136..0x0138],
+            PROP_PRESSURE_SENSOR_VALUE => &[0x02b5..0x02b6],
+            PROP_WATER_LEVEL => &[0x0082..0x0084],
+            PROP_MOTOR_PWM_DUTY_CYCLE => &[0x02b1..0x02b2],
+            PROP_MOTOR_TARGET_SPEED => &[0x00d8..0x00da],
+            PROP_TACHOMETER_SPEED => &[0x01a5..0x01a7, 0x018c..0x018e],
+            _ => &[],
+        }
+    }
+
     async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
         match *prop {
             // General
@@ -1000,16 +1096,16 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             PROP_FAULT_F56 => Ok(self.query_fault(FaultCode::FinalSpinSpeed).await?.into()),
             PROP_FAULT_F63 => Ok(self.query_fault(FaultCode::DetergentOverdose).await?.into()),
             // Operation
-            PROP_OPERATING_STATE => Ok(self.query_operating_state().await?.to_string().into()),
-            PROP_SELECTED_PROGRAM => Ok(self.query_selected_program().await?.to_string().into()),
-            PROP_PROGRAM_TYPE => Ok(self.query_program_type().await?.to_string().into()),
+            PROP_OPERATING_STATE => Ok(Value::from_enum(self.query_operating_state().await?)),
+            PROP_SELECTED_PROGRAM => Ok(Value::from_enum(self.query_selected_program().await?)),
+            PROP_PROGRAM_TYPE => Ok(Value::from_enum(self.query_program_type().await?)),
             PROP_PROGRAM_TEMPERATURE => Ok(self.query_program_temperature().await?.into()),
-            PROP_PROGRAM_OPTIONS => Ok(self.query_program_options().await?.to_string().into()),
+            PROP_PROGRAM_OPTIONS => Ok(Value::from_flags(self.query_program_options().await?)),
             PROP_PROGRAM_SPIN_SETTING => {
-                Ok(self.query_program_spin_setting().await?.to_string().into())
+                Ok(Value::from_enum(self.query_program_spin_setting().await?))
             }
             PROP_PROGRAM_SPIN_SPEED => Ok(self.query_program_spin_speed().await?.into()),
-            PROP_PROGRAM_PHASE => Ok(self.query_program_phase().await?.to_string().into()),
+            PROP_PROGRAM_PHASE => Ok(Value::from_enum(self.query_program_phase().await?)),
             PROP_PROGRAM_LOCKED => Ok(self.query_program_locked().await?.into()),
             PROP_LOAD_LEVEL => Ok(self.query_load_level().await?.into()),
             PROP_IMBALANCE_SPIN_SPEED_LIMIT => {
@@ -1017,17 +1113,18 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             }
             PROP_DISPLAY_CONTENTS => Ok(self.query_display_contents().await?.into()),
             // Input/output
-            PROP_ACTIVE_ACTUATORS => Ok(self.query_active_actuators().await?.to_string().into()),
-            PROP_WATER_DIVERTER_POSITION => Ok(self
-                .query_water_diverter_position()
-                .await?
-                .to_string()
-                .into()),
+            PROP_ACTIVE_ACTUATORS => Ok(Value::from_flags(self.query_active_actuators().await?)),
+            PROP_WATER_DIVERTER_POSITION => Ok(Value::from_enum(
+                self.query_water_diverter_position().await?,
+            )),
             PROP_NTC_RESISTANCE => Ok(self.query_ntc_resistance().await?.into()),
             PROP_TEMPERATURE => Ok(self.query_temperature().await?.into()),
             PROP_PRESSURE_SENSOR_VALUE => Ok(self.query_pressure_sensor_value().await?.into()),
             PROP_WATER_LEVEL => Ok(self.query_water_level().await?.into()),
-            PROP_MOTOR_PWM_DUTY_CYCLE => Ok(self.query_motor_pwm_duty_cycle().await?.into()),
+            PROP_MOTOR_PWM_DUTY_CYCLE => Ok(Value::Decimal {
+                value: utils::duty_cycle_from_pwm(self.query_motor_pwm_duty().await?).into(),
+                scale: 1,
+            }),
             PROP_MOTOR_TARGET_SPEED => Ok(self.query_motor_target_speed().await?.into()),
             PROP_TACHOMETER_SPEED => Ok(self.query_tachometer_speed().await?.into()),
             _ => Err(Error::UnknownProperty),
@@ -1052,9 +1149,15 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
                 None => self.start_program().await,
                 Some(_) => Err(Error::InvalidArgument),
             },
+            ACTION_CLEAR_FAULTS => match param {
+                None => self.clear_faults(None).await,
+                Some(_) => Err(Error::InvalidArgument),
+            },
+            ACTION_CLEAR_FAULT => match param {
+                Some(s) => self.clear_faults(Some(s.parse()?)).await,
+                None => Err(Error::InvalidArgument),
+            },
             _ => Err(Error::UnknownAction),
         }
     }
 }
-
-impl<P> private::Sealed for WashingMachine<P> {}
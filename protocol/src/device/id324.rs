@@ -10,16 +10,16 @@
 
 use crate::device::{
     Action, ActionKind, ActionParameters, Device, DeviceKind, Error, Fault, Interface, Property,
-    PropertyKind, Result, Value, private, utils,
-};
-use alloc::{
-    boxed::Box,
-    string::{String, ToString},
+    PropertyKind, Result, Value, utils,
 };
+use crate::{KeyPair, UnlockSequence};
+use alloc::{boxed::Box, string::String, vec};
 use bitflags_derive::{FlagsDebug, FlagsDisplay, FlagsFromStr};
-use core::{str, time::Duration};
+use core::{ops::Range, str, time::Duration};
 use embedded_io_async::{Read, Write};
-use strum::{Display, EnumString, FromRepr, VariantNames};
+use strum::{
+    Display, EnumIter, EnumString, FromRepr, IntoEnumIterator, IntoStaticStr, VariantNames,
+};
 
 macro_rules! compatible_software_ids {
     () => {
@@ -28,6 +28,11 @@ macro_rules! compatible_software_ids {
 }
 pub(super) use compatible_software_ids;
 
+pub(crate) const UNLOCK_SEQUENCE: UnlockSequence = UnlockSequence {
+    keys: KeyPair::new(0x43ea, 0x1f02),
+    rom_unlock: None,
+};
+
 const PROP_ROM_CODE: Property = Property {
     kind: PropertyKind::General,
     id: "rom_code",
@@ -226,11 +231,23 @@ const ACTION_START_PROGRAM: Action = Action {
     name: "Start Program",
     params: None,
 };
+const ACTION_CLEAR_FAULTS: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_faults",
+    name: "Clear Stored Faults (Until Power Off)",
+    params: None,
+};
+const ACTION_CLEAR_FAULT: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_fault",
+    name: "Clear Stored Fault (Until Power Off)",
+    params: Some(ActionParameters::Enumeration(FaultCode::VARIANTS)),
+};
 
 /// Washing machine fault code.
 ///
 /// Each code represents a specific fault condition that can occur in the machine.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(EnumIter, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum FaultCode {
     /// Analog pressure sensor fault.
     PressureSensor = 1,
@@ -256,7 +273,7 @@ pub enum FaultCode {
 ///
 /// Different modes can be entered by pressing specific button combinations
 /// when turning on the machine.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum OperatingState {
     /// The door is open. Washing programs cannot be started.
@@ -284,7 +301,7 @@ pub enum OperatingState {
 /// Washing machine program.
 ///
 /// Each variant represents a position of the machine's program selector knob.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum Program {
     /// Finish position (no program selected).
@@ -336,7 +353,7 @@ pub enum Program {
 /// Washing program type.
 ///
 /// Represents the general category of a washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramType {
     /// No program.
@@ -383,7 +400,9 @@ bitflags::bitflags! {
 /// Washing program spin setting.
 ///
 /// The actual spin speed depends on the machine's programming configuration.
-#[derive(FromRepr, Display, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(
+    FromRepr, Display, EnumString, VariantNames, IntoStaticStr, PartialEq, Eq, Copy, Clone, Debug,
+)]
 #[repr(u8)]
 pub enum SpinSetting {
     /// No spin.
@@ -408,7 +427,7 @@ pub enum SpinSetting {
 ///
 /// Phases may not always execute in the defined order and some phases
 /// may be skipped depending on the selected washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramPhase {
     /// Program has not started yet.
@@ -485,8 +504,7 @@ impl<P: Read + Write> WashingMachine<P> {
         mut intf: Interface<P>,
         software_id: u16,
     ) -> Result<Self, P::Error> {
-        intf.unlock_read_access(0x43ea).await?;
-        intf.unlock_full_access(0x1f02).await?;
+        intf.unlock(UNLOCK_SEQUENCE).await?;
 
         Ok(Self { intf, software_id })
     }
@@ -516,13 +534,9 @@ impl<P: Read + Write> WashingMachine<P> {
         ))
     }
 
-    /// Queries the status of a fault identified by its fault code.
-    ///
-    /// Faults may be either currently active or stored persistently in EEPROM
-    /// from a previous occurrence when the machine was powered off.
-    /// Returned faults do not include operating hours or occurrence count information.
-    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
-        let ((active_addr, active_mask), (stored_addr, stored_mask)) = match code {
+    /// Returns the active and stored bits of a fault as pairs of address and mask.
+    fn fault_bits(code: FaultCode) -> ((u32, u8), (u32, u8)) {
+        match code {
             FaultCode::PressureSensor => ((0x002e, 0x01), (0x000e, 0x01)),
             FaultCode::NtcThermistor => ((0x0084, 0x20), (0x000e, 0x02)),
             FaultCode::Heater => ((0x0038, 0x40), (0x000e, 0x04)),
@@ -532,7 +546,16 @@ impl<P: Read + Write> WashingMachine<P> {
             FaultCode::Drainage => ((0x0004, 0x40), (0x000e, 0x40)),
             FaultCode::FinalSpinSpeed => ((0x0034, 0x01), (0x000e, 0x80)),
             FaultCode::Eeprom => ((0x000f, 0xc0), (0x000f, 0x01)),
-        };
+        }
+    }
+
+    /// Queries the status of a fault identified by its fault code.
+    ///
+    /// Faults may be either currently active or stored persistently in EEPROM
+    /// from a previous occurrence when the machine was powered off.
+    /// Returned faults do not include operating hours or occurrence count information.
+    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
+        let ((active_addr, active_mask), (stored_addr, stored_mask)) = Self::fault_bits(code);
 
         let active: u8 = self.intf.read_memory(active_addr).await?;
 
@@ -549,6 +572,21 @@ impl<P: Read + Write> WashingMachine<P> {
         }
     }
 
+    /// Clears stored faults, either all faults or only the specified one.
+    ///
+    /// Only the bits of the stored faults are cleared, leaving other bits in the same locations untouched.
+    /// The memory is read back afterwards to confirm that the faults have been cleared.
+    /// Faults that are still active may be stored again by the machine.
+    ///
+    /// Only the fault bits in RAM are cleared, as the location of their copy in EEPROM is unknown.
+    /// Cleared faults are therefore likely to reappear once the machine is powered on again.
+    pub async fn clear_faults(&mut self, code: Option<FaultCode>) -> Result<(), P::Error> {
+        let codes = code.map_or_else(|| FaultCode::iter().collect(), |code| vec![code]);
+        let bits = codes.into_iter().map(|code| Self::fault_bits(code).1);
+
+        utils::clear_memory_bits(&mut self.intf, bits).await
+    }
+
     /// Queries the operating state.
     pub async fn query_operating_state(&mut self) -> Result<OperatingState, P::Error> {
         OperatingState::from_repr(self.intf.read_memory(0x0089).await?)
@@ -721,12 +759,19 @@ impl<P: Read + Write> WashingMachine<P> {
     ///
     /// The duty cycle ranges from `0 %` to `100 %`.
     pub async fn query_motor_pwm_duty_cycle(&mut self) -> Result<u8, P::Error> {
-        // The duty cycle determines the value of the PWM register PWML.
-        let duty: u8 = self.intf.read_memory(0x004f).await?;
+        let duty = self.query_motor_pwm_duty().await?;
 
         Ok((u16::from(duty) * 100 / 0xff).try_into()?)
     }
 
+    /// Queries the raw PWM duty value of the drum motor.
+    ///
+    /// The value ranges from `0x00` to `0xff`, corresponding to a duty cycle of `0 %` to `100 %`.
+    pub async fn query_motor_pwm_duty(&mut self) -> Result<u8, P::Error> {
+        // The duty cycle determines the value of the PWM register PWML.
+        Ok(self.intf.read_memory(0x004f).await?)
+    }
+
     /// Queries the current speed sensed by the tachometer generator and the target speed.
     ///
     /// The speed is provided in `rpm` (revolutions per minute).
@@ -830,9 +875,43 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             ACTION_SET_PROGRAM_OPTIONS,
             ACTION_SET_PROGRAM_SPIN_SETTING,
             ACTION_START_PROGRAM,
+            ACTION_CLEAR_FAULTS,
+            ACTION_CLEAR_FAULT,
         ]
     }
 
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        match *prop {
+            PROP_ROM_CODE => &[0xffdf..0xffe0],
+            PROP_OPERATING_TIME => &[0x0010..0x0014],
+            PROP_FAULT_F1 => &[0x002e..0x002f, 0x000e..0x000f],
+            PROP_FAULT_F2 => &[0x0084..0x0085, 0x000e..0x000f],
+            PROP_FAULT_F3 => &[0x0038..0x0039, 0x000e..0x000f],
+            PROP_FAULT_F4 => &[0x002f..0x0030, 0x000e..0x000f],
+            PROP_FAULT_F5 | PROP_FAULT_F6 | PROP_FAULT_F7 => &[0x0004..0x0005, 0x000e..0x000f],
+            PROP_FAULT_F8 => &[0x0034..0x0035, 0x000e..0x000f],
+            PROP_FAULT_F9 => &[0x000f..0x0010],
+            PROP_OPERATING_STATE => &[0x0089..0x008a],
+            PROP_SELECTED_PROGRAM => &[0x0071..0x0072],
+            PROP_PROGRAM_TYPE => &[0x009c..0x009d],
+            PROP_PROGRAM_TEMPERATURE => &[0x009d..0x009e],
+            PROP_PROGRAM_OPTIONS => &[0x0016..0x0017],
+            PROP_BUZZER_ENABLED | PROP_PROGRAM_LOCKED => &[0x0005..0x0006],
+            PROP_PROGRAM_SPIN_SETTING => &[0x0015..0x0016],
+            PROP_PROGRAM_SPIN_SPEED => &[0x00a8..0x00a9],
+            PROP_PROGRAM_PHASE => &[0x005e..0x005f],
+            PROP_LOAD_LEVEL => &[0x000a..0x000b],
+            PROP_DISPLAY_CONTENTS => &[0x005a..0x005e],
+            PROP_ACTIVE_ACTUATORS => &[0x0039..0x003b],
+            PROP_NTC_RESISTANCE => &[0x0171..0x0172],
+            PROP_TEMPERATURE => &[0x0135..0x0137],
+            PROP_WATER_LEVEL => &[0x003b..0x003d],
+            PROP_MOTOR_PWM_DUTY_CYCLE => &[0x004f..0x0050],
+            PROP_TACHOMETER_SPEED => &[0x0091..0x0096],
+            _ => &[],
+        }
+    }
+
     async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
         match *prop {
             // General
@@ -849,26 +928,29 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             PROP_FAULT_F8 => Ok(self.query_fault(FaultCode::FinalSpinSpeed).await?.into()),
             PROP_FAULT_F9 => Ok(self.query_fault(FaultCode::Eeprom).await?.into()),
             // Operation
-            PROP_OPERATING_STATE => Ok(self.query_operating_state().await?.to_string().into()),
-            PROP_SELECTED_PROGRAM => Ok(self.query_selected_program().await?.to_string().into()),
-            PROP_PROGRAM_TYPE => Ok(self.query_program_type().await?.to_string().into()),
+            PROP_OPERATING_STATE => Ok(Value::from_enum(self.query_operating_state().await?)),
+            PROP_SELECTED_PROGRAM => Ok(Value::from_enum(self.query_selected_program().await?)),
+            PROP_PROGRAM_TYPE => Ok(Value::from_enum(self.query_program_type().await?)),
             PROP_PROGRAM_TEMPERATURE => Ok(self.query_program_temperature().await?.into()),
-            PROP_PROGRAM_OPTIONS => Ok(self.query_program_options().await?.to_string().into()),
+            PROP_PROGRAM_OPTIONS => Ok(Value::from_flags(self.query_program_options().await?)),
             PROP_BUZZER_ENABLED => Ok(self.query_buzzer_enabled().await?.into()),
             PROP_PROGRAM_SPIN_SETTING => {
-                Ok(self.query_program_spin_setting().await?.to_string().into())
+                Ok(Value::from_enum(self.query_program_spin_setting().await?))
             }
             PROP_PROGRAM_SPIN_SPEED => Ok(self.query_program_spin_speed().await?.into()),
-            PROP_PROGRAM_PHASE => Ok(self.query_program_phase().await?.to_string().into()),
+            PROP_PROGRAM_PHASE => Ok(Value::from_enum(self.query_program_phase().await?)),
             PROP_PROGRAM_LOCKED => Ok(self.query_program_locked().await?.into()),
             PROP_LOAD_LEVEL => Ok(self.query_load_level().await?.into()),
             PROP_DISPLAY_CONTENTS => Ok(self.query_display_contents().await?.into()),
             // Input/output
-            PROP_ACTIVE_ACTUATORS => Ok(self.query_active_actuators().await?.to_string().into()),
+            PROP_ACTIVE_ACTUATORS => Ok(Value::from_flags(self.query_active_actuators().await?)),
             PROP_NTC_RESISTANCE => Ok(self.query_ntc_resistance().await?.into()),
             PROP_TEMPERATURE => Ok(self.query_temperature().await?.into()),
             PROP_WATER_LEVEL => Ok(self.query_water_level().await?.into()),
-            PROP_MOTOR_PWM_DUTY_CYCLE => Ok(self.query_motor_pwm_duty_cycle().await?.into()),
+            PROP_MOTOR_PWM_DUTY_CYCLE => Ok(Value::Decimal {
+                value: utils::duty_cycle_from_pwm(self.query_motor_pwm_duty().await?).into(),
+                scale: 1,
+            }),
             PROP_TACHOMETER_SPEED => Ok(self.query_tachometer_speed().await?.into()),
             _ => Err(Error::UnknownProperty),
         }
@@ -892,9 +974,15 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
                 None => self.start_program().await,
                 Some(_) => Err(Error::InvalidArgument),
             },
+            ACTION_CLEAR_FAULTS => match param {
+                None => self.clear_faults(None).await,
+                Some(_) => Err(Error::InvalidArgument),
+            },
+            ACTION_CLEAR_FAULT => match param {
+                Some(s) => self.clear_faults(Some(s.parse()?)).await,
+                None => Err(Error::InvalidArgument),
+            },
             _ => Err(Error::UnknownAction),
         }
     }
 }
-
-impl<P> private::Sealed for WashingMachine<P> {}
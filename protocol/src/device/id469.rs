@@ -10,16 +10,16 @@
 
 use crate::device::{
     Action, ActionKind, ActionParameters, Date, Device, DeviceKind, Error, Fault, Interface,
-    Property, PropertyKind, Result, Value, private, utils,
-};
-use alloc::{
-    boxed::Box,
-    string::{String, ToString},
+    Property, PropertyKind, Result, Value, utils,
 };
+use crate::{KeyPair, RomUnlock, UnlockSequence};
+use alloc::{boxed::Box, string::String, vec};
 use bitflags_derive::{FlagsDebug, FlagsDisplay, FlagsFromStr};
-use core::{str, time::Duration};
+use core::{ops::Range, str, time::Duration};
 use embedded_io_async::{Read, Write};
-use strum::{Display, EnumString, FromRepr, VariantNames};
+use strum::{
+    Display, EnumIter, EnumString, FromRepr, IntoEnumIterator, IntoStaticStr, VariantNames,
+};
 
 macro_rules! compatible_software_ids {
     () => {
@@ -28,6 +28,15 @@ macro_rules! compatible_software_ids {
 }
 pub(super) use compatible_software_ids;
 
+pub(crate) const UNLOCK_SEQUENCE: UnlockSequence = UnlockSequence {
+    keys: KeyPair::new(0x43ea, 0x1f02),
+    // Disable ROM readout protection to access memory above 0x3000
+    rom_unlock: Some(RomUnlock {
+        addr: 0x02e9,
+        val: 0x01,
+    }),
+};
+
 const PROP_MANUFACTURING_DATE: Property = Property {
     kind: PropertyKind::General,
     id: "manufacturing_date",
@@ -250,11 +259,23 @@ const ACTION_START_PROGRAM: Action = Action {
     name: "Start Program",
     params: None,
 };
+const ACTION_CLEAR_FAULTS: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_faults",
+    name: "Clear Stored Faults (Until Power Off)",
+    params: None,
+};
+const ACTION_CLEAR_FAULT: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_fault",
+    name: "Clear Stored Fault (Until Power Off)",
+    params: Some(ActionParameters::Enumeration(FaultCode::VARIANTS)),
+};
 
 /// Washing machine fault code.
 ///
 /// Each code represents a specific fault condition that can occur in the machine.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(EnumIter, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum FaultCode {
     /// Analog pressure sensor fault.
     PressureSensor = 1,
@@ -280,7 +301,7 @@ pub enum FaultCode {
 ///
 /// Different modes can be entered by pressing specific button combinations
 /// when turning on the machine.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum OperatingState {
     /// The door is open. Washing programs cannot be started.
@@ -310,7 +331,7 @@ pub enum OperatingState {
 /// Washing machine program.
 ///
 /// Each variant represents a position of the machine's program selector knob.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum Program {
     /// Finish position (no program selected).
@@ -362,7 +383,7 @@ pub enum Program {
 /// Washing program type.
 ///
 /// Represents the general category of a washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramType {
     /// No program.
@@ -411,7 +432,9 @@ bitflags::bitflags! {
 /// Washing program spin setting.
 ///
 /// The actual spin speed depends on the machine's programming configuration.
-#[derive(FromRepr, Display, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(
+    FromRepr, Display, EnumString, VariantNames, IntoStaticStr, PartialEq, Eq, Copy, Clone, Debug,
+)]
 #[repr(u8)]
 pub enum SpinSetting {
     /// No spin.
@@ -436,7 +459,7 @@ pub enum SpinSetting {
 ///
 /// Phases may not always execute in the defined order and some phases
 /// may be skipped depending on the selected washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramPhase {
     /// Program has not started yet.
@@ -493,7 +516,7 @@ bitflags::bitflags! {
 ///
 /// The water diverter changes its position when the
 /// [`Actuator::WaterDiverter`] is activated.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum WaterDiverterPosition {
     /// Door glass position.
@@ -518,11 +541,7 @@ impl<P: Read + Write> WashingMachine<P> {
         mut intf: Interface<P>,
         software_id: u16,
     ) -> Result<Self, P::Error> {
-        intf.unlock_read_access(0x43ea).await?;
-        intf.unlock_full_access(0x1f02).await?;
-
-        // Disable ROM readout protection to access memory above 0x3000
-        intf.write_memory(0x02e9, 0x01u8).await?;
+        intf.unlock(UNLOCK_SEQUENCE).await?;
 
         Ok(Self { intf, software_id })
     }
@@ -541,7 +560,14 @@ impl<P: Read + Write> WashingMachine<P> {
     /// Queries the ROM code of the machine's microcontroller.
     ///
     /// The ROM code is typically a small number, e.g. `4`.
+    ///
+    /// Returns [`Error::Unavailable`] in read-only mode,
+    /// as the ROM readout protection is not disabled.
     pub async fn query_rom_code(&mut self) -> Result<u8, P::Error> {
+        if self.intf.is_read_only() {
+            return Err(Error::Unavailable);
+        }
+
         Ok(self.intf.read_memory(0xffdb).await?)
     }
 
@@ -563,13 +589,9 @@ impl<P: Read + Write> WashingMachine<P> {
         ))
     }
 
-    /// Queries the status of a fault identified by its fault code.
-    ///
-    /// Faults may be either currently active or stored persistently in EEPROM
-    /// from a previous occurrence when the machine was powered off.
-    /// Returned faults do not include operating hours or occurrence count information.
-    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
-        let ((active_addr, active_mask), (stored_addr, stored_mask)) = match code {
+    /// Returns the active and stored bits of a fault as pairs of address and mask.
+    fn fault_bits(code: FaultCode) -> ((u32, u8), (u32, u8)) {
+        match code {
             FaultCode::PressureSensor => ((0x0070, 0x01), (0x004e, 0x01)),
             FaultCode::NtcThermistor => ((0x00c9, 0x20), (0x004e, 0x02)),
             FaultCode::Heater => ((0x007c, 0x40), (0x004e, 0x04)),
@@ -579,7 +601,16 @@ impl<P: Read + Write> WashingMachine<P> {
             FaultCode::Drainage => ((0x0044, 0x40), (0x004e, 0x40)),
             FaultCode::FinalSpinSpeed => ((0x0076, 0x01), (0x004e, 0x80)),
             FaultCode::Eeprom => ((0x004f, 0xd0), (0x004f, 0x01)),
-        };
+        }
+    }
+
+    /// Queries the status of a fault identified by its fault code.
+    ///
+    /// Faults may be either currently active or stored persistently in EEPROM
+    /// from a previous occurrence when the machine was powered off.
+    /// Returned faults do not include operating hours or occurrence count information.
+    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
+        let ((active_addr, active_mask), (stored_addr, stored_mask)) = Self::fault_bits(code);
 
         let active: u8 = self.intf.read_memory(active_addr).await?;
 
@@ -596,6 +627,21 @@ impl<P: Read + Write> WashingMachine<P> {
         }
     }
 
+    /// Clears stored faults, either all faults or only the specified one.
+    ///
+    /// Only the bits of the stored faults are cleared, leaving other bits in the same locations untouched.
+    /// The memory is read back afterwards to confirm that the faults have been cleared.
+    /// Faults that are still active may be stored again by the machine.
+    ///
+    /// Only the fault bits in RAM are cleared, as the location of their copy in EEPROM is unknown.
+    /// Cleared faults are therefore likely to reappear once the machine is powered on again.
+    pub async fn clear_faults(&mut self, code: Option<FaultCode>) -> Result<(), P::Error> {
+        let codes = code.map_or_else(|| FaultCode::iter().collect(), |code| vec![code]);
+        let bits = codes.into_iter().map(|code| Self::fault_bits(code).1);
+
+        utils::clear_memory_bits(&mut self.intf, bits).await
+    }
+
     /// Queries the operating state.
     pub async fn query_operating_state(&mut self) -> Result<OperatingState, P::Error> {
         OperatingState::from_repr(self.intf.read_memory(0x00ce).await?)
@@ -910,9 +956,47 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             ACTION_SET_PROGRAM_OPTIONS,
             ACTION_SET_PROGRAM_SPIN_SETTING,
             ACTION_START_PROGRAM,
+            ACTION_CLEAR_FAULTS,
+            ACTION_CLEAR_FAULT,
         ]
     }
 
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        match *prop {
+            // The ROM is not readable in read-only mode
+            PROP_ROM_CODE if !self.intf.is_read_only() => &[0xffdb..0xffdc],
+            PROP_OPERATING_TIME => &[0x0052..0x0056],
+            PROP_FAULT_F1 => &[0x0070..0x0071, 0x004e..0x004f],
+            PROP_FAULT_F2 => &[0x00c9..0x00ca, 0x004e..0x004f],
+            PROP_FAULT_F3 => &[0x007c..0x007d, 0x004e..0x004f],
+            PROP_FAULT_F4 => &[0x0071..0x0072, 0x004e..0x004f],
+            PROP_FAULT_F5 | PROP_FAULT_F6 | PROP_FAULT_F7 => &[0x0044..0x0045, 0x004e..0x004f],
+            PROP_FAULT_F8 => &[0x0076..0x0077, 0x004e..0x004f],
+            PROP_FAULT_F9 => &[0x004f..0x0050],
+            PROP_OPERATING_STATE => &[0x00ce..0x00cf],
+            PROP_SELECTED_PROGRAM => &[0x00b6..0x00b7],
+            PROP_PROGRAM_TYPE => &[0x00dd..0x00de],
+            PROP_PROGRAM_TEMPERATURE => &[0x00de..0x00df],
+            PROP_PROGRAM_OPTIONS => &[0x0058..0x0059],
+            PROP_BUZZER_ENABLED | PROP_PROGRAM_LOCKED => &[0x0045..0x0046],
+            PROP_PROGRAM_SPIN_SETTING => &[0x0057..0x0058],
+            PROP_PROGRAM_SPIN_SPEED => &[0x00e9..0x00ea],
+            PROP_PROGRAM_PHASE => &[0x00a4..0x00a5],
+            PROP_LOAD_LEVEL => &[0x004a..0x004b],
+            PROP_IMBALANCE_SPIN_SPEED_LIMIT => &[0x0260..0x0261],
+            PROP_DISPLAY_CONTENTS => &[0x00a0..0x00a4],
+            PROP_ACTIVE_ACTUATORS => &[0x007f..0x0081],
+            PROP_WATER_DIVERTER_POSITION => &[0x004b..0x004c],
+            PROP_NTC_RESISTANCE => &[0x01be..0x01bf],
+            PROP_TEMPERATURE => &[0x0136..0x0138],
+            PROP_PRESSURE_SENSOR_VALUE => &[0x02e5..0x02e6],
+            PROP_WATER_LEVEL => &[0x0081..0x0083],
+            PROP_MOTOR_REVERSED => &[0x00dc..0x00dd],
+            PROP_TACHOMETER_SPEED => &[0x00d3..0x00d8],
+            _ => &[],
+        }
+    }
+
     async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
         match *prop {
             // General
@@ -930,17 +1014,17 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             PROP_FAULT_F8 => Ok(self.query_fault(FaultCode::FinalSpinSpeed).await?.into()),
             PROP_FAULT_F9 => Ok(self.query_fault(FaultCode::Eeprom).await?.into()),
             // Operation
-            PROP_OPERATING_STATE => Ok(self.query_operating_state().await?.to_string().into()),
-            PROP_SELECTED_PROGRAM => Ok(self.query_selected_program().await?.to_string().into()),
-            PROP_PROGRAM_TYPE => Ok(self.query_program_type().await?.to_string().into()),
+            PROP_OPERATING_STATE => Ok(Value::from_enum(self.query_operating_state().await?)),
+            PROP_SELECTED_PROGRAM => Ok(Value::from_enum(self.query_selected_program().await?)),
+            PROP_PROGRAM_TYPE => Ok(Value::from_enum(self.query_program_type().await?)),
             PROP_PROGRAM_TEMPERATURE => Ok(self.query_program_temperature().await?.into()),
-            PROP_PROGRAM_OPTIONS => Ok(self.query_program_options().await?.to_string().into()),
+            PROP_PROGRAM_OPTIONS => Ok(Value::from_flags(self.query_program_options().await?)),
             PROP_BUZZER_ENABLED => Ok(self.query_buzzer_enabled().await?.into()),
             PROP_PROGRAM_SPIN_SETTING => {
-                Ok(self.query_program_spin_setting().await?.to_string().into())
+                Ok(Value::from_enum(self.query_program_spin_setting().await?))
             }
             PROP_PROGRAM_SPIN_SPEED => Ok(self.query_program_spin_speed().await?.into()),
-            PROP_PROGRAM_PHASE => Ok(self.query_program_phase().await?.to_string().into()),
+            PROP_PROGRAM_PHASE => Ok(Value::from_enum(self.query_program_phase().await?)),
             PROP_PROGRAM_LOCKED => Ok(self.query_program_locked().await?.into()),
             PROP_LOAD_LEVEL => Ok(self.query_load_level().await?.into()),
             PROP_IMBALANCE_SPIN_SPEED_LIMIT => {
@@ -948,12 +1032,10 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
             }
             PROP_DISPLAY_CONTENTS => Ok(self.query_display_contents().await?.into()),
             // Input/output
-            PROP_ACTIVE_ACTUATORS => Ok(self.query_active_actuators().await?.to_string().into()),
-            PROP_WATER_DIVERTER_POSITION => Ok(self
-                .query_water_diverter_position()
-                .await?
-                .to_string()
-                .into()),
+            PROP_ACTIVE_ACTUATORS => Ok(Value::from_flags(self.query_active_actuators().await?)),
+            PROP_WATER_DIVERTER_POSITION => Ok(Value::from_enum(
+                self.query_water_diverter_position().await?,
+            )),
             PROP_NTC_RESISTANCE => Ok(self.query_ntc_resistance().await?.into()),
             PROP_TEMPERATURE => Ok(self.query_temperature().await?.into()),
             PROP_PRESSURE_SENSOR_VALUE => Ok(self.query_pressure_sensor_value().await?.into()),
@@ -982,9 +1064,15 @@ impl<P: Read + Write> Device<P> for WashingMachine<P> {
                 None => self.start_program().await,
                 Some(_) => Err(Error::InvalidArgument),
             },
+            ACTION_CLEAR_FAULTS => match param {
+                None => self.clear_faults(None).await,
+                Some(_) => Err(Error::InvalidArgument),
+            },
+            ACTION_CLEAR_FAULT => match param {
+                Some(s) => self.clear_faults(Some(s.parse()?)).await,
+                None => Err(Error::InvalidArgument),
+            },
             _ => Err(Error::UnknownAction),
         }
     }
 }
-
-impl<P> private::Sealed for WashingMachine<P> {}
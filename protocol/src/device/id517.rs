@@ -9,14 +9,16 @@
 //! the device's software ID and return an appropriate device instance.
 
 use crate::device::{
-    Action, ActionKind, Device, DeviceKind, Error, Fault, Interface, Property, PropertyKind,
-    Result, Value, private, utils,
+    Action, ActionKind, ActionParameters, Device, DeviceKind, Error, Fault, Interface, Property,
+    PropertyKind, Result, Value, faults::FaultEntry, utils,
 };
-use alloc::{boxed::Box, string::ToString};
+use crate::{KeyPair, RomUnlock, UnlockSequence};
+use alloc::{boxed::Box, vec::Vec};
 use bitflags_derive::{FlagsDebug, FlagsDisplay};
-use core::{str, time::Duration};
+use core::{ops::Range, str, time::Duration};
 use embedded_io_async::{Read, Write};
-use strum::{Display, FromRepr};
+use log::debug;
+use strum::{Display, EnumString, FromRepr, IntoStaticStr, VariantNames};
 
 macro_rules! compatible_software_ids {
     () => {
@@ -25,6 +27,17 @@ macro_rules! compatible_software_ids {
 }
 pub(super) use compatible_software_ids;
 
+pub(crate) const UNLOCK_SEQUENCE: UnlockSequence = UnlockSequence {
+    keys: KeyPair::new(0x8542, 0x6567),
+    // Disable ROM readout protection to access memory above 0x8000.
+    // The protection is partially broken because the ROM already starts at 0x1000.
+    // Its logic was likely copied from a previous device with a smaller ROM.
+    rom_unlock: Some(RomUnlock {
+        addr: 0x00f4,
+        val: 0x02,
+    }),
+};
+
 const PROP_MACHINE_NUMBER: Property = Property {
     kind: PropertyKind::General,
     id: "machine_number",
@@ -212,11 +225,23 @@ const ACTION_START_PROGRAM: Action = Action {
     name: "Start Program",
     params: None,
 };
+const ACTION_CLEAR_FAULTS: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_faults",
+    name: "Clear Stored Faults (Until Power Off)",
+    params: None,
+};
+const ACTION_CLEAR_FAULT: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_fault",
+    name: "Clear Stored Fault (Until Power Off)",
+    params: Some(ActionParameters::Enumeration(FaultCode::VARIANTS)),
+};
 
 /// Glasswasher fault code.
 ///
 /// Each code represents a specific fault condition that can occur in the machine.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum FaultCode {
     /// Main wash water temperature T1 not reached within 1 hour.
@@ -260,7 +285,7 @@ pub enum FaultCode {
 /// Glasswashing program.
 ///
 /// Each variant represents a position of the machine's program selector knob.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum Program {
     /// Stop position (no program selected).
@@ -292,7 +317,7 @@ pub enum Program {
 /// Glasswashing program type.
 ///
 /// Represents the general category of a glasswashing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramType {
     /// No program.
@@ -318,7 +343,7 @@ pub enum ProgramType {
 /// Glasswashing program phase.
 ///
 /// Some phases may be skipped depending on the selected washing program.
-#[derive(Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum ProgramPhase {
     /// Program has not started yet.
     Idle,
@@ -420,13 +445,7 @@ impl<P: Read + Write> Glasswasher<P> {
         mut intf: Interface<P>,
         software_id: u16,
     ) -> Result<Self, P::Error> {
-        intf.unlock_read_access(0x8542).await?;
-        intf.unlock_full_access(0x6567).await?;
-
-        // Disable ROM readout protection to access memory above 0x8000.
-        // The protection is partially broken because the ROM already starts at 0x1000.
-        // Its logic was likely copied from a previous device with a smaller ROM.
-        intf.write_memory(0x00f4, 0x02u8).await?;
+        intf.unlock(UNLOCK_SEQUENCE).await?;
 
         Ok(Self { intf, software_id })
     }
@@ -507,6 +526,67 @@ impl<P: Read + Write> Glasswasher<P> {
         Ok(Fault::Ok)
     }
 
+    /// Queries the faults stored on the fault stack.
+    ///
+    /// The stack holds up to 3 faults, which are returned in the order they are stored in memory.
+    /// It is unknown whether this order corresponds to the time of occurrence.
+    /// Inlet and draining faults are never stored. Unknown fault codes are skipped.
+    pub async fn query_stored_faults(&mut self) -> Result<Vec<FaultCode>, P::Error> {
+        // Unused entries are set to zero, which is not a valid fault code
+        let stack: [u8; 3] = self.intf.read_memory(0x0213).await?;
+
+        Ok(stack
+            .into_iter()
+            .filter(|&entry| entry != 0x00)
+            .filter_map(|entry| {
+                let code = FaultCode::from_repr(entry);
+
+                if code.is_none() {
+                    debug!("Skipping unknown fault code {entry:#04x} on fault stack");
+                }
+
+                code
+            })
+            .collect())
+    }
+
+    /// Clears stored faults, either all faults or only the specified one.
+    ///
+    /// Stored faults are removed from the fault stack, keeping the order of the remaining faults.
+    /// The memory is read back afterwards to confirm that the faults have been cleared.
+    /// Inlet and draining faults are not stored, so clearing them has no effect.
+    /// Faults that are still active may be stored again by the glasswasher.
+    ///
+    /// Only the fault stack in RAM is cleared, as the location of its copy in EEPROM is unknown.
+    /// Cleared faults are therefore likely to reappear once the glasswasher is powered on again.
+    pub async fn clear_faults(&mut self, code: Option<FaultCode>) -> Result<(), P::Error> {
+        let stack: [u8; 3] = self.intf.read_memory(0x0213).await?;
+        let kept = stack
+            .into_iter()
+            .filter(|&entry| code.is_some_and(|code| entry != code as u8));
+        let mut cleared = [0x00; 3];
+
+        // Remaining faults are moved to the top of the stack,
+        // unused entries are set to zero, which is not a valid fault code
+        for (entry, code) in cleared.iter_mut().zip(kept) {
+            *entry = code;
+        }
+
+        if cleared == stack {
+            return Ok(());
+        }
+
+        self.intf.write_memory(0x0213, cleared).await?;
+
+        let stack: [u8; 3] = self.intf.read_memory(0x0213).await?;
+
+        if stack == cleared {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed)
+        }
+    }
+
     /// Queries the selected program.
     pub async fn query_selected_program(&mut self) -> Result<Program, P::Error> {
         Program::from_repr(self.intf.read_memory(0x0087).await?).ok_or(Error::UnexpectedMemoryValue)
@@ -632,6 +712,30 @@ impl<P: Read + Write> Glasswasher<P> {
             Err(Error::InvalidState)
         }
     }
+
+    /// Returns the fault property reporting the status of a fault code.
+    fn fault_property(code: FaultCode) -> &'static Property {
+        match code {
+            FaultCode::TemperatureMainWash => &PROP_FAULT_F1,
+            FaultCode::TemperatureFinalRinse => &PROP_FAULT_F2,
+            FaultCode::NtcThermistorOpen => &PROP_FAULT_F4,
+            FaultCode::NtcThermistorShort => &PROP_FAULT_F5,
+            FaultCode::SpeedSensor => &PROP_FAULT_F8,
+            FaultCode::MainsFrequency => &PROP_FAULT_F9,
+            FaultCode::ProgramSelector => &PROP_FAULT_F10,
+            FaultCode::ExternalModuleI2C => &PROP_FAULT_F11,
+            FaultCode::InsufficientSalt => &PROP_FAULT_F23,
+            FaultCode::MotorTriac => &PROP_FAULT_F24,
+            FaultCode::PressureSwitchStart => &PROP_FAULT_F25,
+            FaultCode::WaterOverflow => &PROP_FAULT_F26,
+            FaultCode::PressureSwitchDraining => &PROP_FAULT_FA,
+            FaultCode::WaterInletStart => &PROP_FAULT_F0E,
+            FaultCode::WaterInletEnd => &PROP_FAULT_F1E,
+            FaultCode::WaterInletDuration => &PROP_FAULT_F2E,
+            FaultCode::WaterInletPressure => &PROP_FAULT_F3E,
+            FaultCode::WaterInletInadvertent => &PROP_FAULT_F4E,
+        }
+    }
 }
 
 #[async_trait::async_trait(?Send)]
@@ -694,7 +798,36 @@ impl<P: Read + Write> Device<P> for Glasswasher<P> {
     }
 
     fn actions(&self) -> &'static [Action] {
-        &[ACTION_START_PROGRAM]
+        &[
+            ACTION_START_PROGRAM,
+            ACTION_CLEAR_FAULTS,
+            ACTION_CLEAR_FAULT,
+        ]
+    }
+
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        match *prop {
+            PROP_MACHINE_NUMBER => &[0x0234..0x0238],
+            PROP_OPERATING_TIME => &[0x00c7..0x00cb],
+            PROP_FAULT_F1 | PROP_FAULT_F2 | PROP_FAULT_F4 | PROP_FAULT_F5 | PROP_FAULT_F8 => {
+                &[0x0090..0x0091, 0x0213..0x0216]
+            }
+            PROP_FAULT_F9 | PROP_FAULT_F10 | PROP_FAULT_F11 | PROP_FAULT_F24 | PROP_FAULT_F25
+            | PROP_FAULT_F26 => &[0x0091..0x0092, 0x0213..0x0216],
+            PROP_FAULT_F23 => &[0x0093..0x0094, 0x0213..0x0216],
+            PROP_FAULT_FA | PROP_FAULT_F0E | PROP_FAULT_F1E | PROP_FAULT_F2E | PROP_FAULT_F3E
+            | PROP_FAULT_F4E => &[0x0095..0x0096],
+            PROP_SELECTED_PROGRAM => &[0x0087..0x0088],
+            PROP_PROGRAM_TYPE => &[0x0086..0x0087],
+            PROP_DRYING_ENABLED => &[0x0078..0x0079],
+            PROP_PROGRAM_PHASE | PROP_PROGRAM_STEP => &[0x006f..0x0070],
+            PROP_PROGRAM_ELAPSED_TIME => &[0x0076..0x0077],
+            PROP_ACTIVE_ACTUATORS => &[0x00e9..0x00ed],
+            PROP_CLOSED_SWITCHES => &[0x0059..0x005a],
+            PROP_NTC_RESISTANCE => &[0x005a..0x005b, 0x00b6..0x00b7],
+            PROP_FLOW_METER_PULSES => &[0x0070..0x0072, 0x00bc..0x00be],
+            _ => &[],
+        }
     }
 
     async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
@@ -746,21 +879,36 @@ impl<P: Read + Write> Device<P> for Glasswasher<P> {
                 .await?
                 .into()),
             // Operation
-            PROP_SELECTED_PROGRAM => Ok(self.query_selected_program().await?.to_string().into()),
-            PROP_PROGRAM_TYPE => Ok(self.query_program_type().await?.to_string().into()),
+            PROP_SELECTED_PROGRAM => Ok(Value::from_enum(self.query_selected_program().await?)),
+            PROP_PROGRAM_TYPE => Ok(Value::from_enum(self.query_program_type().await?)),
             PROP_DRYING_ENABLED => Ok(self.query_drying_enabled().await?.into()),
-            PROP_PROGRAM_PHASE => Ok(self.query_program_phase().await?.to_string().into()),
+            PROP_PROGRAM_PHASE => Ok(Value::from_enum(self.query_program_phase().await?)),
             PROP_PROGRAM_STEP => Ok(self.query_program_step().await?.into()),
             PROP_PROGRAM_ELAPSED_TIME => Ok(self.query_program_elapsed_time().await?.into()),
             // Input/output
-            PROP_ACTIVE_ACTUATORS => Ok(self.query_active_actuators().await?.to_string().into()),
-            PROP_CLOSED_SWITCHES => Ok(self.query_closed_switches().await?.to_string().into()),
+            PROP_ACTIVE_ACTUATORS => Ok(Value::from_flags(self.query_active_actuators().await?)),
+            PROP_CLOSED_SWITCHES => Ok(Value::from_flags(self.query_closed_switches().await?)),
             PROP_NTC_RESISTANCE => Ok(self.query_ntc_resistance().await?.into()),
             PROP_FLOW_METER_PULSES => Ok(self.query_flow_meter_pulses().await?.into()),
             _ => Err(Error::UnknownProperty),
         }
     }
 
+    async fn query_fault_history(&mut self) -> Result<Vec<FaultEntry>, P::Error> {
+        // The fault stack doesn't record when or how often a fault occurred
+        Ok(self
+            .query_stored_faults()
+            .await?
+            .into_iter()
+            .map(|code| FaultEntry {
+                property: Self::fault_property(code),
+                status: Fault::Stored(None),
+                time: None,
+                operating_time: None,
+            })
+            .collect())
+    }
+
     async fn trigger_action(
         &mut self,
         action: &Action,
@@ -771,9 +919,15 @@ impl<P: Read + Write> Device<P> for Glasswasher<P> {
                 None => self.start_program().await,
                 Some(_) => Err(Error::InvalidArgument),
             },
+            ACTION_CLEAR_FAULTS => match param {
+                None => self.clear_faults(None).await,
+                Some(_) => Err(Error::InvalidArgument),
+            },
+            ACTION_CLEAR_FAULT => match param {
+                Some(s) => self.clear_faults(Some(s.parse()?)).await,
+                None => Err(Error::InvalidArgument),
+            },
             _ => Err(Error::UnknownAction),
         }
     }
 }
-
-impl<P> private::Sealed for Glasswasher<P> {}
@@ -9,14 +9,17 @@
 //! the device's software ID and return an appropriate device instance.
 
 use crate::device::{
-    Action, ActionKind, Date, Device, DeviceKind, Error, Fault, Interface, Property, PropertyKind,
-    Result, Value, private, utils,
+    Action, ActionKind, ActionParameters, Date, Device, DeviceKind, Error, Fault, Interface,
+    Property, PropertyKind, Result, Value, utils,
 };
-use alloc::{boxed::Box, string::ToString};
+use crate::{KeyPair, RomUnlock, UnlockSequence};
+use alloc::{boxed::Box, vec};
 use bitflags_derive::{FlagsDebug, FlagsDisplay};
-use core::str;
+use core::{ops::Range, str};
 use embedded_io_async::{Read, Write};
-use strum::{Display, FromRepr};
+use strum::{
+    Display, EnumIter, EnumString, FromRepr, IntoEnumIterator, IntoStaticStr, VariantNames,
+};
 
 macro_rules! compatible_software_ids {
     () => {
@@ -25,6 +28,15 @@ macro_rules! compatible_software_ids {
 }
 pub(super) use compatible_software_ids;
 
+pub(crate) const UNLOCK_SEQUENCE: UnlockSequence = UnlockSequence {
+    keys: KeyPair::new(0x1234, 0x5678),
+    // Disable ROM readout protection to access memory above 0x8000
+    rom_unlock: Some(RomUnlock {
+        addr: 0x00f4,
+        val: 0x02,
+    }),
+};
+
 const PROP_MANUFACTURING_DATE: Property = Property {
     kind: PropertyKind::General,
     id: "manufacturing_date",
@@ -152,11 +164,23 @@ const ACTION_START_PROGRAM: Action = Action {
     name: "Start Program",
     params: None,
 };
+const ACTION_CLEAR_FAULTS: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_faults",
+    name: "Clear Stored Faults (Until Power Off)",
+    params: None,
+};
+const ACTION_CLEAR_FAULT: Action = Action {
+    kind: ActionKind::Operation,
+    id: "clear_fault",
+    name: "Clear Stored Fault (Until Power Off)",
+    params: Some(ActionParameters::Enumeration(FaultCode::VARIANTS)),
+};
 
 /// Dishwasher fault code.
 ///
 /// Each code represents a specific fault condition that can occur in the machine.
-#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(EnumIter, EnumString, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum FaultCode {
     /// NTC thermistor (temperature sensor) open circuit fault.
     NtcThermistorOpen = 1,
@@ -181,7 +205,7 @@ pub enum FaultCode {
 /// Dishwashing program.
 ///
 /// Each variant represents a position of the machine's program selector knob.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum Program {
     /// Stop position (no program selected).
@@ -213,7 +237,7 @@ pub enum Program {
 /// Dishwashing program type.
 ///
 /// Represents the general category of a dishwashing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramType {
     /// No program.
@@ -243,7 +267,7 @@ pub enum ProgramType {
 /// Dishwashing program phase.
 ///
 /// Some phases may be skipped depending on the selected washing program.
-#[derive(FromRepr, Display, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(FromRepr, Display, IntoStaticStr, VariantNames, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
 pub enum ProgramPhase {
     /// Program has not started yet.
@@ -334,11 +358,7 @@ impl<P: Read + Write> Dishwasher<P> {
         mut intf: Interface<P>,
         software_id: u16,
     ) -> Result<Self, P::Error> {
-        intf.unlock_read_access(0x1234).await?;
-        intf.unlock_full_access(0x5678).await?;
-
-        // Disable ROM readout protection to access memory above 0x8000
-        intf.write_memory(0x00f4, 0x02u8).await?;
+        intf.unlock(UNLOCK_SEQUENCE).await?;
 
         Ok(Self { intf, software_id })
     }
@@ -354,13 +374,9 @@ impl<P: Read + Write> Dishwasher<P> {
         ))
     }
 
-    /// Queries the status of a fault identified by its fault code.
-    ///
-    /// Faults may be either currently active or stored persistently in EEPROM
-    /// from a previous occurrence when the machine was powered off.
-    /// Returned faults do not include operating hours or occurrence count information.
-    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
-        let (active, (stored_addr, stored_mask)) = match code {
+    /// Returns the active and stored bits of a fault as pairs of address and mask.
+    fn fault_bits(code: FaultCode) -> (Option<(u32, u8)>, (u32, u8)) {
+        match code {
             // NTC open/short and pressure switch heating only have two states (ok/active)
             // They are stored but don't have a dedicated bit for the active state
             FaultCode::NtcThermistorOpen => (None, (0x0082, 0x01)),
@@ -372,7 +388,16 @@ impl<P: Read + Write> Dishwasher<P> {
             FaultCode::WaterInletEnd => (Some((0x0052, 0x08)), (0x0082, 0x40)),
             FaultCode::PressureSwitchInlet => (Some((0x0052, 0x10)), (0x0082, 0x80)),
             FaultCode::PressureSwitchHeating => (None, (0x0083, 0x01)),
-        };
+        }
+    }
+
+    /// Queries the status of a fault identified by its fault code.
+    ///
+    /// Faults may be either currently active or stored persistently in EEPROM
+    /// from a previous occurrence when the machine was powered off.
+    /// Returned faults do not include operating hours or occurrence count information.
+    pub async fn query_fault(&mut self, code: FaultCode) -> Result<Fault, P::Error> {
+        let (active, (stored_addr, stored_mask)) = Self::fault_bits(code);
 
         if let Some((active_addr, active_mask)) = active {
             let active: u8 = self.intf.read_memory(active_addr).await?;
@@ -391,6 +416,21 @@ impl<P: Read + Write> Dishwasher<P> {
         }
     }
 
+    /// Clears stored faults, either all faults or only the specified one.
+    ///
+    /// Only the bits of the stored faults are cleared, leaving other bits in the same locations untouched.
+    /// The memory is read back afterwards to confirm that the faults have been cleared.
+    /// Faults that are still active may be stored again by the dishwasher.
+    ///
+    /// Only the fault bits in RAM are cleared, as the location of their copy in EEPROM is unknown.
+    /// Cleared faults are therefore likely to reappear once the dishwasher is powered on again.
+    pub async fn clear_faults(&mut self, code: Option<FaultCode>) -> Result<(), P::Error> {
+        let codes = code.map_or_else(|| FaultCode::iter().collect(), |code| vec![code]);
+        let bits = codes.into_iter().map(|code| Self::fault_bits(code).1);
+
+        utils::clear_memory_bits(&mut self.intf, bits).await
+    }
+
     /// Queries the selected program.
     pub async fn query_selected_program(&mut self) -> Result<Program, P::Error> {
         Program::from_repr(self.intf.read_memory(0x00af).await?).ok_or(Error::UnexpectedMemoryValue)
@@ -573,7 +613,33 @@ impl<P: Read + Write> Device<P> for Dishwasher<P> {
     }
 
     fn actions(&self) -> &'static [Action] {
-        &[ACTION_START_PROGRAM]
+        &[
+            ACTION_START_PROGRAM,
+            ACTION_CLEAR_FAULTS,
+            ACTION_CLEAR_FAULT,
+        ]
+    }
+
+    fn memory_spans(&self, prop: &Property) -> &[Range<u32>] {
+        match *prop {
+            PROP_FAULT_F1 | PROP_FAULT_F2 => &[0x0082..0x0083],
+            PROP_FAULT_F3 | PROP_FAULT_F5 | PROP_FAULT_F6 | PROP_FAULT_F7 | PROP_FAULT_F8 => {
+                &[0x0052..0x0053, 0x0082..0x0083]
+            }
+            PROP_FAULT_F4 => &[0x0053..0x0054, 0x0082..0x0083],
+            PROP_FAULT_F9 => &[0x0083..0x0084],
+            PROP_SELECTED_PROGRAM => &[0x00af..0x00b0],
+            PROP_PROGRAM_TYPE => &[0x0065..0x0066],
+            PROP_TOP_SOLO_ENABLED => &[0x008e..0x008f],
+            PROP_PROGRAM_PHASE => &[0x006a..0x006b],
+            PROP_PROGRAM_STEP => &[0x020d..0x020e],
+            PROP_ACTIVE_ACTUATORS => &[0x022a..0x022c],
+            PROP_CLOSED_SWITCHES => &[0x006f..0x0070],
+            PROP_NTC_RESISTANCE => &[0x0061..0x0062, 0x006c..0x006d],
+            PROP_FLOW_METER_PULSES => &[0x0088..0x008a, 0x00c5..0x00c7],
+            PROP_TARGET_WATER_AMOUNT => &[0x00d6..0x00d8],
+            _ => &[],
+        }
     }
 
     async fn query_property(&mut self, prop: &Property) -> Result<Value, P::Error> {
@@ -600,14 +666,14 @@ impl<P: Read + Write> Device<P> for Dishwasher<P> {
                 .await?
                 .into()),
             // Operation
-            PROP_SELECTED_PROGRAM => Ok(self.query_selected_program().await?.to_string().into()),
-            PROP_PROGRAM_TYPE => Ok(self.query_program_type().await?.to_string().into()),
+            PROP_SELECTED_PROGRAM => Ok(Value::from_enum(self.query_selected_program().await?)),
+            PROP_PROGRAM_TYPE => Ok(Value::from_enum(self.query_program_type().await?)),
             PROP_TOP_SOLO_ENABLED => Ok(self.query_top_solo_enabled().await?.into()),
-            PROP_PROGRAM_PHASE => Ok(self.query_program_phase().await?.to_string().into()),
+            PROP_PROGRAM_PHASE => Ok(Value::from_enum(self.query_program_phase().await?)),
             PROP_PROGRAM_STEP => Ok(self.query_program_step().await?.into()),
             // Input/output
-            PROP_ACTIVE_ACTUATORS => Ok(self.query_active_actuators().await?.to_string().into()),
-            PROP_CLOSED_SWITCHES => Ok(self.query_closed_switches().await?.to_string().into()),
+            PROP_ACTIVE_ACTUATORS => Ok(Value::from_flags(self.query_active_actuators().await?)),
+            PROP_CLOSED_SWITCHES => Ok(Value::from_flags(self.query_closed_switches().await?)),
             PROP_NTC_RESISTANCE => Ok(self.query_ntc_resistance().await?.into()),
             PROP_FLOW_METER_PULSES => Ok(self.query_flow_meter_pulses().await?.into()),
             PROP_TARGET_WATER_AMOUNT => Ok(self.query_target_water_amount().await?.into()),
@@ -625,9 +691,15 @@ impl<P: Read + Write> Device<P> for Dishwasher<P> {
                 None => self.start_program().await,
                 Some(_) => Err(Error::InvalidArgument),
             },
+            ACTION_CLEAR_FAULTS => match param {
+                None => self.clear_faults(None).await,
+                Some(_) => Err(Error::InvalidArgument),
+            },
+            ACTION_CLEAR_FAULT => match param {
+                Some(s) => self.clear_faults(Some(s.parse()?)).await,
+                None => Err(Error::InvalidArgument),
+            },
             _ => Err(Error::UnknownAction),
         }
     }
 }
-
-impl<P> private::Sealed for Dishwasher<P> {}
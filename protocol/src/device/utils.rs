@@ -1,5 +1,9 @@
 //! Utility functions for device implementations.
 
+use crate::device::{Error, Interface, Result};
+use alloc::vec::Vec;
+use embedded_io_async::{Read, Write};
+
 /// Decodes a BCD-encoded value into a base-10 integer.
 pub fn decode_bcd_value(mut val: u32) -> u32 {
     let mut mul = 1;
@@ -36,6 +40,13 @@ pub fn ntc_resistance_from_adc(val: u8) -> u32 {
     (2150 * u32::from(val)) / (256 - u32::from(val))
 }
 
+/// Computes the PWM duty cycle in tenths of a percent from a raw 8-bit duty value.
+pub fn duty_cycle_from_pwm(duty: u8) -> u16 {
+    (u32::from(duty) * 1000 / 0xff)
+        .try_into()
+        .unwrap_or(u16::MAX)
+}
+
 /// Decodes raw data for a three-digit Motorola MC14489 seven-segment display into characters.
 ///
 /// Each digit (including decimal points) is decoded using [`decode_mc14489_digit`].
@@ -124,6 +135,42 @@ pub fn rpm_from_motor_speed_vfd(speed: u16) -> u16 {
     }
 }
 
+/// Clears bits in memory and reads the memory back to confirm that they have been cleared.
+///
+/// The bits are specified as pairs of address and mask.
+/// Each address is only written once, preserving all bits outside of its masks.
+pub async fn clear_memory_bits<P: Read + Write>(
+    intf: &mut Interface<P>,
+    bits: impl IntoIterator<Item = (u32, u8)>,
+) -> Result<(), P::Error> {
+    let mut masks: Vec<(u32, u8)> = Vec::new();
+
+    for (addr, mask) in bits {
+        match masks.iter_mut().find(|(a, _)| *a == addr) {
+            Some((_, m)) => *m |= mask,
+            None => masks.push((addr, mask)),
+        }
+    }
+
+    for (addr, mask) in masks {
+        let val: u8 = intf.read_memory(addr).await?;
+
+        if (val & mask) == 0x00 {
+            continue;
+        }
+
+        intf.write_memory(addr, val & !mask).await?;
+
+        let val: u8 = intf.read_memory(addr).await?;
+
+        if (val & mask) != 0x00 {
+            return Err(Error::VerificationFailed);
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,6 +195,14 @@ mod tests {
         assert_eq!(ntc_resistance_from_adc(0xff), 548_250);
     }
 
+    #[test]
+    fn pwm_duty_cycle() {
+        assert_eq!(duty_cycle_from_pwm(0x00), 0);
+        assert_eq!(duty_cycle_from_pwm(0x01), 3);
+        assert_eq!(duty_cycle_from_pwm(0x80), 501);
+        assert_eq!(duty_cycle_from_pwm(0xff), 1000);
+    }
+
     #[test]
     fn mc14489() {
         assert_eq!(
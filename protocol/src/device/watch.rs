@@ -0,0 +1,524 @@
+//! Property watcher for reacting to changes of device properties.
+//!
+//! Instead of polling properties and comparing their values manually,
+//! a [`PropertyWatcher`] polls a set of properties and emits a [`PropertyChange`]
+//! whenever a value differs from the previously reported one.
+//!
+//! Each property is polled at its own interval, and all properties due at the
+//! same time are queried at once using [`Device::query_properties`].
+//! Short-lived changes, e.g. a water level fluctuating while filling,
+//! can be suppressed using [`PropertyWatcher::debounce`].
+//!
+//! As the stream borrows the device, it needs to be dropped before using the device otherwise.
+//! Dropping it while a query is in progress interrupts the transfer, so use a [`StopHandle`]
+//! to end the stream between two polls instead.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use freemdu::{device::watch::PropertyWatcher, serial::Delay};
+//! use futures_util::StreamExt;
+//! use std::{pin::pin, time::{Duration, Instant}};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+//! let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+//! let mut dev = freemdu::device::connect(&mut port).await?;
+//! let start = Instant::now();
+//! let watcher = PropertyWatcher::new(Duration::from_secs(1))
+//!     .watch(dev.properties())
+//!     .debounce(Duration::from_secs(2));
+//! let mut changes = pin!(watcher.stream(&mut *dev, Delay, move || start.elapsed()));
+//!
+//! while let Some(change) = changes.next().await {
+//!     let change = change?;
+//!
+//!     println!("{}: {:?} -> {:?}", change.property.name, change.old, change.new);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    device::{Device, Property, Result, Value},
+    time::{Clock, Timer},
+};
+use alloc::{collections::vec_deque::VecDeque, rc::Rc, vec::Vec};
+use core::{cell::Cell, time::Duration};
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{ErrorType, Read, Write};
+use futures_util::{Stream, stream};
+
+/// Change of a property's value.
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PropertyChange {
+    /// Changed property.
+    pub property: &'static Property,
+    /// Previously reported value, `None` for the initial value.
+    pub old: Option<Value>,
+    /// Current value.
+    pub new: Value,
+    /// Time of the poll that first read the current value, as returned by the watcher's clock.
+    pub time: Duration,
+}
+
+/// Handle for ending property watcher streams between two polls.
+///
+/// Clones of a handle share the same state.
+#[derive(Clone, Default, Debug)]
+pub struct StopHandle(Rc<Cell<bool>>);
+
+impl StopHandle {
+    /// Constructs a new handle that has not been stopped.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ends the streams using this handle before their next poll.
+    pub fn stop(&self) {
+        self.0.set(true);
+    }
+
+    /// Returns whether the handle has been stopped.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.0.get()
+    }
+}
+
+/// Watcher that polls device properties and reports changes.
+#[derive(Clone, Debug)]
+pub struct PropertyWatcher {
+    interval: Duration,
+    debounce: Duration,
+    props: Vec<(&'static Property, Duration)>,
+    stop: StopHandle,
+}
+
+impl PropertyWatcher {
+    /// Constructs a new watcher polling at the specified default interval.
+    ///
+    /// If a poll takes longer than the interval, the next poll starts immediately.
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            debounce: Duration::ZERO,
+            props: Vec::new(),
+            stop: StopHandle::new(),
+        }
+    }
+
+    /// Adds properties to poll at the default interval.
+    #[must_use]
+    pub fn watch(self, props: impl IntoIterator<Item = &'static Property>) -> Self {
+        let interval = self.interval;
+
+        self.watch_every(props, interval)
+    }
+
+    /// Adds properties to poll at the specified interval.
+    ///
+    /// Properties that are already watched are polled at the new interval.
+    #[must_use]
+    pub fn watch_every(
+        mut self,
+        props: impl IntoIterator<Item = &'static Property>,
+        interval: Duration,
+    ) -> Self {
+        for prop in props {
+            match self.props.iter_mut().find(|(p, _)| *p == prop) {
+                Some((_, int)) => *int = interval,
+                None => self.props.push((prop, interval)),
+            }
+        }
+
+        self
+    }
+
+    /// Only reports values that remain unchanged for the specified duration.
+    ///
+    /// A changed value is reported once it has been read again after the duration has elapsed.
+    /// Values that change back in the meantime are never reported.
+    #[must_use]
+    pub fn debounce(mut self, dur: Duration) -> Self {
+        self.debounce = dur;
+        self
+    }
+
+    /// Ends the stream before the next poll once the handle has been stopped.
+    ///
+    /// Changes that have already been detected are still reported.
+    /// A query in progress is completed before the stream ends.
+    #[must_use]
+    pub fn stop_on(mut self, handle: StopHandle) -> Self {
+        self.stop = handle;
+        self
+    }
+
+    /// Returns a stream of changes, polling properties using the provided device.
+    ///
+    /// The first poll of each property reports its initial value.
+    /// The delay is used to wait between polls and the clock provides the change timestamps.
+    /// Failed queries are reported as errors, after which polling continues.
+    /// The stream ends immediately if no properties are watched,
+    /// or once stopped using the handle provided to [`PropertyWatcher::stop_on`].
+    pub fn stream<'a, P: Read + Write + 'a, D: Device<P> + ?Sized>(
+        self,
+        dev: &'a mut D,
+        delay: impl DelayNs + 'a,
+        clock: impl Clock + 'a,
+    ) -> impl Stream<Item = Result<PropertyChange, P::Error>> + 'a {
+        let poller = Poller {
+            dev,
+            delay,
+            clock,
+            debounce: self.debounce,
+            stop: self.stop,
+            entries: self
+                .props
+                .into_iter()
+                .map(|(prop, interval)| Entry {
+                    prop,
+                    interval,
+                    next_poll: Duration::ZERO,
+                    last: None,
+                    pending: None,
+                })
+                .collect(),
+            events: VecDeque::new(),
+        };
+
+        stream::unfold(poller, async |mut poller| {
+            if poller.entries.is_empty() {
+                return None;
+            }
+
+            let change = poller.next().await?;
+
+            Some((change, poller))
+        })
+    }
+}
+
+/// Polling state of a single property.
+struct Entry {
+    prop: &'static Property,
+    interval: Duration,
+    next_poll: Duration,
+    last: Option<Value>,
+    pending: Option<(Value, Duration)>,
+}
+
+impl Entry {
+    /// Updates the entry with a newly read value, returning a change if it should be reported.
+    fn update(&mut self, val: Value, time: Duration, debounce: Duration) -> Option<PropertyChange> {
+        let Some(last) = &self.last else {
+            return Some(self.report(val, time));
+        };
+
+        if *last == val {
+            self.pending = None;
+
+            return None;
+        }
+
+        match self.pending.take() {
+            // Report the new value once it has been stable for long enough
+            Some((pending, since)) if pending == val => {
+                if time.saturating_sub(since) >= debounce {
+                    Some(self.report(val, since))
+                } else {
+                    self.pending = Some((pending, since));
+
+                    None
+                }
+            }
+            _ if debounce.is_zero() => Some(self.report(val, time)),
+            _ => {
+                self.pending = Some((val, time));
+
+                None
+            }
+        }
+    }
+
+    /// Records the value as reported.
+    fn report(&mut self, val: Value, time: Duration) -> PropertyChange {
+        PropertyChange {
+            property: self.prop,
+            old: self.last.replace(val.clone()),
+            new: val,
+            time,
+        }
+    }
+}
+
+/// State of an active property watcher stream.
+struct Poller<'a, P: ErrorType, D: ?Sized, T, C> {
+    dev: &'a mut D,
+    delay: T,
+    clock: C,
+    debounce: Duration,
+    stop: StopHandle,
+    entries: Vec<Entry>,
+    events: VecDeque<Result<PropertyChange, P::Error>>,
+}
+
+impl<P: Read + Write, D: Device<P> + ?Sized, T: DelayNs, C: Clock> Poller<'_, P, D, T, C> {
+    /// Polls properties until a change is detected or a query fails.
+    ///
+    /// Returns `None` if the stop handle has been stopped.
+    async fn next(&mut self) -> Option<Result<PropertyChange, P::Error>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(event);
+            }
+
+            self.wait().await;
+
+            if self.stop.is_stopped() {
+                return None;
+            }
+
+            self.poll().await;
+        }
+    }
+
+    /// Waits until the next properties are due.
+    async fn wait(&mut self) {
+        let next_poll = self
+            .entries
+            .iter()
+            .map(|entry| entry.next_poll)
+            .min()
+            .unwrap_or_default();
+        let wait = next_poll.saturating_sub(self.clock.now());
+
+        if !wait.is_zero() {
+            self.delay.delay(wait).await;
+        }
+    }
+
+    /// Queries the due properties.
+    async fn poll(&mut self) {
+        let time = self.clock.now();
+        let mut due: Vec<_> = self
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.next_poll <= time)
+            .collect();
+        let props: Vec<_> = due.iter().map(|entry| entry.prop).collect();
+        let res = self.dev.query_properties(&props).await;
+
+        for entry in &mut due {
+            entry.next_poll = time + entry.interval;
+        }
+
+        match res {
+            Ok(vals) => {
+                for (entry, val) in due.into_iter().zip(vals) {
+                    match val {
+                        Ok(val) => {
+                            if let Some(change) = entry.update(val, time, self.debounce) {
+                                self.events.push_back(Ok(change));
+                            }
+                        }
+                        Err(err) => self.events.push_back(Err(err)),
+                    }
+                }
+            }
+            Err(err) => self.events.push_back(Err(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{device, simulator::Simulator, tests::init_logger};
+    use core::{convert::Infallible, pin::pin};
+    use futures_util::StreamExt;
+
+    /// Port that updates the simulated memory with the number of elapsed ticks.
+    ///
+    /// The load level is taken from the provided sequence,
+    /// while the program temperature is set to the number of ticks.
+    struct TickingPort {
+        sim: Simulator,
+        ticks: Rc<Cell<u8>>,
+        load_levels: &'static [u8],
+    }
+
+    impl ErrorType for TickingPort {
+        type Error = Infallible;
+    }
+
+    impl Read for TickingPort {
+        async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Infallible> {
+            self.sim.read(buf).await
+        }
+    }
+
+    impl Write for TickingPort {
+        async fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Infallible> {
+            let ticks = self.ticks.get();
+            let level = self.load_levels[usize::from(ticks).min(self.load_levels.len() - 1)];
+
+            self.sim.write_memory(0x004a, &[level]);
+            self.sim.write_memory(0x00df, &[ticks]);
+            self.sim.write(buf).await
+        }
+
+        async fn flush(&mut self) -> core::result::Result<(), Infallible> {
+            self.sim.flush().await
+        }
+    }
+
+    /// Delay that advances the ticks instead of waiting.
+    struct TickingDelay(Rc<Cell<u8>>);
+
+    impl DelayNs for TickingDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    /// Collects the first changes of the load level and program temperature properties.
+    async fn collect_changes(
+        load_levels: &'static [u8],
+        count: usize,
+        configure: impl FnOnce(PropertyWatcher, &'static Property, &'static Property) -> PropertyWatcher,
+    ) -> Result<Vec<(&'static str, Option<Value>, Value, u64)>, Infallible> {
+        let ticks = Rc::new(Cell::new(0));
+        let port = TickingPort {
+            sim: Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x0400]),
+            ticks: ticks.clone(),
+            load_levels,
+        };
+        let mut dev = device::connect(port).await?;
+        let find = |id| dev.properties().iter().find(|prop| prop.id == id).unwrap();
+        let watcher = configure(
+            PropertyWatcher::new(Duration::from_secs(1)),
+            find("load_level"),
+            find("program_temperature"),
+        );
+        let clock_ticks = ticks.clone();
+        let changes: Vec<_> = watcher
+            .stream(&mut *dev, TickingDelay(ticks), move || {
+                Duration::from_secs(clock_ticks.get().into())
+            })
+            .take(count)
+            .collect()
+            .await;
+
+        changes
+            .into_iter()
+            .map(|change| {
+                change.map(|change| {
+                    (
+                        change.property.id,
+                        change.old,
+                        change.new,
+                        change.time.as_secs(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn watch_changes() -> Result<(), Infallible> {
+        init_logger();
+
+        let changes = collect_changes(&[0, 0, 7], 4, |watcher, load, temp| {
+            watcher
+                .watch([load])
+                .watch_every([temp], Duration::from_secs(3))
+        })
+        .await?;
+
+        assert_eq!(
+            changes,
+            [
+                ("load_level", None, Value::Number(0), 0),
+                ("program_temperature", None, Value::Number(0), 0),
+                ("load_level", Some(Value::Number(0)), Value::Number(7), 2),
+                (
+                    "program_temperature",
+                    Some(Value::Number(0)),
+                    Value::Number(3),
+                    3
+                ),
+            ],
+            "changes should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn debounce() -> Result<(), Infallible> {
+        init_logger();
+
+        // The short-lived value 5 is never reported
+        let changes = collect_changes(&[0, 5, 0, 3], 2, |watcher, load, _| {
+            watcher.watch([load]).debounce(Duration::from_secs(2))
+        })
+        .await?;
+
+        assert_eq!(
+            changes,
+            [
+                ("load_level", None, Value::Number(0), 0),
+                ("load_level", Some(Value::Number(0)), Value::Number(3), 3),
+            ],
+            "changes should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stop() -> Result<(), Infallible> {
+        init_logger();
+
+        let ticks = Rc::new(Cell::new(0));
+        let port = TickingPort {
+            sim: Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x0400]),
+            ticks: ticks.clone(),
+            load_levels: &[0, 3, 7],
+        };
+        let mut dev = device::connect(port).await?;
+        let load = dev
+            .properties()
+            .iter()
+            .find(|prop| prop.id == "load_level")
+            .unwrap();
+        let handle = StopHandle::new();
+        let watcher = PropertyWatcher::new(Duration::from_secs(1))
+            .watch([load])
+            .stop_on(handle.clone());
+        let clock_ticks = ticks.clone();
+        let mut changes = pin!(watcher.stream(&mut *dev, TickingDelay(ticks), move || {
+            Duration::from_secs(clock_ticks.get().into())
+        }));
+
+        assert_eq!(
+            changes.next().await.transpose()?.map(|change| change.new),
+            Some(Value::Number(0)),
+            "initial value should be correct"
+        );
+
+        handle.stop();
+
+        assert!(
+            changes.next().await.is_none(),
+            "stream should end once stopped"
+        );
+
+        Ok(())
+    }
+}
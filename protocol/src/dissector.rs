@@ -0,0 +1,652 @@
+//! Decoding of raw diagnostic protocol traffic.
+//!
+//! The [`Dissector`] reconstructs the frames exchanged between a client and a device
+//! from the bytes transferred in both directions, e.g. from a [`Capture`].
+//! It tracks the protocol state across requests, so address extensions
+//! and chunk size changes are taken into account when splitting frames.
+//!
+//! Traffic that does not match the expected protocol flow is reported as
+//! [`Frame::Unknown`], after which decoding resumes with the next request.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{
+//!     capture::Capture,
+//!     dissector::{self, Frame},
+//! };
+//!
+//! let capture: Capture = "# freemdu capture v1
+//! 0.000000 tx 11 00 00 02 13
+//! 0.004817 rx 00
+//! 0.004931 rx 75 02 77
+//! 0.016244 tx 00"
+//!     .parse()
+//!     .unwrap();
+//! let frames = dissector::dissect(&capture);
+//!
+//! assert_eq!(frames.len(), 4);
+//! assert_eq!(frames[0].to_string(), "tx request QuerySoftwareId param=0000 len=2");
+//! assert_eq!(frames[2].to_string(), "rx data 75 02");
+//! ```
+
+use crate::{
+    Command, DEFAULT_CHUNK_SIZE, ResponseCode,
+    capture::{Capture, Direction},
+    compute_checksum,
+};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+/// Length of a request frame, excluding the checksum.
+const REQUEST_LEN: usize = 4;
+
+/// Frame decoded from raw protocol traffic.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Frame {
+    /// Request sent to the device.
+    Request {
+        /// Raw command code, see [`Command::from_repr`].
+        cmd: u8,
+        /// Request parameter.
+        param: u16,
+        /// Request length.
+        len: u8,
+        /// Full address accessed by the request, including
+        /// the upper bits of a preceding [`Command::ExtendAddress`].
+        addr: Option<u32>,
+        /// Length of the payload sent or received after the request,
+        /// including the upper bits of a preceding [`Command::ExtendAddress`].
+        payload_len: usize,
+        /// Whether the frame's checksum is correct.
+        checksum_valid: bool,
+    },
+    /// Chunk of payload data.
+    Data {
+        /// Direction of the transfer.
+        direction: Direction,
+        /// Chunk data, excluding the checksum.
+        data: Vec<u8>,
+        /// Whether the chunk's checksum is correct.
+        checksum_valid: bool,
+    },
+    /// Response code acknowledging a request or chunk, see [`ResponseCode::from_repr`].
+    Response {
+        /// Direction of the transfer.
+        direction: Direction,
+        /// Raw response code.
+        code: u8,
+    },
+    /// Data that does not match the expected protocol flow.
+    Unknown {
+        /// Direction of the transfer.
+        direction: Direction,
+        /// Raw data.
+        data: Vec<u8>,
+    },
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::Request {
+                cmd,
+                param,
+                len,
+                addr,
+                payload_len,
+                checksum_valid,
+            } => {
+                match Command::from_repr(*cmd) {
+                    Some(cmd) => write!(f, "tx request {cmd:?}")?,
+                    None => write!(f, "tx request {cmd:02x}")?,
+                }
+
+                write!(f, " param={param:04x} len={len}")?;
+
+                if let Some(addr) = addr {
+                    write!(f, " addr={addr:08x}")?;
+                }
+
+                if *payload_len != usize::from(*len) {
+                    write!(f, " payload_len={payload_len}")?;
+                }
+
+                if !checksum_valid {
+                    write!(f, " (incorrect checksum)")?;
+                }
+
+                Ok(())
+            }
+            Self::Data {
+                direction,
+                data,
+                checksum_valid,
+            } => {
+                write!(f, "{direction} data")?;
+                write_hex(f, data)?;
+
+                if !checksum_valid {
+                    write!(f, " (incorrect checksum)")?;
+                }
+
+                Ok(())
+            }
+            Self::Response { direction, code } => match ResponseCode::from_repr(*code) {
+                Some(code) => write!(f, "{direction} response {code:?}"),
+                None => write!(f, "{direction} response {code:02x}"),
+            },
+            Self::Unknown { direction, data } => {
+                write!(f, "{direction} unknown")?;
+                write_hex(f, data)
+            }
+        }
+    }
+}
+
+/// Writes the data as space-separated hexadecimal bytes.
+fn write_hex(f: &mut Formatter, data: &[u8]) -> core::fmt::Result {
+    for byte in data {
+        write!(f, " {byte:02x}")?;
+    }
+
+    Ok(())
+}
+
+/// Payload transfer following a request.
+#[derive(Copy, Clone, Debug)]
+struct Transfer {
+    direction: Direction,
+    /// Number of remaining bytes, unknown for smart home responses.
+    remaining: Option<usize>,
+}
+
+/// Request whose payload is currently being transferred.
+#[derive(Clone, Debug)]
+struct Transaction {
+    cmd: Option<Command>,
+    param: u16,
+    len: u8,
+    /// Pending transfers in reverse order.
+    transfers: Vec<Transfer>,
+    /// Data of all received chunks.
+    received: Vec<u8>,
+}
+
+/// Expected next segment of the protocol flow.
+#[derive(Clone, Debug)]
+enum Phase {
+    Request,
+    Response {
+        direction: Direction,
+        txn: Transaction,
+    },
+    Chunk {
+        txn: Transaction,
+    },
+    Unknown,
+}
+
+/// Stateful decoder for raw protocol traffic.
+///
+/// Data is fed to the dissector in the order it was transferred using [`Dissector::push`].
+#[derive(Debug)]
+pub struct Dissector {
+    chunk_size: u8,
+    extension: Option<(u16, u8)>,
+    phase: Phase,
+    pending: Vec<u8>,
+}
+
+impl Default for Dissector {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            extension: None,
+            phase: Phase::Request,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Dissector {
+    /// Constructs a new dissector, expecting a request with the default chunk size.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the chunk size currently used for payload transfers.
+    #[must_use]
+    pub fn chunk_size(&self) -> u8 {
+        self.chunk_size
+    }
+
+    /// Processes transferred data, returning all frames completed by it.
+    pub fn push(&mut self, direction: Direction, data: &[u8]) -> Vec<Frame> {
+        let mut frames = Vec::new();
+
+        for &byte in data {
+            if direction != self.direction() {
+                self.resync(direction, &mut frames);
+            }
+
+            self.pending.push(byte);
+
+            if self.segment_len() == Some(self.pending.len()) {
+                self.complete_segment(&mut frames);
+            }
+        }
+
+        frames
+    }
+
+    /// Returns all frames from data that has not been fully decoded yet.
+    ///
+    /// Should be called once all data has been pushed.
+    pub fn finish(&mut self) -> Vec<Frame> {
+        let mut frames = Vec::new();
+
+        if self.is_open_ended() && !self.pending.is_empty() {
+            self.complete_segment(&mut frames);
+        } else {
+            self.flush(&mut frames);
+        }
+
+        self.phase = Phase::Request;
+
+        frames
+    }
+
+    /// Returns whether the current transfer has an unknown length.
+    fn is_open_ended(&self) -> bool {
+        match &self.phase {
+            Phase::Chunk { txn } => txn.transfers.last().is_some_and(|t| t.remaining.is_none()),
+            _ => false,
+        }
+    }
+
+    /// Returns the direction of the next expected segment.
+    fn direction(&self) -> Direction {
+        match &self.phase {
+            Phase::Request => Direction::Tx,
+            Phase::Response { direction, .. } => *direction,
+            Phase::Chunk { txn } => txn.transfers.last().map_or(Direction::Tx, |t| t.direction),
+            Phase::Unknown => Direction::Rx,
+        }
+    }
+
+    /// Returns the length of the next expected segment, if known.
+    fn segment_len(&self) -> Option<usize> {
+        let chunk_size = usize::from(self.chunk_size);
+
+        match &self.phase {
+            Phase::Request => Some(REQUEST_LEN + 1),
+            Phase::Response { .. } => Some(1),
+            Phase::Chunk { txn } => {
+                let remaining = txn.transfers.last()?.remaining.unwrap_or(chunk_size);
+
+                Some(remaining.min(chunk_size) + 1)
+            }
+            Phase::Unknown => None,
+        }
+    }
+
+    /// Handles data transferred in an unexpected direction.
+    fn resync(&mut self, direction: Direction, frames: &mut Vec<Frame>) {
+        // Responses of unknown length end with a shorter chunk or a change of direction
+        if self.is_open_ended() {
+            if self.pending.is_empty() {
+                if let Phase::Chunk { txn } = &mut self.phase {
+                    txn.transfers.pop();
+                }
+
+                self.advance();
+            } else {
+                self.complete_segment(frames);
+            }
+
+            if direction == self.direction() {
+                return;
+            }
+        }
+
+        self.flush(frames);
+        self.phase = match direction {
+            Direction::Tx => Phase::Request,
+            Direction::Rx => Phase::Unknown,
+        };
+    }
+
+    /// Reports all pending data as unknown.
+    fn flush(&mut self, frames: &mut Vec<Frame>) {
+        if !self.pending.is_empty() {
+            frames.push(Frame::Unknown {
+                direction: self.direction(),
+                data: core::mem::take(&mut self.pending),
+            });
+        }
+    }
+
+    /// Decodes a complete segment.
+    fn complete_segment(&mut self, frames: &mut Vec<Frame>) {
+        let segment = core::mem::take(&mut self.pending);
+
+        match core::mem::replace(&mut self.phase, Phase::Request) {
+            Phase::Request => {
+                let txn = self.decode_request(&segment, frames);
+
+                self.phase = Phase::Response {
+                    direction: Direction::Rx,
+                    txn,
+                };
+            }
+            Phase::Response { direction, txn } => {
+                let code = segment[0];
+
+                frames.push(Frame::Response { direction, code });
+
+                // Other response codes abort the transaction
+                if ResponseCode::from_repr(code) == Some(ResponseCode::Success) {
+                    self.phase = Phase::Chunk { txn };
+                    self.advance();
+                }
+            }
+            Phase::Chunk { mut txn } => {
+                let (checksum, data) = segment.split_last().unwrap_or((&0x00, &[]));
+                let Some(transfer) = txn.transfers.last_mut() else {
+                    return;
+                };
+                let direction = transfer.direction;
+
+                if let Some(remaining) = &mut transfer.remaining {
+                    *remaining -= data.len();
+                }
+
+                if direction == Direction::Rx {
+                    txn.received.extend_from_slice(data);
+                }
+
+                frames.push(Frame::Data {
+                    direction,
+                    data: data.to_vec(),
+                    checksum_valid: *checksum == compute_checksum(data),
+                });
+
+                self.phase = Phase::Response {
+                    direction: opposite(direction),
+                    txn,
+                };
+            }
+            Phase::Unknown => frames.push(Frame::Unknown {
+                direction: Direction::Rx,
+                data: segment,
+            }),
+        }
+    }
+
+    /// Decodes a request segment, returning the resulting transaction.
+    fn decode_request(&mut self, segment: &[u8], frames: &mut Vec<Frame>) -> Transaction {
+        let (checksum, data) = segment.split_last().unwrap_or((&0x00, &[]));
+        let cmd = Command::from_repr(data[0]);
+        let param = u16::from_le_bytes([data[1], data[2]]);
+        let len = data[3];
+
+        // Extension only applies to the directly following request
+        let (ext_param, ext_len) = self.extension.take().unwrap_or_default();
+        let ext_addr = (u32::from(ext_param) << 16) | u32::from(param);
+        let ext_len = (usize::from(ext_len) << 8) | usize::from(len);
+
+        let (addr, payload_len) = match cmd {
+            Some(Command::ReadMemory | Command::WriteMemory) => (Some(ext_addr), ext_len),
+            Some(Command::JumpToSubroutine) => (Some(ext_addr), 0),
+            Some(Command::ReadEeprom | Command::WriteEeprom) => {
+                (Some(u32::from(param)), usize::from(len))
+            }
+            _ => (None, usize::from(len)),
+        };
+        let transfers = match cmd {
+            Some(
+                Command::QuerySoftwareId
+                | Command::QueryMaxBaudRate
+                | Command::ReadMemory
+                | Command::ReadEeprom
+                | Command::SetChunkSize
+                | Command::SetBaudRate,
+            ) => [Some(Direction::Rx), None],
+            Some(Command::WriteMemory | Command::WriteEeprom) => [Some(Direction::Tx), None],
+            Some(Command::RequestSmartHome) => [Some(Direction::Tx), Some(Direction::Rx)],
+            _ => [None, None],
+        };
+
+        frames.push(Frame::Request {
+            cmd: data[0],
+            param,
+            len,
+            addr,
+            payload_len,
+            checksum_valid: *checksum == compute_checksum(data),
+        });
+
+        Transaction {
+            cmd,
+            param,
+            len,
+            transfers: transfers
+                .into_iter()
+                .flatten()
+                .map(|direction| Transfer {
+                    direction,
+                    // Length of smart home responses is not part of the request
+                    remaining: (direction == Direction::Tx
+                        || cmd != Some(Command::RequestSmartHome))
+                    .then_some(payload_len),
+                })
+                .rev()
+                .collect(),
+            received: Vec::new(),
+        }
+    }
+
+    /// Continues with the next transfer after a chunk or request has been acknowledged.
+    fn advance(&mut self) {
+        let Phase::Chunk { txn } = &mut self.phase else {
+            return;
+        };
+
+        while txn.transfers.last().is_some_and(|t| t.remaining == Some(0)) {
+            txn.transfers.pop();
+        }
+
+        if !txn.transfers.is_empty() {
+            return;
+        }
+
+        let Phase::Chunk { txn } = core::mem::replace(&mut self.phase, Phase::Request) else {
+            return;
+        };
+
+        self.complete(&txn);
+    }
+
+    /// Applies the effects of a completed transaction.
+    fn complete(&mut self, txn: &Transaction) {
+        match txn.cmd {
+            Some(Command::ExtendAddress) => self.extension = Some((txn.param, txn.len)),
+            Some(Command::SetChunkSize) => {
+                // Device responds with actual chunk size
+                if let Some(&size) = txn.received.first() {
+                    self.chunk_size = size;
+                }
+            }
+            Some(Command::Reset) => self.chunk_size = DEFAULT_CHUNK_SIZE,
+            _ => {}
+        }
+    }
+}
+
+/// Returns the opposite transfer direction.
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::Tx => Direction::Rx,
+        Direction::Rx => Direction::Tx,
+    }
+}
+
+/// Decodes all frames contained in a capture.
+#[must_use]
+pub fn dissect(capture: &Capture) -> Vec<Frame> {
+    let mut dissector = Dissector::new();
+    let mut frames: Vec<_> = capture
+        .records()
+        .iter()
+        .flat_map(|record| dissector.push(record.direction, &record.data))
+        .collect();
+
+    frames.extend(dissector.finish());
+
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BaudRate, Interface, capture::CapturePort, simulator::Simulator, tests::init_logger,
+    };
+    use alloc::vec;
+    use core::{convert::Infallible, time::Duration};
+
+    #[tokio::test]
+    async fn dissect_session() -> crate::Result<(), Infallible> {
+        init_logger();
+
+        let sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0x0001_0000, [0x00; 0x0200])
+            .with_extended_commands(BaudRate::Baud38400, 64);
+        let mut port = CapturePort::new(sim, || Duration::ZERO);
+
+        {
+            let mut intf = Interface::new(&mut port);
+
+            intf.query_software_id().await?;
+            intf.unlock_read_access(0x43ea).await?;
+            intf.unlock_full_access(0x1f02).await?;
+            intf.set_chunk_size(128).await?;
+
+            let _: [u8; 0x0200] = intf.read_memory(0x0001_0000).await?;
+        }
+
+        let frames = dissect(port.capture());
+        let requests: Vec<_> = frames
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::Request { cmd, .. } => Command::from_repr(*cmd),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            requests,
+            [
+                Command::QuerySoftwareId,
+                Command::UnlockReadAccess,
+                Command::UnlockFullAccess,
+                Command::SetChunkSize,
+                Command::ExtendAddress,
+                Command::ReadMemory,
+            ],
+            "requests should be correct"
+        );
+        assert!(
+            frames
+                .iter()
+                .all(|frame| !matches!(frame, Frame::Unknown { .. })),
+            "all frames should be decoded"
+        );
+        assert!(
+            frames.contains(&Frame::Request {
+                cmd: Command::ReadMemory as u8,
+                param: 0x0000,
+                len: 0x00,
+                addr: Some(0x0001_0000),
+                payload_len: 0x0200,
+                checksum_valid: true,
+            }),
+            "memory request should be extended"
+        );
+
+        let chunks: Vec<_> = frames
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::Data { data, .. } => Some(data.len()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            chunks,
+            [2, 1, 64, 64, 64, 64, 64, 64, 64, 64],
+            "chunk lengths should follow chunk size"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn dissect_errors() {
+        let mut dissector = Dissector::new();
+        let mut frames = dissector.push(Direction::Tx, &[0x30, 0x00, 0x00, 0x02, 0x00]);
+
+        frames.extend(dissector.push(Direction::Rx, &[0x01]));
+        frames.extend(dissector.push(Direction::Rx, &[0xaa, 0xbb]));
+        frames.extend(dissector.push(Direction::Tx, &[0x30, 0x00, 0x00, 0x02, 0x32]));
+        frames.extend(dissector.push(Direction::Rx, &[0x00, 0x12, 0x34, 0x00]));
+        frames.extend(dissector.push(Direction::Tx, &[0x01]));
+        frames.extend(dissector.finish());
+
+        assert_eq!(
+            frames,
+            [
+                Frame::Request {
+                    cmd: 0x30,
+                    param: 0x0000,
+                    len: 2,
+                    addr: Some(0x0000),
+                    payload_len: 2,
+                    checksum_valid: false,
+                },
+                Frame::Response {
+                    direction: Direction::Rx,
+                    code: ResponseCode::IncorrectChecksum as u8,
+                },
+                Frame::Unknown {
+                    direction: Direction::Rx,
+                    data: vec![0xaa, 0xbb],
+                },
+                Frame::Request {
+                    cmd: 0x30,
+                    param: 0x0000,
+                    len: 2,
+                    addr: Some(0x0000),
+                    payload_len: 2,
+                    checksum_valid: true,
+                },
+                Frame::Response {
+                    direction: Direction::Rx,
+                    code: ResponseCode::Success as u8,
+                },
+                Frame::Data {
+                    direction: Direction::Rx,
+                    data: vec![0x12, 0x34],
+                    checksum_valid: false,
+                },
+                Frame::Response {
+                    direction: Direction::Tx,
+                    code: ResponseCode::IncorrectChecksum as u8,
+                },
+            ],
+            "frames should be correct"
+        );
+    }
+}
@@ -0,0 +1,561 @@
+//! Firmware extraction from a device's ROM.
+//!
+//! The ROM of every supported device can be read after its readout protection
+//! has been disabled during initialization. [`rom_range`] returns the ROM address
+//! range of a software ID, derived from the ROM size of its microcontroller.
+//!
+//! A [`FirmwareDump`] reads the ROM in small blocks, so that an interrupted dump
+//! can be resumed from the data read so far. Once complete, it is verified by
+//! reading the ROM a second time, which results in a [`FirmwareImage`].
+//! Images can be exported as raw binary, Intel HEX or Motorola S-record data
+//! and identified by their SHA-256 fingerprint.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{firmware::FirmwareDump, simulator::Simulator};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), core::convert::Infallible> {
+//! let mut sim = Simulator::new(605, 0x1234, 0x5678).with_rom(0x8000, [0xea; 0x8000]);
+//! let mut dev = freemdu::device::connect(&mut sim).await?;
+//! let mut dump = FirmwareDump::for_software_id(dev.software_id()).unwrap();
+//!
+//! while !dump.is_complete() {
+//!     dump.read_next(dev.interface()).await?;
+//!     // Save `dump.data()` to resume after an interruption
+//! }
+//!
+//! let image = dump.verify(dev.interface(), |_, _| {}).await?;
+//!
+//! assert_eq!(image.base_addr, 0x8000);
+//! assert!(image.to_intel_hex().ends_with(":00000001FF\n"));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    Interface,
+    device::{Error, Result},
+    state::CanRead,
+};
+use alloc::{string::String, vec::Vec};
+use core::{fmt::Write as _, ops::Range};
+use embedded_io_async::{Read, Write};
+use log::debug;
+
+/// Number of bytes read by [`FirmwareDump::read_next`].
+const BLOCK_LEN: usize = 0x0100;
+
+/// Number of data bytes per Intel HEX and S-record line.
+const BYTES_PER_RECORD: usize = 16;
+
+/// Returns the ROM address range of the specified software ID.
+///
+/// The ROM of all supported microcontrollers ends at `0xffff`, including the interrupt vectors.
+/// Its start address is determined by the microcontroller's ROM size.
+/// Returns `None` if the software ID is unknown.
+#[must_use]
+pub fn rom_range(software_id: u16) -> Option<Range<u32>> {
+    let start = match software_id {
+        // M37451MC and M38078MC: 48 KiB
+        218 | 324 | 360 | 419 => 0x4000,
+        // M38078MF and M38079MF/EF: 60 KiB
+        469 | 517 | 629 | 2088 | 2895 => 0x1000,
+        // M38027M8: 32 KiB
+        605 => 0x8000,
+        _ => return None,
+    };
+
+    Some(start..0x0001_0000)
+}
+
+/// Resumable dump of a memory range.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FirmwareDump {
+    software_id: u16,
+    range: Range<u32>,
+    data: Vec<u8>,
+}
+
+impl FirmwareDump {
+    /// Constructs a new dump of the specified memory range.
+    #[must_use]
+    pub fn new(software_id: u16, range: Range<u32>) -> Self {
+        Self {
+            software_id,
+            range,
+            data: Vec::new(),
+        }
+    }
+
+    /// Constructs a new dump of the ROM of the specified software ID.
+    ///
+    /// Returns `None` if the ROM range of the software ID is unknown.
+    #[must_use]
+    pub fn for_software_id(software_id: u16) -> Option<Self> {
+        rom_range(software_id).map(|range| Self::new(software_id, range))
+    }
+
+    /// Resumes the dump from previously read data, starting at the beginning of the range.
+    ///
+    /// Data exceeding the range is discarded.
+    #[must_use]
+    pub fn with_data(mut self, mut data: Vec<u8>) -> Self {
+        data.truncate(self.len());
+        self.data = data;
+        self
+    }
+
+    /// Returns the software ID of the dumped device.
+    #[must_use]
+    pub fn software_id(&self) -> u16 {
+        self.software_id
+    }
+
+    /// Returns the dumped memory range.
+    #[must_use]
+    pub fn range(&self) -> Range<u32> {
+        self.range.clone()
+    }
+
+    /// Returns the data read so far.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the total number of bytes in the range.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.range.end.saturating_sub(self.range.start) as usize
+    }
+
+    /// Returns `true` if the range is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the entire range has been read.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.data.len() == self.len()
+    }
+
+    /// Reads the next block of the range and returns its length.
+    ///
+    /// If reading fails, the data read so far is kept and the block can be read again.
+    pub async fn read_next<P: Read + Write, S: CanRead>(
+        &mut self,
+        intf: &mut Interface<P, S>,
+    ) -> Result<usize, P::Error> {
+        let offset = self.data.len();
+        let len = (self.len() - offset).min(BLOCK_LEN);
+        let addr = self.range.start + u32::try_from(offset).map_err(|_| Error::InvalidArgument)?;
+        let block = intf.read_memory_vec(addr, len, |_, _| {}).await?;
+
+        self.data.extend(block);
+
+        Ok(len)
+    }
+
+    /// Reads the entire range a second time and compares it with the dumped data.
+    ///
+    /// The `progress` callback is invoked with the number of bytes
+    /// read so far and the total number of bytes after every chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidState`] if the dump is not complete.
+    /// - [`Error::VerificationFailed`] if the memory contents differ from the dumped data.
+    pub async fn verify<P: Read + Write, S: CanRead>(
+        &self,
+        intf: &mut Interface<P, S>,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<FirmwareImage, P::Error> {
+        if !self.is_complete() {
+            return Err(Error::InvalidState);
+        }
+
+        let data = intf
+            .read_memory_vec(self.range.start, self.len(), progress)
+            .await?;
+
+        if let Some(offset) = data.iter().zip(&self.data).position(|(a, b)| a != b) {
+            debug!(
+                "Memory at {:04x} differs from dump",
+                self.range.start as usize + offset
+            );
+
+            return Err(Error::VerificationFailed);
+        }
+
+        Ok(FirmwareImage {
+            software_id: self.software_id,
+            base_addr: self.range.start,
+            data,
+        })
+    }
+}
+
+/// Verified firmware image.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FirmwareImage {
+    /// Software ID of the device.
+    pub software_id: u16,
+    /// Memory address of the first byte.
+    pub base_addr: u32,
+    /// Image contents.
+    pub data: Vec<u8>,
+}
+
+impl FirmwareImage {
+    /// Returns the SHA-256 hash of the data, which serves as the image's fingerprint.
+    #[must_use]
+    pub fn fingerprint(&self) -> [u8; 32] {
+        compute_sha256(&self.data)
+    }
+
+    /// Exports the image in the Intel HEX format.
+    ///
+    /// Extended linear address records are emitted for addresses above `0xffff`.
+    #[must_use]
+    pub fn to_intel_hex(&self) -> String {
+        let mut out = String::new();
+        let mut upper = 0;
+
+        for (addr, chunk) in self.records() {
+            if addr >> 16 != upper {
+                upper = addr >> 16;
+                push_intel_hex_record(&mut out, 0x04, 0x0000, &addr.to_be_bytes()[..2]);
+            }
+
+            push_intel_hex_record(&mut out, 0x00, addr, chunk);
+        }
+
+        push_intel_hex_record(&mut out, 0x01, 0x0000, &[]);
+
+        out
+    }
+
+    /// Exports the image in the Motorola S-record format.
+    ///
+    /// The header record contains the software ID. The address width
+    /// is chosen based on the highest address of the image.
+    #[must_use]
+    pub fn to_srecord(&self) -> String {
+        let end = self.base_addr as usize + self.data.len();
+        let (data_type, term_type, addr_len) = match end {
+            0..=0x0001_0000 => (1, 9, 2),
+            0x0001_0001..=0x0100_0000 => (2, 8, 3),
+            _ => (3, 7, 4),
+        };
+        let mut out = String::new();
+        let mut header = String::new();
+
+        let _ = write!(header, "freemdu {}", self.software_id);
+        push_srecord(&mut out, 0, 0, 2, header.as_bytes());
+
+        for (addr, chunk) in self.records() {
+            push_srecord(&mut out, data_type, addr, addr_len, chunk);
+        }
+
+        push_srecord(&mut out, term_type, 0, addr_len, &[]);
+
+        out
+    }
+
+    /// Returns the address and data of every record.
+    fn records(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        (self.base_addr..)
+            .step_by(BYTES_PER_RECORD)
+            .zip(self.data.chunks(BYTES_PER_RECORD))
+    }
+}
+
+/// Appends an Intel HEX record to the output.
+///
+/// Only the lower 16 bits of the address are included in the record.
+fn push_intel_hex_record(out: &mut String, kind: u8, addr: u32, data: &[u8]) {
+    let mut bytes = alloc::vec![u8::try_from(data.len()).unwrap_or(u8::MAX)];
+
+    bytes.extend(&addr.to_be_bytes()[2..]);
+    bytes.push(kind);
+    bytes.extend(data);
+
+    let checksum = bytes
+        .iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg();
+
+    out.push(':');
+
+    for byte in bytes.iter().chain([&checksum]) {
+        let _ = write!(out, "{byte:02X}");
+    }
+
+    out.push('\n');
+}
+
+/// Appends a Motorola S-record with the specified address length to the output.
+fn push_srecord(out: &mut String, kind: u8, addr: u32, addr_len: usize, data: &[u8]) {
+    let mut bytes = alloc::vec![u8::try_from(addr_len + data.len() + 1).unwrap_or(u8::MAX)];
+
+    bytes.extend(&addr.to_be_bytes()[4 - addr_len..]);
+    bytes.extend(data);
+
+    let checksum = !bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+
+    let _ = write!(out, "S{kind}");
+
+    for byte in bytes.iter().chain([&checksum]) {
+        let _ = write!(out, "{byte:02X}");
+    }
+
+    out.push('\n');
+}
+
+/// Round constants of the SHA-256 hash function.
+const SHA256_K: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+/// Computes the SHA-256 hash of the provided data.
+#[expect(clippy::many_single_char_names)]
+fn compute_sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09_e667,
+        0xbb67_ae85,
+        0x3c6e_f372,
+        0xa54f_f53a,
+        0x510e_527f,
+        0x9b05_688c,
+        0x1f83_d9ab,
+        0x5be0_cd19,
+    ];
+    let bit_len = (data.len() as u64).wrapping_mul(8);
+    let mut msg = data.to_vec();
+
+    // Pad the message to a multiple of 64 bytes, ending with its length in bits
+    msg.push(0x80);
+    msg.resize((msg.len() + 8).next_multiple_of(64), 0x00);
+
+    let len = msg.len();
+
+    msg[len - 8..].copy_from_slice(&bit_len.to_be_bytes());
+
+    for block in msg.chunks_exact(64) {
+        let mut w = [0u32; 64];
+
+        for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+
+        for (k, w) in SHA256_K.iter().zip(w) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(*k)
+                .wrapping_add(w);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (state, val) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(val);
+        }
+    }
+
+    let mut hash = [0x00; 32];
+
+    for (bytes, word) in hash.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{device, simulator::Simulator, tests::init_logger};
+    use alloc::vec;
+    use core::convert::Infallible;
+
+    #[test]
+    fn sha256() {
+        assert_eq!(
+            compute_sha256(b"abc")[..4],
+            [0xba, 0x78, 0x16, 0xbf],
+            "hash should be correct"
+        );
+        assert_eq!(
+            compute_sha256(&[0x61; 56])[..4],
+            [0xb3, 0x54, 0x39, 0xa4],
+            "hash should be correct"
+        );
+    }
+
+    #[test]
+    fn export() {
+        let image = FirmwareImage {
+            software_id: 605,
+            base_addr: 0xfff0,
+            data: (0x00..0x14).collect(),
+        };
+
+        assert_eq!(
+            image.to_intel_hex(),
+            ":10FFF000000102030405060708090A0B0C0D0E0F89\n\
+             :020000040001F9\n\
+             :0400000010111213B6\n\
+             :00000001FF\n",
+            "Intel HEX data should be correct"
+        );
+        assert_eq!(
+            image.to_srecord(),
+            "S00E0000667265656D6475203630354E\n\
+             S21400FFF0000102030405060708090A0B0C0D0E0F84\n\
+             S20801000010111213B0\n\
+             S804000000FB\n",
+            "S-record data should be correct"
+        );
+    }
+
+    #[tokio::test]
+    async fn dump_and_resume() -> device::Result<(), Infallible> {
+        init_logger();
+
+        let rom: Vec<u8> = (0x00..=0xff).cycle().take(0x8000).collect();
+        let mut sim = Simulator::new(605, 0x1234, 0x5678).with_rom(0x8000, rom.clone());
+        let mut dev = device::connect(&mut sim).await?;
+        let mut dump = FirmwareDump::for_software_id(605).unwrap();
+
+        assert_eq!(dump.range(), 0x8000..0x0001_0000, "range should be correct");
+        assert_eq!(
+            dump.read_next(dev.interface()).await?,
+            BLOCK_LEN,
+            "block length should be correct"
+        );
+
+        // Resume from the partial data
+        let mut dump = FirmwareDump::for_software_id(605)
+            .unwrap()
+            .with_data(dump.data().to_vec());
+
+        assert_eq!(
+            dump.verify(dev.interface(), |_, _| {}).await,
+            Err(Error::InvalidState),
+            "result should be state error"
+        );
+
+        while !dump.is_complete() {
+            dump.read_next(dev.interface()).await?;
+        }
+
+        let image = dump.verify(dev.interface(), |_, _| {}).await?;
+
+        assert_eq!(image.base_addr, 0x8000, "base address should be correct");
+        assert_eq!(image.data, rom, "data should be correct");
+
+        let corrupted = FirmwareDump::for_software_id(605)
+            .unwrap()
+            .with_data(vec![0x00; 0x8000]);
+
+        assert_eq!(
+            corrupted.verify(dev.interface(), |_, _| {}).await,
+            Err(Error::VerificationFailed),
+            "result should be verification error"
+        );
+
+        Ok(())
+    }
+}
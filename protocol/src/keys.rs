@@ -0,0 +1,158 @@
+//! Database of known diagnostic keys.
+//!
+//! The diagnostic interface is protected by a pair of keys, which is typically
+//! shared by all devices with the same software ID. Different software IDs
+//! often share their keys as well, which allows unlocking devices that are not
+//! supported by the [`device`](crate::device) module.
+//!
+//! A [`KeyDatabase`] maps software IDs to candidate unlock sequences, consisting of
+//! a key pair and an optional write disabling the ROM readout protection.
+//! It is used by [`Interface::unlock_with_known_keys`](crate::Interface::unlock_with_known_keys)
+//! to unlock a device without a device-specific implementation.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{KeyPair, keys::KeyDatabase};
+//!
+//! let mut db = KeyDatabase::builtin();
+//!
+//! db.insert(1234, KeyPair::new(0xabcd, 0xef01));
+//!
+//! assert_eq!(db.candidates(1234).next(), Some(KeyPair::new(0xabcd, 0xef01).into()));
+//! ```
+
+use crate::{
+    UnlockSequence,
+    device::{id218, id324, id360, id419, id469, id517, id605, id629, id2088, id2895},
+};
+use alloc::vec::Vec;
+
+/// Unlock sequences of the devices supported by the [`device`](crate::device) module.
+const BUILTIN_KEYS: [(u16, UnlockSequence); 10] = [
+    (218, id218::UNLOCK_SEQUENCE),
+    (324, id324::UNLOCK_SEQUENCE),
+    (360, id360::UNLOCK_SEQUENCE),
+    (419, id419::UNLOCK_SEQUENCE),
+    (469, id469::UNLOCK_SEQUENCE),
+    (517, id517::UNLOCK_SEQUENCE),
+    (605, id605::UNLOCK_SEQUENCE),
+    (629, id629::UNLOCK_SEQUENCE),
+    (2088, id2088::UNLOCK_SEQUENCE),
+    (2895, id2895::UNLOCK_SEQUENCE),
+];
+
+/// Mapping of software IDs to candidate unlock sequences.
+///
+/// Multiple sequences can be registered for the same software ID.
+/// They are tried in the order they were inserted.
+#[derive(Clone, Default, Debug)]
+pub struct KeyDatabase {
+    entries: Vec<(u16, UnlockSequence)>,
+}
+
+impl KeyDatabase {
+    /// Constructs an empty database.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a database containing the unlock sequences of all supported devices.
+    #[must_use]
+    pub fn builtin() -> Self {
+        Self {
+            entries: BUILTIN_KEYS.to_vec(),
+        }
+    }
+
+    /// Adds a candidate unlock sequence or key pair for the specified software ID.
+    ///
+    /// The sequence is tried after all previously added sequences for the same software ID.
+    pub fn insert(&mut self, software_id: u16, seq: impl Into<UnlockSequence>) {
+        let entry = (software_id, seq.into());
+
+        if !self.entries.contains(&entry) {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Returns the unlock sequences registered for the specified software ID.
+    pub fn get(&self, software_id: u16) -> impl Iterator<Item = UnlockSequence> {
+        self.entries
+            .iter()
+            .filter(move |(id, _)| *id == software_id)
+            .map(|(_, seq)| *seq)
+    }
+
+    /// Returns all candidate unlock sequences for the specified software ID.
+    ///
+    /// The sequences registered for the software ID come first,
+    /// followed by the key pairs of all other known sequences, each returned only once.
+    /// The ROM readout protection is only disabled for sequences registered for the software ID,
+    /// as its address differs between firmware versions.
+    pub fn candidates(&self, software_id: u16) -> impl Iterator<Item = UnlockSequence> {
+        let mut seqs: Vec<_> = self.get(software_id).collect();
+
+        for (_, other) in &self.entries {
+            if !seqs.iter().any(|seq| seq.keys == other.keys) {
+                seqs.push(other.keys.into());
+            }
+        }
+
+        seqs.into_iter()
+    }
+}
+
+impl<S: Into<UnlockSequence>> Extend<(u16, S)> for KeyDatabase {
+    fn extend<T: IntoIterator<Item = (u16, S)>>(&mut self, iter: T) {
+        for (software_id, seq) in iter {
+            self.insert(software_id, seq);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KeyPair, RomUnlock};
+
+    #[test]
+    fn candidates() {
+        let mut db = KeyDatabase::builtin();
+
+        db.insert(517, KeyPair::new(0x1111, 0x2222));
+        db.insert(1234, KeyPair::new(0x3333, 0x4444));
+
+        assert_eq!(
+            db.get(517).collect::<Vec<_>>(),
+            [
+                UnlockSequence {
+                    keys: KeyPair::new(0x8542, 0x6567),
+                    rom_unlock: Some(RomUnlock {
+                        addr: 0x00f4,
+                        val: 0x02,
+                    }),
+                },
+                KeyPair::new(0x1111, 0x2222).into(),
+            ],
+            "sequences should be correct"
+        );
+        assert!(
+            db.candidates(1234).all(|seq| seq.rom_unlock.is_none()),
+            "ROM readout protection should only be disabled for registered sequences"
+        );
+        assert_eq!(
+            db.candidates(1234).map(|seq| seq.keys).collect::<Vec<_>>(),
+            [
+                KeyPair::new(0x3333, 0x4444),
+                KeyPair::new(0x43ea, 0x1f02),
+                KeyPair::new(0xb4ee, 0x4e83),
+                KeyPair::new(0x8542, 0x6567),
+                KeyPair::new(0x1234, 0x5678),
+                KeyPair::new(0x1111, 0x2222),
+            ],
+            "candidates should be correct"
+        );
+    }
+}
@@ -0,0 +1,557 @@
+//! Resumable search for unknown diagnostic keys.
+//!
+//! Devices whose keys are unknown can be unlocked by trying all possible keys.
+//! A [`KeySearch`] first tries the key pairs from a [`KeyDatabase`], as many devices
+//! share their keys. It then brute-forces the read access key, followed by the
+//! full access key, resulting in up to 2 × 65536 attempts.
+//!
+//! After each attempt, a [`Status`] containing the current [`Progress`] and an estimate
+//! of the remaining time is reported. The progress can be persisted using its text form
+//! and passed to [`KeySearch::with_progress`] to resume an interrupted search.
+//! If the search fails due to a communication error, the progress is preserved
+//! and [`KeySearch::run`] can simply be called again.
+//!
+//! Unlike [`Interface::halt`], which is used by traditional key searches,
+//! the default [`FullAccessProbe`] does not affect the device's operation.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{Interface, KeyPair, keysearch::KeySearch, simulator::Simulator};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::Result<(), core::convert::Infallible> {
+//! let mut sim = Simulator::new(1234, 0x43ea, 0x1f02).with_ram(0x0000, [0x00]);
+//! let mut intf = Interface::new(&mut sim);
+//! let mut search = KeySearch::new();
+//!
+//! let keys = search
+//!     .run(&mut intf, |status| println!("{}", status.progress))
+//!     .await?;
+//!
+//! assert_eq!(keys, KeyPair::new(0x43ea, 0x1f02));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Error, Interface, KeyPair, Result, UnlockSequence, keys::KeyDatabase, time::Clock};
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::{Debug, Display, Formatter},
+    str::FromStr,
+    time::Duration,
+};
+use embedded_io_async::{Read, Write};
+use log::{debug, trace};
+
+/// Method used to verify that full access has been unlocked.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
+pub enum FullAccessProbe {
+    /// Writes zero bytes to the device's memory, which has no side effects.
+    #[default]
+    EmptyWrite,
+    /// Halts the device using [`Interface::halt`].
+    ///
+    /// This can be used for devices that do not accept empty memory writes,
+    /// but stops the appliance once the key has been found.
+    Halt,
+}
+
+/// Progress of a key search.
+///
+/// The text form is a single line, e.g. `read 1a2b` or `full 43ea 0000`.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Progress {
+    /// Trying the known key pairs, starting at the specified candidate index.
+    ///
+    /// The index refers to the candidates returned by [`KeyDatabase::candidates`]
+    /// for the device's software ID.
+    Known {
+        /// Index of the next candidate.
+        index: usize,
+        /// Read access key found while trying the candidates.
+        read: Option<u16>,
+    },
+    /// Searching the read access key.
+    Read {
+        /// Next key to try.
+        next: u16,
+    },
+    /// Searching the full access key.
+    Full {
+        /// Read access key that has been found.
+        read: u16,
+        /// Next key to try.
+        next: u16,
+    },
+    /// Both keys have been found.
+    Found(KeyPair),
+}
+
+impl Progress {
+    /// Returns the progress of a newly started search.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::Known {
+            index: 0,
+            read: None,
+        }
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            Self::Known { index, read: None } => write!(f, "known {index}"),
+            Self::Known {
+                index,
+                read: Some(read),
+            } => write!(f, "known {index} {read:04x}"),
+            Self::Read { next } => write!(f, "read {next:04x}"),
+            Self::Full { read, next } => write!(f, "full {read:04x} {next:04x}"),
+            Self::Found(keys) => write!(f, "found {:04x} {:04x}", keys.read, keys.full),
+        }
+    }
+}
+
+/// Error returned when parsing an invalid key search progress.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct ParseProgressError;
+
+impl Display for ParseProgressError {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "invalid key search progress")
+    }
+}
+
+impl core::error::Error for ParseProgressError {}
+
+impl FromStr for Progress {
+    type Err = ParseProgressError;
+
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
+        let fields: Vec<_> = s.split_ascii_whitespace().collect();
+        let key = |field: &str| u16::from_str_radix(field, 16).map_err(|_| ParseProgressError);
+
+        match fields.as_slice() {
+            ["known", index] => Ok(Self::Known {
+                index: index.parse().map_err(|_| ParseProgressError)?,
+                read: None,
+            }),
+            ["known", index, read] => Ok(Self::Known {
+                index: index.parse().map_err(|_| ParseProgressError)?,
+                read: Some(key(read)?),
+            }),
+            ["read", next] => Ok(Self::Read { next: key(next)? }),
+            ["full", read, next] => Ok(Self::Full {
+                read: key(read)?,
+                next: key(next)?,
+            }),
+            ["found", read, full] => Ok(Self::Found(KeyPair::new(key(read)?, key(full)?))),
+            _ => Err(ParseProgressError),
+        }
+    }
+}
+
+/// Status reported after each attempt of a key search.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Status {
+    /// Current progress, which can be persisted to resume the search.
+    pub progress: Progress,
+    /// Number of attempts since [`KeySearch::run`] was called.
+    pub attempts: u32,
+    /// Number of remaining attempts in the current stage.
+    pub remaining: u32,
+    /// Estimated time until the current stage is exhausted.
+    ///
+    /// Only available if a clock was provided using [`KeySearch::with_clock`].
+    pub eta: Option<Duration>,
+}
+
+/// Resumable search for a device's diagnostic keys.
+#[derive(Debug)]
+pub struct KeySearch {
+    db: KeyDatabase,
+    probe: FullAccessProbe,
+    progress: Progress,
+    clock: Option<Box<dyn Clock>>,
+}
+
+impl KeySearch {
+    /// Constructs a new search using the built-in key database.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            db: KeyDatabase::builtin(),
+            probe: FullAccessProbe::default(),
+            progress: Progress::new(),
+            clock: None,
+        }
+    }
+
+    /// Uses the provided database for the known key pairs.
+    #[must_use]
+    pub fn with_database(mut self, db: KeyDatabase) -> Self {
+        self.db = db;
+        self
+    }
+
+    /// Uses the specified method to verify full access.
+    #[must_use]
+    pub fn with_probe(mut self, probe: FullAccessProbe) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    /// Resumes the search from a previously reported progress.
+    #[must_use]
+    pub fn with_progress(mut self, progress: Progress) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Estimates the remaining time using the provided clock.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Returns the current progress.
+    #[must_use]
+    pub fn progress(&self) -> Progress {
+        self.progress
+    }
+
+    /// Runs the search until the keys are found.
+    ///
+    /// The provided callback is invoked after each attempt.
+    /// On success, the interface is left with full access unlocked
+    /// and the keys are remembered like with [`Interface::unlock`].
+    ///
+    /// Returns [`Error::KeysNotFound`] if all keys have been tried unsuccessfully.
+    /// On any other error, the progress is preserved and the search can be continued.
+    pub async fn run<P: Read + Write>(
+        &mut self,
+        intf: &mut Interface<P>,
+        mut status: impl FnMut(&Status),
+    ) -> Result<KeyPair, P::Error> {
+        let software_id = intf.query_software_id().await?;
+        let candidates: Vec<_> = self
+            .db
+            .candidates(software_id)
+            .map(|seq| seq.keys)
+            .collect();
+        let start = self.clock.as_ref().map(|clock| clock.now());
+        let mut attempts = 0;
+
+        debug!(
+            "Searching keys for software ID {software_id} from {}",
+            self.progress
+        );
+
+        loop {
+            self.progress = match self.progress {
+                Progress::Known { index, read } if index >= candidates.len() => {
+                    // Continue with the brute-force search
+                    self.progress = read.map_or(Progress::Read { next: 0x0000 }, |read| {
+                        Progress::Full { read, next: 0x0000 }
+                    });
+
+                    continue;
+                }
+                Progress::Known { index, read } => {
+                    let keys = candidates[index];
+
+                    if !self.attempt(intf, keys.read, None).await? {
+                        Progress::Known {
+                            index: index + 1,
+                            read,
+                        }
+                    } else if self.attempt(intf, keys.read, Some(keys.full)).await? {
+                        Progress::Found(keys)
+                    } else {
+                        Progress::Known {
+                            index: index + 1,
+                            read: Some(keys.read),
+                        }
+                    }
+                }
+                Progress::Read { next } => {
+                    if self.attempt(intf, next, None).await? {
+                        Progress::Full {
+                            read: next,
+                            next: 0x0000,
+                        }
+                    } else {
+                        Progress::Read {
+                            next: next.checked_add(1).ok_or(Error::KeysNotFound)?,
+                        }
+                    }
+                }
+                Progress::Full { read, next } => {
+                    if self.attempt(intf, read, Some(next)).await? {
+                        Progress::Found(KeyPair::new(read, next))
+                    } else {
+                        Progress::Full {
+                            read,
+                            next: next.checked_add(1).ok_or(Error::KeysNotFound)?,
+                        }
+                    }
+                }
+                Progress::Found(keys) => {
+                    // Keys from a resumed search have not been applied yet
+                    if attempts == 0 && !self.attempt(intf, keys.read, Some(keys.full)).await? {
+                        Progress::new()
+                    } else {
+                        debug!("Found keys {keys:x?}");
+
+                        intf.unlock_seq = Some(UnlockSequence {
+                            keys,
+                            rom_unlock: None,
+                        });
+
+                        return Ok(keys);
+                    }
+                }
+            };
+            attempts += 1;
+
+            status(&self.status(&candidates, start, attempts));
+        }
+    }
+
+    /// Unlocks the interface using the specified keys and verifies the access level.
+    async fn attempt<P: Read + Write>(
+        &self,
+        intf: &mut Interface<P>,
+        read: u16,
+        full: Option<u16>,
+    ) -> Result<bool, P::Error> {
+        trace!("Trying keys {read:04x}, {full:04x?}");
+
+        // Device expects the software ID query before each unlock attempt
+        intf.query_software_id().await?;
+        intf.unlock_read_access_inner(read).await?;
+
+        let Some(full) = full else {
+            return intf.probe_read_access().await;
+        };
+
+        intf.unlock_full_access_inner(full).await?;
+
+        match self.probe {
+            FullAccessProbe::EmptyWrite => intf.probe_empty_write().await,
+            FullAccessProbe::Halt => match intf.halt().await {
+                Ok(()) => Ok(true),
+                Err(Error::InvalidCommand) => Ok(false),
+                Err(err) => Err(err),
+            },
+        }
+    }
+
+    /// Computes the status after the specified number of attempts.
+    fn status(&self, candidates: &[KeyPair], start: Option<Duration>, attempts: u32) -> Status {
+        let remaining = match self.progress {
+            Progress::Known { index, .. } => {
+                u32::try_from(candidates.len().saturating_sub(index)).unwrap_or(u32::MAX)
+            }
+            Progress::Read { next } | Progress::Full { next, .. } => 0x10000 - u32::from(next),
+            Progress::Found(_) => 0,
+        };
+        let eta = self
+            .clock
+            .as_ref()
+            .zip(start)
+            .map(|(clock, start)| clock.now().saturating_sub(start) / attempts * remaining);
+
+        Status {
+            progress: self.progress,
+            attempts,
+            remaining,
+            eta,
+        }
+    }
+}
+
+impl Default for KeySearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessLevel, simulator::Simulator, tests::init_logger};
+    use alloc::{string::ToString, vec};
+    use core::convert::Infallible;
+
+    #[tokio::test]
+    async fn brute_force() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = Simulator::new(1234, 0x0003, 0x0002).with_ram(0x0000, [0x00]);
+        let mut intf = Interface::new(&mut sim);
+        let mut search = KeySearch::new().with_database(KeyDatabase::new());
+        let mut statuses = Vec::new();
+        let keys = search
+            .run(&mut intf, |status| statuses.push(status.progress))
+            .await?;
+
+        assert_eq!(keys, KeyPair::new(0x0003, 0x0002), "keys should be correct");
+        assert_eq!(
+            statuses,
+            [
+                Progress::Read { next: 0x0001 },
+                Progress::Read { next: 0x0002 },
+                Progress::Read { next: 0x0003 },
+                Progress::Full {
+                    read: 0x0003,
+                    next: 0x0000
+                },
+                Progress::Full {
+                    read: 0x0003,
+                    next: 0x0001
+                },
+                Progress::Full {
+                    read: 0x0003,
+                    next: 0x0002
+                },
+                Progress::Found(keys),
+            ],
+            "progress should be correct"
+        );
+        assert_eq!(
+            intf.access_level(),
+            AccessLevel::Full,
+            "access level should be full"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn known_keys() -> Result<(), Infallible> {
+        init_logger();
+
+        // Read access key is shared with a known device
+        let mut sim = Simulator::new(1234, 0x43ea, 0x0001).with_ram(0x0000, [0x00]);
+        let mut intf = Interface::new(&mut sim);
+        let mut db = KeyDatabase::new();
+
+        db.insert(1234, KeyPair::new(0x43ea, 0x1f02));
+
+        let mut search = KeySearch::new()
+            .with_database(db)
+            .with_probe(FullAccessProbe::Halt);
+        let mut statuses = Vec::new();
+        let keys = search
+            .run(&mut intf, |status| statuses.push(status.progress))
+            .await?;
+
+        assert_eq!(keys, KeyPair::new(0x43ea, 0x0001), "keys should be correct");
+        assert_eq!(
+            statuses[..2],
+            [
+                Progress::Known {
+                    index: 1,
+                    read: Some(0x43ea)
+                },
+                Progress::Full {
+                    read: 0x43ea,
+                    next: 0x0001
+                },
+            ],
+            "progress should be correct"
+        );
+        assert!(sim.is_halted(), "device should be halted");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = Simulator::new(1234, 0x1000, 0x2000).with_ram(0x0000, [0x00]);
+        let mut intf = Interface::new(&mut sim);
+        let progress = "full 1000 1ffe".parse().unwrap();
+        let mut search = KeySearch::new()
+            .with_progress(progress)
+            .with_clock(|| Duration::ZERO);
+        let mut statuses = vec![];
+
+        search
+            .run(&mut intf, |status| statuses.push(*status))
+            .await?;
+
+        assert_eq!(
+            statuses[0],
+            Status {
+                progress: Progress::Full {
+                    read: 0x1000,
+                    next: 0x1fff
+                },
+                attempts: 1,
+                remaining: 0xe001,
+                eta: Some(Duration::ZERO),
+            },
+            "status should be correct"
+        );
+        assert_eq!(
+            search.progress().to_string(),
+            "found 1000 2000",
+            "progress should be correct"
+        );
+
+        // Resuming a finished search unlocks the interface again
+        intf.lock().await?;
+
+        let keys = search.run(&mut intf, |_| {}).await?;
+
+        assert_eq!(keys, KeyPair::new(0x1000, 0x2000), "keys should be correct");
+        assert_eq!(
+            intf.access_level(),
+            AccessLevel::Full,
+            "access level should be full"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_progress() {
+        for progress in [
+            Progress::new(),
+            Progress::Known {
+                index: 3,
+                read: Some(0xabcd),
+            },
+            Progress::Read { next: 0x1234 },
+            Progress::Full {
+                read: 0x43ea,
+                next: 0xffff,
+            },
+            Progress::Found(KeyPair::new(0x43ea, 0x1f02)),
+        ] {
+            assert_eq!(
+                progress.to_string().parse(),
+                Ok(progress),
+                "progress should be preserved"
+            );
+        }
+
+        assert_eq!(
+            "read 10000".parse::<Progress>(),
+            Err(ParseProgressError),
+            "result should be parse error"
+        );
+    }
+}
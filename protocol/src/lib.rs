@@ -10,7 +10,24 @@
 //!
 //! - Use the high-level [`device`] module to query diagnostic properties and trigger actions.
 //! - Instantiate device implementations (e.g. [`device::id629`]) to access model-specific methods.
+//! - Add support for new devices at runtime using TOML definitions (requires the `definitions` feature).
+//! - Explore unsupported devices using a [`device::generic::GenericDevice`].
+//! - Register out-of-tree device implementations in a [`device::DeviceRegistry`].
+//! - Serialize properties and their values to JSON (requires the `serde` feature).
+//! - React to property changes using a [`device::watch::PropertyWatcher`].
+//! - Keep a history of program runs and estimate their remaining time using a
+//!   [`device::cycles::CycleTracker`].
+//! - Record when and how often faults occur using a [`device::faults::FaultLog`].
 //! - Work directly with the low-level diagnostic [`Interface`].
+//! - Enforce the diagnostic access levels at compile time using the [`state`] module.
+//! - Test tools and applications without an appliance using the [`simulator`].
+//! - Back up and restore a device's EEPROM using the [`backup`] module.
+//! - Record and replay diagnostic sessions using the [`capture`] module.
+//! - Decode raw protocol traffic using the [`dissector`].
+//! - Extract and archive device firmware using the [`firmware`] module.
+//! - Unlock devices without a device-specific implementation using the [`keys`] database.
+//! - Find the keys of unknown devices using a resumable [`keysearch`].
+//! - Track down memory addresses of new devices using the memory [`watcher`].
 //!
 //! # Getting started
 //!
@@ -130,6 +147,8 @@
 //! Once fully unlocked, the appliance accepts all diagnostic commands.
 //! However, the interface automatically locks again after 3 seconds
 //! of inactivity, so tools must send commands periodically to maintain access.
+//! Alternatively, [`Interface::enable_session`] transparently unlocks
+//! the interface again whenever the session has expired.
 
 #![no_std]
 #![warn(missing_docs)]
@@ -137,21 +156,60 @@
 
 extern crate alloc;
 
+pub mod backup;
+pub mod capture;
 pub mod device;
+pub mod dissector;
+pub mod firmware;
+pub mod keys;
+pub mod keysearch;
+pub mod simulator;
+pub mod state;
+pub mod time;
+pub mod watcher;
 
 #[cfg(feature = "native-serial")]
 #[cfg_attr(docsrs, doc(cfg(feature = "native-serial")))]
 pub mod serial;
 
+pub use embedded_hal_async;
 pub use embedded_io_async;
 
+use alloc::{boxed::Box, vec, vec::Vec};
 use core::{
     fmt::{Debug, Display, Formatter},
+    marker::PhantomData,
     num::Wrapping,
+    ops::Range,
+    time::Duration,
+};
+use embassy_futures::select::{self, Either};
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{ErrorType, Read, ReadExactError, Write};
+use keys::KeyDatabase;
+use log::{debug, trace};
+use state::{
+    AccessState, CanConfigure, CanQuery, CanRead, CanWrite, Dynamic, FullAccess, Locked,
+    ReadAccess, SmartHomeAccess, TypedState,
 };
-use embedded_io_async::{Read, ReadExactError, Write};
-use log::trace;
 use strum::FromRepr;
+use time::{Clock, LOCK_TIMEOUT, Timeouts, Timer};
+
+/// Idle time after which the session is considered to be locked.
+///
+/// Slightly shorter than the device's lock timeout to account for transmission delays.
+const SESSION_TIMEOUT: Duration = LOCK_TIMEOUT.saturating_sub(Duration::from_millis(500));
+
+/// Idle time after which [`Interface::keep_alive`] sends a command to the device.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum gap between memory ranges merged by [`Interface::prefetch_memory`].
+///
+/// Reading a few unneeded bytes is faster than the overhead of an additional frame.
+const PREFETCH_MAX_GAP: u32 = 16;
+
+/// Default chunk size of diagnostic frames, adjustable on newer devices.
+pub(crate) const DEFAULT_CHUNK_SIZE: u8 = 4;
 
 /// A specialized [`Result`] type for [`Interface`] operations.
 ///
@@ -174,8 +232,16 @@ pub enum Error<E> {
     InvalidCommand,
     /// The device returned an invalid response.
     InvalidResponse,
+    /// The interface is not in the required state for the operation.
+    InvalidState,
     /// The port encountered an unexpected end-of-file.
     UnexpectedEof,
+    /// The device did not respond in time.
+    Timeout,
+    /// None of the known keys unlocked the device.
+    KeysNotFound,
+    /// The operation modifies the device, but the interface is read-only.
+    ReadOnly,
     /// A port-specific input/output error.
     Io(E),
 }
@@ -187,7 +253,11 @@ impl<E: core::error::Error> Display for Error<E> {
             Self::IncorrectChecksum => write!(f, "incorrect checksum"),
             Self::InvalidCommand => write!(f, "invalid command"),
             Self::InvalidResponse => write!(f, "invalid response"),
+            Self::InvalidState => write!(f, "invalid state"),
             Self::UnexpectedEof => write!(f, "unexpected end-of-file"),
+            Self::Timeout => write!(f, "timeout"),
+            Self::KeysNotFound => write!(f, "keys not found"),
+            Self::ReadOnly => write!(f, "interface is read-only"),
             Self::Io(err) => write!(f, "input/output error: {err}"),
         }
     }
@@ -246,33 +316,148 @@ impl BaudRate {
     }
 }
 
+/// Port whose baud rate can be reconfigured at runtime.
+///
+/// Used by [`Interface::negotiate_fast_mode`] to follow the baud rate changes of the device.
+pub trait ConfigurableBaudRate: ErrorType {
+    /// Reconfigures the port to use the specified baud rate.
+    fn set_baud_rate(&mut self, rate: BaudRate) -> core::result::Result<(), Self::Error>;
+}
+
+impl<T: ConfigurableBaudRate + ?Sized> ConfigurableBaudRate for &mut T {
+    fn set_baud_rate(&mut self, rate: BaudRate) -> core::result::Result<(), Self::Error> {
+        T::set_baud_rate(self, rate)
+    }
+}
+
+/// Diagnostic access level of the interface.
+///
+/// The access level determines which commands are accepted by the device.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum AccessLevel {
+    /// Interface is locked. Only the unlock sequence is accepted.
+    Locked,
+    /// Smart home access, see [`Interface::unlock_smart_home_access`].
+    SmartHome,
+    /// Read-only access, see [`Interface::unlock_read_access`].
+    Read,
+    /// Full access, see [`Interface::unlock_full_access`].
+    Full,
+}
+
+impl AccessLevel {
+    /// Returns whether this access level includes the permissions of another level.
+    #[must_use]
+    pub fn includes(self, other: Self) -> bool {
+        match (self, other) {
+            (_, Self::Locked) | (Self::Full, _) => true,
+            (level, other) => level == other,
+        }
+    }
+}
+
+/// Pair of keys required to unlock the diagnostic interface.
+///
+/// The keys are typically unique for each software ID.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct KeyPair {
+    /// Key passed to [`Interface::unlock_read_access`].
+    pub read: u16,
+    /// Key passed to [`Interface::unlock_full_access`].
+    pub full: u16,
+}
+
+impl KeyPair {
+    /// Constructs a new key pair.
+    #[must_use]
+    pub const fn new(read: u16, full: u16) -> Self {
+        Self { read, full }
+    }
+}
+
+/// Memory write that disables a device's ROM readout protection.
+///
+/// Many devices prevent reading memory above a certain address
+/// until a specific value is written to a RAM location.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct RomUnlock {
+    /// Memory address to write to.
+    pub addr: u32,
+    /// Value to write.
+    pub val: u8,
+}
+
+/// Sequence of operations that fully unlocks a device's diagnostic interface.
+///
+/// Used by [`Interface::unlock`], which also remembers the sequence
+/// so it can be replayed when a session is restored.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct UnlockSequence {
+    /// Read and full access keys.
+    pub keys: KeyPair,
+    /// Optional write disabling the ROM readout protection.
+    pub rom_unlock: Option<RomUnlock>,
+}
+
+impl From<KeyPair> for UnlockSequence {
+    fn from(keys: KeyPair) -> Self {
+        Self {
+            keys,
+            rom_unlock: None,
+        }
+    }
+}
+
 /// Command code used by the diagnostic interface.
-#[derive(Debug)]
+///
+/// Exposed for decoding raw protocol traffic, see the [`dissector`] module.
+/// Commands are sent by the [`Interface`] methods and cannot be sent directly.
+#[non_exhaustive]
+#[derive(FromRepr, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
-enum Command {
+pub enum Command {
+    /// Locks the diagnostic interface.
     Lock = 0x10,
+    /// Queries the software ID.
     QuerySoftwareId = 0x11,
+    /// Unlocks read access using the parameter as key.
     UnlockReadAccess = 0x20,
-    UnlockSmartHomeAccess = 0x21, // Available on newer devices
+    /// Unlocks smart home access. Available on newer devices.
+    UnlockSmartHomeAccess = 0x21,
+    /// Reads from memory.
     ReadMemory = 0x30,
+    /// Reads from EEPROM.
     ReadEeprom = 0x31,
+    /// Unlocks full access using the parameter as key.
     UnlockFullAccess = 0x32,
-    ExtendAddress = 0x37,    // Available on newer devices
-    QueryMaxBaudRate = 0x38, // Available on newer devices
+    /// Provides the upper address and length bits for the next request. Available on newer devices.
+    ExtendAddress = 0x37,
+    /// Queries the maximum supported baud rate. Available on newer devices.
+    QueryMaxBaudRate = 0x38,
+    /// Writes to memory.
     WriteMemory = 0x40,
+    /// Writes to EEPROM.
     WriteEeprom = 0x41,
+    /// Jumps to a subroutine.
     JumpToSubroutine = 0x42,
+    /// Halts the microcontroller.
     Halt = 0x45,
+    /// Switches to 2400 baud.
     SetBaudRate2400 = 0x46,
+    /// Switches to 9600 baud.
     SetBaudRate9600 = 0x47,
-    SetChunkSize = 0x4a,     // Available on newer devices
-    SetBaudRate = 0x4b,      // Available on newer devices
-    Reset = 0x4e,            // Available on newer devices
-    RequestSmartHome = 0x85, // Available on newer devices
+    /// Sets the chunk size. Available on newer devices.
+    SetChunkSize = 0x4a,
+    /// Switches to the baud rate specified by the parameter. Available on newer devices.
+    SetBaudRate = 0x4b,
+    /// Resets the microcontroller. Available on newer devices.
+    Reset = 0x4e,
+    /// Sends a smart home request. Available on newer devices.
+    RequestSmartHome = 0x85,
 }
 
 /// Request frame sent to the diagnostic interface.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 struct Request {
     cmd: Command,
     param: u16,
@@ -305,14 +490,29 @@ impl From<Request> for Payload<4> {
 ///
 /// Used in communication with the device, both when
 /// sending requests and when interpreting responses.
-#[derive(FromRepr, Debug)]
+#[derive(FromRepr, PartialEq, Eq, Copy, Clone, Debug)]
 #[repr(u8)]
-enum ResponseCode {
+pub enum ResponseCode {
+    /// The frame was received successfully.
     Success,
+    /// The frame has an incorrect checksum.
     IncorrectChecksum,
+    /// The command is unknown or not permitted at the current access level.
     InvalidCommand,
 }
 
+/// EEPROM addressing mode of a device.
+///
+/// Older devices expect EEPROM addresses to be specified in 16-bit words,
+/// while newer devices address individual bytes.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum EepromAddressing {
+    /// Addresses refer to individual bytes.
+    Byte,
+    /// Addresses refer to 16-bit words.
+    Word,
+}
+
 /// Diagnostic interface payload.
 ///
 /// Wraps a fixed-size byte array used for communication with the device.
@@ -404,10 +604,31 @@ impl From<Payload<4>> for u32 {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// The access level is tracked at runtime by default.
+/// To enforce it at compile time instead, see the [`state`] module.
 #[derive(Debug)]
-pub struct Interface<P> {
+pub struct Interface<P, S = Dynamic> {
     port: P,
     chunk_size: u8,
+    baud_rate: BaudRate,
+    extended_addressing: bool,
+    eeprom_addressing: EepromAddressing,
+    access: AccessLevel,
+    unlock_seq: Option<UnlockSequence>,
+    session: Option<Session>,
+    timer: Option<(Box<dyn Timer>, Timeouts)>,
+    max_retries: u8,
+    read_only: bool,
+    prefetched: Vec<(u32, Vec<u8>)>,
+    state: PhantomData<S>,
+}
+
+/// State of an automatically managed diagnostic session.
+#[derive(Debug)]
+struct Session {
+    clock: Box<dyn Clock>,
+    last_command: Option<Duration>,
 }
 
 impl<P: Read + Write> Interface<P> {
@@ -415,32 +636,48 @@ impl<P: Read + Write> Interface<P> {
     pub fn new(port: P) -> Self {
         Self {
             port,
-            chunk_size: 4, // Default size, adjustable on newer devices
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            baud_rate: BaudRate::Baud2400,
+            extended_addressing: false,
+            eeprom_addressing: EepromAddressing::Word,
+            access: AccessLevel::Locked,
+            unlock_seq: None,
+            session: None,
+            timer: None,
+            max_retries: 0,
+            read_only: false,
+            prefetched: Vec::new(),
+            state: PhantomData,
         }
     }
 
-    /// Locks the diagnostic interface.
+    /// Converts the interface into the typestate API, see the [`state`] module.
     ///
-    /// This command resets the device's diagnostic access level.
-    /// After locking, any further diagnostic operations require
-    /// repeating the full unlock sequence.
-    pub async fn lock(&mut self) -> Result<(), P::Error> {
-        self.send(Request::new(Command::Lock, 0x0000, 0x00).into())
-            .await
+    /// The resulting interface is in the [`Locked`] state, regardless of the current access level.
+    pub fn into_typed(self) -> Interface<P, Locked> {
+        self.into_state()
     }
 
-    /// Queries the software ID of the device.
+    /// Unlocks full diagnostic access using the provided sequence.
     ///
-    /// This number identifies the software/firmware running on the device.
-    /// Note that different electronics boards can share the same software ID.
+    /// Before calling this function, the software ID must be
+    /// queried using [`Interface::query_software_id`].
+    /// Unlocks read and full access and disables the ROM readout protection, if required.
     ///
-    /// This must be called first as part of the unlock sequence.
-    /// See [`Interface::unlock_read_access`] for the next step.
-    pub async fn query_software_id(&mut self) -> Result<u16, P::Error> {
-        self.send(Request::new(Command::QuerySoftwareId, 0x0000, 0x02).into())
-            .await?;
+    /// The sequence is remembered and replayed automatically
+    /// when session management is enabled via [`Interface::enable_session`].
+    /// In read-only mode, only read access is unlocked, see [`Interface::set_read_only`].
+    pub async fn unlock(&mut self, seq: UnlockSequence) -> Result<(), P::Error> {
+        self.unlock_inner(seq).await
+    }
 
-        Ok(self.receive().await?.into())
+    /// Locks the diagnostic interface.
+    ///
+    /// This command resets the device's diagnostic access level.
+    /// After locking, any further diagnostic operations require
+    /// repeating the full unlock sequence.
+    pub async fn lock(&mut self) -> Result<(), P::Error> {
+        self.lock_inner().await
     }
 
     /// Unlocks read-only diagnostic access.
@@ -456,8 +693,7 @@ impl<P: Read + Write> Interface<P> {
     /// - [`Interface::query_max_baud_rate`]
     /// - [`Interface::send_smart_home_request`]
     pub async fn unlock_read_access(&mut self, key: u16) -> Result<(), P::Error> {
-        self.send(Request::new(Command::UnlockReadAccess, key, 0x00).into())
-            .await
+        self.unlock_read_access_inner(key).await
     }
 
     /// Unlocks access to the smart home functionality.
@@ -476,84 +712,7 @@ impl<P: Read + Write> Interface<P> {
     /// - [`Interface::set_chunk_size`]
     /// - [`Interface::send_smart_home_request`]
     pub async fn unlock_smart_home_access(&mut self) -> Result<(), P::Error> {
-        self.send(Request::new(Command::UnlockSmartHomeAccess, 0x0000, 0x00).into())
-            .await
-    }
-
-    /// Reads data from the device's memory.
-    ///
-    /// Newer devices support reading up to 65535 bytes from a 32-bit memory address,
-    /// while older devices are limited to 255 bytes and 16-bit addresses.
-    ///
-    /// # Errors
-    ///
-    /// - [`Error::InvalidArgument`] if the payload length exceeds 65535 bytes.
-    pub async fn read_memory<L: From<Payload<N>>, const N: usize>(
-        &mut self,
-        addr: u32,
-    ) -> Result<L, P::Error> {
-        let len: u16 = N.try_into().map_err(|_| Error::InvalidArgument)?;
-
-        // Send upper bytes of address or length
-        if addr > 0xffff || len > 0xff {
-            self.send(
-                Request::new(
-                    Command::ExtendAddress,
-                    (addr >> 16) as u16,
-                    (len >> 8) as u8,
-                )
-                .into(),
-            )
-            .await?;
-        }
-
-        self.send(
-            Request::new(
-                Command::ReadMemory,
-                (addr & 0xffff) as u16,
-                (len & 0xff) as u8,
-            )
-            .into(),
-        )
-        .await?;
-
-        Ok(self.receive().await?.into())
-    }
-
-    /// Reads data from the device's EEPROM.
-    ///
-    /// For older devices, the address must be specified in words, not bytes.
-    /// As an example, to read a byte at address `0x64`, provide the word address `0x32`.
-    ///
-    /// # Errors
-    ///
-    /// - [`Error::InvalidArgument`] if the payload length exceeds 255 bytes.
-    pub async fn read_eeprom<L: From<Payload<N>>, const N: usize>(
-        &mut self,
-        addr: u16,
-    ) -> Result<L, P::Error> {
-        let len = N.try_into().map_err(|_| Error::InvalidArgument)?;
-
-        self.send(Request::new(Command::ReadEeprom, addr, len).into())
-            .await?;
-
-        Ok(self.receive().await?.into())
-    }
-
-    /// Queries the device's maximum supported baud rate.
-    ///
-    /// The maximum baud rate can only be queried on newer devices.
-    ///
-    /// # Errors
-    ///
-    /// - [`Error::InvalidResponse`] if the device responds with an invalid baud rate
-    pub async fn query_max_baud_rate(&mut self) -> Result<BaudRate, P::Error> {
-        self.send(Request::new(Command::QueryMaxBaudRate, 0x0000, 0x02).into())
-            .await?;
-
-        let resp: [u8; 2] = self.receive().await?.into();
-
-        BaudRate::from_repr(resp[1]).ok_or(Error::InvalidResponse)
+        self.unlock_smart_home_access_inner().await
     }
 
     /// Unlocks full diagnostic access.
@@ -572,68 +731,7 @@ impl<P: Read + Write> Interface<P> {
     /// - [`Interface::set_chunk_size`]
     /// - [`Interface::reset`]
     pub async fn unlock_full_access(&mut self, key: u16) -> Result<(), P::Error> {
-        self.send(Request::new(Command::UnlockFullAccess, key, 0x00).into())
-            .await
-    }
-
-    /// Writes data to the device's memory.
-    ///
-    /// Newer devices support writing up to 65535 bytes to a 32-bit memory address,
-    /// while older devices are limited to 255 bytes and 16-bit addresses.
-    ///
-    /// # Errors
-    ///
-    /// - [`Error::InvalidArgument`] if the payload length exceeds 65535 bytes.
-    pub async fn write_memory<L: Into<Payload<N>>, const N: usize>(
-        &mut self,
-        addr: u32,
-        payload: L,
-    ) -> Result<(), P::Error> {
-        let len: u16 = N.try_into().map_err(|_| Error::InvalidArgument)?;
-
-        // Send upper bytes of address or length
-        if addr > 0xffff || len > 0xff {
-            self.send(
-                Request::new(
-                    Command::ExtendAddress,
-                    (addr >> 16) as u16,
-                    (len >> 8) as u8,
-                )
-                .into(),
-            )
-            .await?;
-        }
-
-        self.send(
-            Request::new(
-                Command::WriteMemory,
-                (addr & 0xffff) as u16,
-                (len & 0xff) as u8,
-            )
-            .into(),
-        )
-        .await?;
-        self.send(payload.into()).await
-    }
-
-    /// Writes data to the device's EEPROM.
-    ///
-    /// For older devices, the address must be specified in words, not bytes.
-    /// As an example, to write a byte at address `0x64`, provide the word address `0x32`.
-    ///
-    /// # Errors
-    ///
-    /// - [`Error::InvalidArgument`] if the payload length exceeds 255 bytes.
-    pub async fn write_eeprom<L: Into<Payload<N>>, const N: usize>(
-        &mut self,
-        addr: u16,
-        payload: L,
-    ) -> Result<(), P::Error> {
-        let len = N.try_into().map_err(|_| Error::InvalidArgument)?;
-
-        self.send(Request::new(Command::WriteEeprom, addr, len).into())
-            .await?;
-        self.send(payload.into()).await
+        self.unlock_full_access_inner(key).await
     }
 
     /// Jumps to a specified subroutine and waits for it to return.
@@ -645,24 +743,7 @@ impl<P: Read + Write> Interface<P> {
     /// The interface must be unlocked again after this operation
     /// to perform further diagnostic commands.
     pub async fn jump_to_subroutine(&mut self, addr: u32) -> Result<(), P::Error> {
-        // Send upper bytes of address
-        if addr > 0xffff {
-            self.send(Request::new(Command::ExtendAddress, (addr >> 16) as u16, 0x00).into())
-                .await?;
-        }
-
-        // Response is sent once subroutine returns
-        self.send(Request::new(Command::JumpToSubroutine, (addr & 0xffff) as u16, 0x00).into())
-            .await?;
-        self.read(&mut [0x00]).await
-    }
-
-    /// Halts the device's normal operation.
-    ///
-    /// Causes the device to enter an infinite loop.
-    pub async fn halt(&mut self) -> Result<(), P::Error> {
-        self.send(Request::new(Command::Halt, 0x0000, 0x00).into())
-            .await
+        self.jump_to_subroutine_inner(addr).await
     }
 
     /// Sets the device's baud rate.
@@ -677,83 +758,683 @@ impl<P: Read + Write> Interface<P> {
     /// The interface must be unlocked again after this operation
     /// to perform further diagnostic commands.
     ///
+    /// Returns the baud rate selected by the device.
+    ///
     /// Note that this does not change the baud rate of the current port instance.
     /// A new [`Interface`] must be created with a port configured for the selected baud rate.
-    pub async fn set_baud_rate(&mut self, rate: BaudRate) -> Result<(), P::Error> {
-        match rate {
-            BaudRate::Baud2400 => {
-                self.send(Request::new(Command::SetBaudRate2400, 0x0000, 0x00).into())
-                    .await
-            }
-            BaudRate::Baud9600 => {
-                self.send(Request::new(Command::SetBaudRate9600, 0x0000, 0x00).into())
-                    .await
-            }
-            _ => {
-                self.send(Request::new(Command::SetBaudRate, rate as u16, 0x01).into())
-                    .await?;
-
-                // Device responds with actual baud rate
-                let _: u8 = self.receive().await?.into();
+    /// Alternatively, [`Interface::negotiate_fast_mode`] switches both the device
+    /// and a [`ConfigurableBaudRate`] port automatically.
+    pub async fn set_baud_rate(&mut self, rate: BaudRate) -> Result<BaudRate, P::Error> {
+        self.set_baud_rate_inner(rate).await
+    }
 
-                Ok(())
-            }
-        }
+    /// Resets the device's microcontroller.
+    ///
+    /// A reset can only be performed on newer devices.
+    pub async fn reset(&mut self) -> Result<(), P::Error> {
+        self.reset_inner().await
     }
 
-    /// Configures the diagnostic frame chunk size.
+    /// Unlocks full diagnostic access by trying the keys from the provided database.
     ///
-    /// The chunk size can only be adjusted on newer devices.
+    /// Queries the software ID and tries the candidate sequences returned by
+    /// [`KeyDatabase::candidates`] in order. As the device does not report incorrect keys,
+    /// the read key is verified by probing a memory read. No read requires full access,
+    /// so the full key is verified by disabling the ROM readout protection and reading back
+    /// the written value. The full key of sequences without a ROM unlock is not verified.
+    /// In read-only mode, only the read key is verified.
+    /// This allows unlocking devices with unsupported software IDs that share their keys
+    /// with a known device.
     ///
-    /// If the requested size is outside the supported range,
-    /// it is clamped by the device to the nearest supported boundary.
-    /// The supported range is device-specific,
-    /// but is typically between 4 and 128 bytes.
-    pub async fn set_chunk_size(&mut self, size: u8) -> Result<(), P::Error> {
-        self.send(Request::new(Command::SetChunkSize, u16::from(size), 0x01).into())
-            .await?;
+    /// Returns the key pair that unlocked the device, or [`Error::KeysNotFound`]
+    /// if none of the candidates are accepted.
+    /// Like [`Interface::unlock`], the resulting sequence is remembered for session management.
+    pub async fn unlock_with_known_keys(&mut self, db: &KeyDatabase) -> Result<KeyPair, P::Error> {
+        self.unlock_with_known_keys_inner(db).await
+    }
+}
 
-        // Device responds with actual chunk size
-        self.chunk_size = self.receive().await?.into();
+impl<P: Read + Write, S: AccessState> Interface<P, S> {
+    /// Enables response timeouts using the provided delay implementation.
+    ///
+    /// Any runtime-specific timer implementing [`DelayNs`] can be used,
+    /// e.g. `embassy_time::Delay` or [`serial::Delay`] with the `native-serial` feature.
+    /// If the device does not respond within the specified [`Timeouts`],
+    /// the operation fails with [`Error::Timeout`].
+    pub fn enable_timeouts(&mut self, delay: impl DelayNs + 'static, timeouts: Timeouts) {
+        self.timer = Some((Box::new(delay), timeouts));
+    }
 
-        Ok(())
+    /// Disables response timeouts.
+    pub fn disable_timeouts(&mut self) {
+        self.timer = None;
     }
 
-    /// Resets the device's microcontroller.
+    /// Sets the maximum number of retries for commands failing due to an incorrect checksum.
     ///
-    /// A reset can only be performed on newer devices.
-    pub async fn reset(&mut self) -> Result<(), P::Error> {
-        self.send(Request::new(Command::Reset, 0x0000, 0x00).into())
-            .await
+    /// Corrupted frames are rejected by both the device and the interface.
+    /// In that case, the entire command is repeated up to the specified number of times.
+    /// No retries are performed by default.
+    pub fn set_max_retries(&mut self, retries: u8) {
+        self.max_retries = retries;
     }
 
-    /// Sends a smart home request to the device and returns the response.
+    /// Enables or disables read-only mode.
     ///
-    /// Smart home functionality is only supported on newer devices.
+    /// In read-only mode, unlocking stops after read access: the full access key
+    /// and the write disabling the ROM readout protection are never sent.
+    /// All commands modifying the device's memory or operation, e.g. [`Interface::write_memory`],
+    /// fail with [`Error::ReadOnly`] without communicating with the device.
+    /// The baud rate and chunk size can still be configured, provided the device grants
+    /// the required access level, e.g. after [`Interface::unlock_smart_home_access`].
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Returns `true` if the interface is in read-only mode.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Enables automatic session management.
     ///
-    /// # Errors
+    /// The device locks its diagnostic interface after 3 seconds of inactivity
+    /// and after commands like [`Interface::jump_to_subroutine`].
+    /// With session management enabled, the interface tracks the time of the
+    /// last command using the provided [`Clock`], as well as the current access level.
+    /// If the session has expired or the access level is insufficient for the next command,
+    /// the unlock sequence passed to [`Interface::unlock`] is transparently replayed.
     ///
-    /// - [`Error::InvalidArgument`] if the payload length exceeds 255 bytes.
-    pub async fn send_smart_home_request<const M: usize, const N: usize>(
-        &mut self,
-        cmd: u16,
-        payload: Payload<N>,
-    ) -> Result<Payload<M>, P::Error> {
-        let len = N.try_into().map_err(|_| Error::InvalidArgument)?;
+    /// Use [`Interface::keep_alive`] to prevent the session from expiring.
+    pub fn enable_session(&mut self, clock: impl Clock + 'static) {
+        self.session = Some(Session {
+            clock: Box::new(clock),
+            last_command: None,
+        });
+    }
 
-        self.send(Request::new(Command::RequestSmartHome, cmd, len).into())
-            .await?;
-        self.send(payload).await?;
-        self.receive().await
+    /// Disables automatic session management.
+    pub fn disable_session(&mut self) {
+        self.session = None;
     }
 
-    /// Sends a payload to the port.
+    /// Enables or disables extended memory addressing.
     ///
-    /// The payload is split into chunks with an appended checksum.
+    /// Newer devices support 32-bit memory addresses and frames of up to 65535 bytes.
+    /// Extended addressing is enabled automatically once
+    /// [`Interface::query_max_baud_rate`] succeeds.
+    ///
+    /// Only affects the frame splitting of [`Interface::read_memory_slice`],
+    /// [`Interface::read_memory_vec`] and [`Interface::write_memory_slice`].
+    pub fn set_extended_addressing(&mut self, enabled: bool) {
+        self.extended_addressing = enabled;
+    }
+
+    /// Sets the EEPROM addressing mode of the device.
+    ///
+    /// Older devices address their EEPROM in 16-bit words, which is the default.
+    ///
+    /// Only affects the frame splitting of [`Interface::read_eeprom_slice`],
+    /// [`Interface::read_eeprom_vec`] and [`Interface::write_eeprom_slice`].
+    pub fn set_eeprom_addressing(&mut self, addressing: EepromAddressing) {
+        self.eeprom_addressing = addressing;
+    }
+
+    /// Returns the EEPROM addressing mode of the device.
+    pub fn eeprom_addressing(&self) -> EepromAddressing {
+        self.eeprom_addressing
+    }
+
+    /// Returns the current access level.
+    ///
+    /// The access level is tracked based on the commands sent to the device.
+    /// Because the device does not report whether an unlock was successful,
+    /// the returned level assumes that the provided keys were correct.
+    pub fn access_level(&self) -> AccessLevel {
+        self.access
+    }
+
+    /// Queries the software ID of the device.
+    ///
+    /// This number identifies the software/firmware running on the device.
+    /// Note that different electronics boards can share the same software ID.
+    ///
+    /// This must be called first as part of the unlock sequence.
+    /// See [`Interface::unlock_read_access`] for the next step.
+    pub async fn query_software_id(&mut self) -> Result<u16, P::Error> {
+        Ok(self
+            .query(Request::new(Command::QuerySoftwareId, 0x0000, 0x02))
+            .await?
+            .into())
+    }
+
+    /// Converts the interface into the specified access state.
+    fn into_state<T>(self) -> Interface<P, T> {
+        Interface {
+            port: self.port,
+            chunk_size: self.chunk_size,
+            baud_rate: self.baud_rate,
+            extended_addressing: self.extended_addressing,
+            eeprom_addressing: self.eeprom_addressing,
+            access: self.access,
+            unlock_seq: self.unlock_seq,
+            session: self.session,
+            timer: self.timer,
+            max_retries: self.max_retries,
+            read_only: self.read_only,
+            prefetched: self.prefetched,
+            state: PhantomData,
+        }
+    }
+
+    /// Unlocks full diagnostic access and remembers the sequence.
+    async fn unlock_inner(&mut self, seq: UnlockSequence) -> Result<(), P::Error> {
+        self.unlock_read_access_inner(seq.keys.read).await?;
+
+        if !self.read_only {
+            self.unlock_full_access_inner(seq.keys.full).await?;
+
+            if let Some(RomUnlock { addr, val }) = seq.rom_unlock {
+                self.write_memory_unchecked(addr, val).await?;
+            }
+        }
+
+        self.unlock_seq = Some(seq);
+
+        Ok(())
+    }
+
+    /// Tries all candidate sequences until the device accepts one of them.
+    async fn unlock_with_known_keys_inner(
+        &mut self,
+        db: &KeyDatabase,
+    ) -> Result<KeyPair, P::Error> {
+        let software_id = self.query_software_id().await?;
+
+        for seq in db.candidates(software_id) {
+            // Previous attempt may have left read access unlocked
+            self.lock_inner().await?;
+            // Device expects the software ID query before each unlock attempt
+            self.query_software_id().await?;
+            self.unlock_read_access_inner(seq.keys.read).await?;
+
+            if !self.probe_read_access().await? {
+                continue;
+            }
+
+            if !self.read_only {
+                self.unlock_full_access_inner(seq.keys.full).await?;
+
+                if let Some(rom_unlock) = seq.rom_unlock {
+                    if !self.probe_full_access(rom_unlock).await? {
+                        continue;
+                    }
+                }
+            }
+
+            debug!("Unlocked software ID {software_id} using {seq:x?}");
+
+            self.unlock_seq = Some(seq);
+
+            return Ok(seq.keys);
+        }
+
+        Err(Error::KeysNotFound)
+    }
+
+    /// Checks whether read access is unlocked by reading a single byte of memory.
+    async fn probe_read_access(&mut self) -> Result<bool, P::Error> {
+        match self
+            .read_memory_frame(0x0000, &mut [0x00], &mut |_| {})
+            .await
+        {
+            Ok(()) => Ok(true),
+            Err(Error::InvalidCommand) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Checks whether full access is unlocked by disabling the ROM readout protection.
+    ///
+    /// The write is only accepted with full access, which is confirmed by reading back the value.
+    async fn probe_full_access(&mut self, rom_unlock: RomUnlock) -> Result<bool, P::Error> {
+        let RomUnlock { addr, val } = rom_unlock;
+
+        match self.write_memory_unchecked(addr, val).await {
+            Ok(()) => {}
+            Err(Error::InvalidCommand) => return Ok(false),
+            Err(err) => return Err(err),
+        }
+
+        let mut buf = [0x00];
+
+        self.read_memory_frame(addr, &mut buf, &mut |_| {}).await?;
+
+        Ok(buf[0] == val)
+    }
+
+    /// Checks whether full access is unlocked by writing zero bytes to memory.
+    ///
+    /// Unlike [`Interface::halt`], this does not affect the device's operation.
+    async fn probe_empty_write(&mut self) -> Result<bool, P::Error> {
+        match self.write_memory_frame(0x0000, &[], &mut |_| {}).await {
+            Ok(()) => Ok(true),
+            Err(Error::InvalidCommand) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sends the lock command.
+    async fn lock_inner(&mut self) -> Result<(), P::Error> {
+        self.request(Request::new(Command::Lock, 0x0000, 0x00))
+            .await?;
+        self.access = AccessLevel::Locked;
+
+        Ok(())
+    }
+
+    /// Sends the read access key.
+    async fn unlock_read_access_inner(&mut self, key: u16) -> Result<(), P::Error> {
+        self.request(Request::new(Command::UnlockReadAccess, key, 0x00))
+            .await?;
+        self.access = AccessLevel::Read;
+
+        Ok(())
+    }
+
+    /// Requests smart home access.
+    async fn unlock_smart_home_access_inner(&mut self) -> Result<(), P::Error> {
+        self.request(Request::new(Command::UnlockSmartHomeAccess, 0x0000, 0x00))
+            .await?;
+
+        if self.access == AccessLevel::Locked {
+            self.access = AccessLevel::SmartHome;
+        }
+
+        Ok(())
+    }
+
+    /// Sends the full access key.
+    async fn unlock_full_access_inner(&mut self, key: u16) -> Result<(), P::Error> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+
+        self.request(Request::new(Command::UnlockFullAccess, key, 0x00))
+            .await?;
+        self.access = AccessLevel::Full;
+
+        Ok(())
+    }
+
+    /// Jumps to a subroutine and waits for it to return.
+    async fn jump_to_subroutine_inner(&mut self, addr: u32) -> Result<(), P::Error> {
+        self.prepare_write().await?;
+
+        self.transaction(async |intf| {
+            // Send upper bytes of address
+            if addr > 0xffff {
+                intf.send(Request::new(Command::ExtendAddress, (addr >> 16) as u16, 0x00).into())
+                    .await?;
+            }
+
+            // Response is sent once subroutine returns
+            intf.send(Request::new(Command::JumpToSubroutine, (addr & 0xffff) as u16, 0x00).into())
+                .await
+        })
+        .await?;
+        self.access = AccessLevel::Locked;
+        self.read(&mut [0x00]).await
+    }
+
+    /// Sets the device's baud rate, returning the rate selected by the device.
+    async fn set_baud_rate_inner(&mut self, rate: BaudRate) -> Result<BaudRate, P::Error> {
+        // Only the extended command is accepted with smart home access
+        self.prepare(match rate {
+            BaudRate::Baud2400 | BaudRate::Baud9600 => AccessLevel::Full,
+            _ => AccessLevel::SmartHome,
+        })
+        .await?;
+
+        let rate = match rate {
+            BaudRate::Baud2400 => {
+                self.request(Request::new(Command::SetBaudRate2400, 0x0000, 0x00))
+                    .await?;
+
+                rate
+            }
+            BaudRate::Baud9600 => {
+                self.request(Request::new(Command::SetBaudRate9600, 0x0000, 0x00))
+                    .await?;
+
+                rate
+            }
+            _ => {
+                // Device responds with actual baud rate
+                let resp: u8 = self
+                    .query(Request::new(Command::SetBaudRate, rate as u16, 0x01))
+                    .await?
+                    .into();
+
+                BaudRate::from_repr(resp).ok_or(Error::InvalidResponse)?
+            }
+        };
+
+        self.access = AccessLevel::Locked;
+
+        Ok(rate)
+    }
+
+    /// Resets the device's microcontroller.
+    async fn reset_inner(&mut self) -> Result<(), P::Error> {
+        self.prepare_write().await?;
+        self.request(Request::new(Command::Reset, 0x0000, 0x00))
+            .await?;
+        self.access = AccessLevel::Locked;
+
+        Ok(())
+    }
+
+    /// Writes data to the device's memory without checking the access level.
+    async fn write_memory_unchecked<L: Into<Payload<N>>, const N: usize>(
+        &mut self,
+        addr: u32,
+        payload: L,
+    ) -> Result<(), P::Error> {
+        self.write_memory_frame(addr, &payload.into().0, &mut |_| {})
+            .await
+    }
+
+    /// Returns the time since the last command if session management is enabled.
+    fn idle_time(&self) -> Option<Duration> {
+        let sess = self.session.as_ref()?;
+
+        Some(
+            sess.last_command
+                .map_or(Duration::MAX, |last| sess.clock.now().saturating_sub(last)),
+        )
+    }
+
+    /// Prepares the session for a command modifying the device's memory or operation.
+    ///
+    /// Such commands are refused in read-only mode.
+    async fn prepare_write(&mut self) -> Result<(), P::Error> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+
+        self.prepare(AccessLevel::Full).await
+    }
+
+    /// Prepares the session for a command requiring the specified access level.
+    ///
+    /// If session management is enabled and the session has expired or
+    /// the current access level is insufficient, the unlock sequence is replayed.
+    async fn prepare(&mut self, level: AccessLevel) -> Result<(), P::Error> {
+        let Some(seq) = self.unlock_seq else {
+            return Ok(());
+        };
+
+        if self.idle_time().is_some_and(|time| time >= SESSION_TIMEOUT) {
+            self.access = AccessLevel::Locked;
+        }
+
+        if self.session.is_some() && !self.access.includes(level) {
+            debug!("Restoring diagnostic session");
+
+            self.restore(seq).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Unlocks the interface again using the specified sequence.
+    async fn restore(&mut self, seq: UnlockSequence) -> Result<(), P::Error> {
+        self.query_software_id().await?;
+        self.unlock_inner(seq).await
+    }
+
+    /// Performs a command, repeating it if a frame was corrupted.
+    ///
+    /// The command is repeated up to the number of times configured
+    /// using [`Interface::set_max_retries`].
+    async fn transaction<T>(
+        &mut self,
+        mut cmd: impl AsyncFnMut(&mut Self) -> Result<T, P::Error>,
+    ) -> Result<T, P::Error> {
+        let mut retries = 0;
+
+        loop {
+            match cmd(self).await {
+                Err(Error::IncorrectChecksum) if retries < self.max_retries => {
+                    retries += 1;
+
+                    debug!(
+                        "Retrying command after incorrect checksum ({retries}/{})",
+                        self.max_retries
+                    );
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Sends a request without a response payload.
+    async fn request(&mut self, req: Request) -> Result<(), P::Error> {
+        self.transaction(async |intf| intf.send(req.into()).await)
+            .await
+    }
+
+    /// Sends a request and receives the response payload.
+    async fn query<const N: usize>(&mut self, req: Request) -> Result<Payload<N>, P::Error> {
+        self.transaction(async |intf| {
+            intf.send(req.into()).await?;
+            intf.receive().await
+        })
+        .await
+    }
+
+    /// Sends a memory access request, extending the address or length if required.
+    async fn send_memory_request(
+        &mut self,
+        cmd: Command,
+        addr: u32,
+        len: usize,
+    ) -> Result<(), P::Error> {
+        let len: u16 = len.try_into().map_err(|_| Error::InvalidArgument)?;
+
+        // Send upper bytes of address or length
+        if addr > 0xffff || len > 0xff {
+            self.send(
+                Request::new(
+                    Command::ExtendAddress,
+                    (addr >> 16) as u16,
+                    (len >> 8) as u8,
+                )
+                .into(),
+            )
+            .await?;
+        }
+
+        self.send(Request::new(cmd, (addr & 0xffff) as u16, (len & 0xff) as u8).into())
+            .await
+    }
+
+    /// Reads a single frame from the device's memory.
+    async fn read_memory_frame(
+        &mut self,
+        addr: u32,
+        buf: &mut [u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), P::Error> {
+        self.transaction(async |intf| {
+            intf.send_memory_request(Command::ReadMemory, addr, buf.len())
+                .await?;
+            intf.receive_data(buf, progress).await
+        })
+        .await
+    }
+
+    /// Writes a single frame to the device's memory.
+    async fn write_memory_frame(
+        &mut self,
+        addr: u32,
+        data: &[u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), P::Error> {
+        // Prefetched memory may be outdated after a write
+        if !data.is_empty() {
+            self.prefetched.clear();
+        }
+
+        self.transaction(async |intf| {
+            intf.send_memory_request(Command::WriteMemory, addr, data.len())
+                .await?;
+            intf.send_data(data, progress).await
+        })
+        .await
+    }
+
+    /// Reads a single frame from the device's EEPROM.
+    async fn read_eeprom_frame(
+        &mut self,
+        addr: u16,
+        buf: &mut [u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), P::Error> {
+        let len = buf.len().try_into().map_err(|_| Error::InvalidArgument)?;
+
+        self.transaction(async |intf| {
+            intf.send(Request::new(Command::ReadEeprom, addr, len).into())
+                .await?;
+            intf.receive_data(buf, progress).await
+        })
+        .await
+    }
+
+    /// Writes a single frame to the device's EEPROM.
+    async fn write_eeprom_frame(
+        &mut self,
+        addr: u16,
+        data: &[u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), P::Error> {
+        let len = data.len().try_into().map_err(|_| Error::InvalidArgument)?;
+
+        self.transaction(async |intf| {
+            intf.send(Request::new(Command::WriteEeprom, addr, len).into())
+                .await?;
+            intf.send_data(data, progress).await
+        })
+        .await
+    }
+
+    /// Checks whether a memory range can be accessed with the current addressing mode.
+    fn check_memory_range(&self, addr: u32, len: usize) -> Result<(), P::Error> {
+        let end = u64::from(addr) + len as u64;
+        let limit = if self.extended_addressing {
+            1 << 32
+        } else {
+            1 << 16
+        };
+
+        if end > limit {
+            return Err(Error::InvalidArgument);
+        }
+
+        Ok(())
+    }
+
+    /// Copies prefetched memory into the buffer if the whole range has been prefetched.
+    fn read_prefetched(&self, addr: u32, buf: &mut [u8]) -> bool {
+        let end = u64::from(addr) + buf.len() as u64;
+        let Some((start, data)) = self
+            .prefetched
+            .iter()
+            .find(|(start, data)| *start <= addr && end <= u64::from(*start) + data.len() as u64)
+        else {
+            return false;
+        };
+        let offset = (addr - start) as usize;
+
+        buf.copy_from_slice(&data[offset..offset + buf.len()]);
+
+        true
+    }
+
+    /// Returns the maximum length of a memory frame starting at the specified address.
+    ///
+    /// Frames never cross a 64 KiB boundary, as the upper
+    /// address bytes are only transmitted once per frame.
+    fn max_memory_frame_len(&self, addr: u32) -> usize {
+        let max_len = if self.extended_addressing {
+            u16::MAX
+        } else {
+            u8::MAX.into()
+        };
+
+        usize::from(max_len).min(0x0001_0000 - (addr & 0xffff) as usize)
+    }
+
+    /// Checks whether an EEPROM range can be accessed with the current addressing mode.
+    fn check_eeprom_range(&self, addr: u16, len: usize) -> Result<(), P::Error> {
+        // Word-addressed ranges always cover whole words
+        let len = match self.eeprom_addressing {
+            EepromAddressing::Byte => len,
+            EepromAddressing::Word => len.next_multiple_of(2),
+        };
+        let end = usize::from(addr) + self.eeprom_frame_offset(len);
+
+        if end > 0x0001_0000 {
+            return Err(Error::InvalidArgument);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the maximum length of an EEPROM frame.
+    ///
+    /// Word-addressed frames must contain an even number of bytes.
+    fn max_eeprom_frame_len(&self) -> usize {
+        match self.eeprom_addressing {
+            EepromAddressing::Byte => u8::MAX.into(),
+            EepromAddressing::Word => usize::from(u8::MAX) & !1,
+        }
+    }
+
+    /// Returns the EEPROM address of a frame at the specified byte offset.
+    fn eeprom_frame_addr(&self, addr: u16, offset: usize) -> Result<u16, P::Error> {
+        (usize::from(addr) + self.eeprom_frame_offset(offset))
+            .try_into()
+            .map_err(|_| Error::InvalidArgument)
+    }
+
+    /// Converts a byte offset into an EEPROM address offset.
+    fn eeprom_frame_offset(&self, offset: usize) -> usize {
+        match self.eeprom_addressing {
+            EepromAddressing::Byte => offset,
+            EepromAddressing::Word => offset / 2,
+        }
+    }
+
+    /// Sends a payload to the port.
+    ///
+    /// The payload is split into chunks with an appended checksum.
     /// Chunks are sent sequentially, verifying the response code for every transmission.
     async fn send<const N: usize>(&mut self, payload: Payload<N>) -> Result<(), P::Error> {
-        for chunk in payload.0.chunks(self.chunk_size as usize) {
-            let checksum = Self::compute_checksum(chunk);
+        self.send_data(&payload.0, &mut |_| {}).await
+    }
+
+    /// Sends data to the port, reporting the number of bytes sent after every chunk.
+    async fn send_data(
+        &mut self,
+        data: &[u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), P::Error> {
+        let mut sent = 0;
+
+        for chunk in data.chunks(self.chunk_size as usize) {
+            let checksum = compute_checksum(chunk);
             let mut resp = [0xff];
 
             self.write(chunk).await?;
@@ -763,87 +1444,1170 @@ impl<P: Read + Write> Interface<P> {
             match ResponseCode::from_repr(resp[0]) {
                 Some(ResponseCode::Success) => Ok(()),
                 Some(ResponseCode::IncorrectChecksum) => Err(Error::IncorrectChecksum),
-                Some(ResponseCode::InvalidCommand) => Err(Error::InvalidCommand),
+                Some(ResponseCode::InvalidCommand) => {
+                    // Device rejects commands when the interface is locked
+                    self.access = AccessLevel::Locked;
+
+                    Err(Error::InvalidCommand)
+                }
                 None => Err(Error::InvalidResponse),
             }?;
+
+            sent += chunk.len();
+            progress(sent);
         }
 
         Ok(())
     }
 
-    /// Receives a payload from the port.
-    ///
-    /// Chunks of the payload are read and their checksums verified.
-    /// A response code is sent for every received chunk.
-    async fn receive<const N: usize>(&mut self) -> Result<Payload<N>, P::Error> {
-        let mut payload = Payload([0x00; N]);
-
-        for chunk in payload.0.chunks_mut(self.chunk_size as usize) {
-            let mut checksum = [0x00];
+    /// Receives a payload from the port.
+    ///
+    /// Chunks of the payload are read and their checksums verified.
+    /// A response code is sent for every received chunk.
+    async fn receive<const N: usize>(&mut self) -> Result<Payload<N>, P::Error> {
+        let mut payload = Payload([0x00; N]);
+
+        self.receive_data(&mut payload.0, &mut |_| {}).await?;
+
+        Ok(payload)
+    }
+
+    /// Receives data from the port, reporting the number of bytes received after every chunk.
+    async fn receive_data(
+        &mut self,
+        buf: &mut [u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), P::Error> {
+        let mut received = 0;
+
+        for chunk in buf.chunks_mut(self.chunk_size as usize) {
+            let mut checksum = [0x00];
+
+            self.read(chunk).await?;
+            self.read(&mut checksum).await?;
+
+            if checksum[0] != compute_checksum(chunk) {
+                // Abort the transfer
+                self.write(&[ResponseCode::IncorrectChecksum as u8]).await?;
+
+                return Err(Error::IncorrectChecksum);
+            }
+
+            // Acknowledge reception of chunk
+            // Sending other response codes here aborts the transfer
+            self.write(&[ResponseCode::Success as u8]).await?;
+
+            received += chunk.len();
+            progress(received);
+        }
+
+        Ok(())
+    }
+
+    /// Reads data from the port into the provided buffer.
+    ///
+    /// If timeouts are enabled, the first byte must be received within the frame timeout
+    /// and every subsequent byte within the byte timeout.
+    async fn read(&mut self, buf: &mut [u8]) -> Result<(), P::Error> {
+        match &mut self.timer {
+            Some((timer, timeouts)) => {
+                let mut timeout = timeouts.frame;
+                let mut pos = 0;
+
+                while pos < buf.len() {
+                    match select::select(self.port.read(&mut buf[pos..]), timer.delay(timeout))
+                        .await
+                    {
+                        Either::First(Ok(0)) => return Err(Error::UnexpectedEof),
+                        Either::First(Ok(len)) => pos += len,
+                        Either::First(Err(err)) => return Err(Error::Io(err)),
+                        Either::Second(()) => {
+                            trace!("Timed out reading from port: {:02x?}", &buf[..pos]);
+
+                            return Err(Error::Timeout);
+                        }
+                    }
+
+                    timeout = timeouts.byte;
+                }
+            }
+            None => self.port.read_exact(buf).await?,
+        }
+
+        trace!("Read from port: {buf:02x?}");
+
+        Ok(())
+    }
+
+    /// Writes the provided buffer to the port.
+    async fn write(&mut self, buf: &[u8]) -> Result<(), P::Error> {
+        trace!("Write to port: {buf:02x?}");
+        self.port.write_all(buf).await?;
+
+        if let Some(sess) = &mut self.session {
+            sess.last_command = Some(sess.clock.now());
+        }
+
+        Ok(())
+    }
+}
+
+impl<P: Read + Write, S: CanQuery> Interface<P, S> {
+    /// Queries the device's maximum supported baud rate.
+    ///
+    /// The maximum baud rate can only be queried on newer devices.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidResponse`] if the device responds with an invalid baud rate
+    pub async fn query_max_baud_rate(&mut self) -> Result<BaudRate, P::Error> {
+        self.prepare(AccessLevel::Read).await?;
+        let resp: [u8; 2] = self
+            .query(Request::new(Command::QueryMaxBaudRate, 0x0000, 0x02))
+            .await?
+            .into();
+
+        // Only newer devices support this command
+        self.extended_addressing = true;
+
+        BaudRate::from_repr(resp[1]).ok_or(Error::InvalidResponse)
+    }
+
+    /// Sends a smart home request to the device and returns the response.
+    ///
+    /// Smart home functionality is only supported on newer devices.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the payload length exceeds 255 bytes.
+    pub async fn send_smart_home_request<const M: usize, const N: usize>(
+        &mut self,
+        cmd: u16,
+        payload: Payload<N>,
+    ) -> Result<Payload<M>, P::Error> {
+        let len = N.try_into().map_err(|_| Error::InvalidArgument)?;
+
+        self.prepare(AccessLevel::SmartHome).await?;
+        self.transaction(async |intf| {
+            intf.send(Request::new(Command::RequestSmartHome, cmd, len).into())
+                .await?;
+            intf.send(Payload(payload.0)).await?;
+            intf.receive().await
+        })
+        .await
+    }
+}
+
+impl<P: Read + Write, S: CanRead> Interface<P, S> {
+    /// Keeps the diagnostic session alive.
+    ///
+    /// Only has an effect if session management is enabled via [`Interface::enable_session`].
+    /// Sends a short memory read if no command has been sent for a while.
+    /// If the session has already expired, it is restored first.
+    /// The read always communicates with the device, even if the memory has been prefetched.
+    ///
+    /// Long-running applications should call this function at least every 2 seconds.
+    pub async fn keep_alive(&mut self) -> Result<(), P::Error> {
+        if self
+            .idle_time()
+            .is_some_and(|time| time >= KEEP_ALIVE_INTERVAL)
+        {
+            self.prepare(AccessLevel::Read).await?;
+            self.read_memory_frame(0x0000, &mut [0x00], &mut |_| {})
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads data from the device's memory.
+    ///
+    /// Newer devices support reading up to 65535 bytes from a 32-bit memory address,
+    /// while older devices are limited to 255 bytes and 16-bit addresses.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the payload length exceeds 65535 bytes.
+    pub async fn read_memory<L: From<Payload<N>>, const N: usize>(
+        &mut self,
+        addr: u32,
+    ) -> Result<L, P::Error> {
+        let mut payload = Payload([0x00; N]);
+
+        if !self.read_prefetched(addr, &mut payload.0) {
+            self.prepare(AccessLevel::Read).await?;
+            self.read_memory_frame(addr, &mut payload.0, &mut |_| {})
+                .await?;
+        }
+
+        Ok(payload.into())
+    }
+
+    /// Reads data from the device's memory into the provided buffer.
+    ///
+    /// In contrast to [`Interface::read_memory`], the length is not limited.
+    /// The range is split into the largest frames the device allows,
+    /// see [`Interface::set_extended_addressing`].
+    /// The `progress` callback is invoked with the number of bytes
+    /// transferred so far and the total number of bytes after every chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the range exceeds the addressable memory.
+    pub async fn read_memory_slice(
+        &mut self,
+        addr: u32,
+        buf: &mut [u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), P::Error> {
+        let total = buf.len();
+        let mut done = 0;
+
+        self.check_memory_range(addr, total)?;
+
+        if self.read_prefetched(addr, buf) {
+            progress(total, total);
+
+            return Ok(());
+        }
+
+        while done < total {
+            let frame_addr = addr + u32::try_from(done).map_err(|_| Error::InvalidArgument)?;
+            let len = self.max_memory_frame_len(frame_addr).min(total - done);
+
+            self.prepare(AccessLevel::Read).await?;
+            self.read_memory_frame(frame_addr, &mut buf[done..done + len], &mut |n| {
+                progress(done + n, total);
+            })
+            .await?;
+
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the specified number of bytes from the device's memory.
+    ///
+    /// See [`Interface::read_memory_slice`] for details.
+    pub async fn read_memory_vec(
+        &mut self,
+        addr: u32,
+        len: usize,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<u8>, P::Error> {
+        let mut data = vec![0x00; len];
+
+        self.read_memory_slice(addr, &mut data, progress).await?;
+
+        Ok(data)
+    }
+
+    /// Reads the specified memory ranges in advance.
+    ///
+    /// Overlapping or nearby ranges are merged and read using as few frames as possible.
+    /// Subsequent memory reads contained in a prefetched range are served without
+    /// communicating with the device, until [`Interface::clear_prefetched`] is called
+    /// or memory is written.
+    ///
+    /// Used by [`device::Device::query_properties`] to query multiple properties at once.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if a range exceeds the addressable memory.
+    pub async fn prefetch_memory(
+        &mut self,
+        ranges: impl IntoIterator<Item = Range<u32>>,
+    ) -> Result<(), P::Error> {
+        let mut ranges: Vec<_> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+        let mut merged: Vec<Range<u32>> = Vec::new();
+
+        ranges.sort_unstable_by_key(|r| r.start);
+
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end.saturating_add(PREFETCH_MAX_GAP) => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+
+        let mut prefetched = Vec::with_capacity(merged.len());
+
+        self.prefetched.clear();
+
+        for range in merged {
+            let data = self
+                .read_memory_vec(range.start, (range.end - range.start) as usize, |_, _| {})
+                .await?;
+
+            prefetched.push((range.start, data));
+        }
+
+        self.prefetched = prefetched;
+
+        Ok(())
+    }
+
+    /// Discards all memory read by [`Interface::prefetch_memory`].
+    pub fn clear_prefetched(&mut self) {
+        self.prefetched.clear();
+    }
+
+    /// Reads data from the device's EEPROM.
+    ///
+    /// For older devices, the address must be specified in words, not bytes.
+    /// As an example, to read a byte at address `0x64`, provide the word address `0x32`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the payload length exceeds 255 bytes.
+    pub async fn read_eeprom<L: From<Payload<N>>, const N: usize>(
+        &mut self,
+        addr: u16,
+    ) -> Result<L, P::Error> {
+        let mut payload = Payload([0x00; N]);
+
+        self.prepare(AccessLevel::Read).await?;
+        self.read_eeprom_frame(addr, &mut payload.0, &mut |_| {})
+            .await?;
+
+        Ok(payload.into())
+    }
+
+    /// Reads data from the device's EEPROM into the provided buffer.
+    ///
+    /// In contrast to [`Interface::read_eeprom`], the length is not limited.
+    /// The range is split into frames of up to 255 bytes.
+    /// Frame addresses are calculated based on the EEPROM addressing mode,
+    /// see [`Interface::set_eeprom_addressing`].
+    /// The `progress` callback is invoked with the number of bytes
+    /// transferred so far and the total number of bytes after every chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the range exceeds the addressable EEPROM.
+    pub async fn read_eeprom_slice(
+        &mut self,
+        addr: u16,
+        buf: &mut [u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), P::Error> {
+        let total = buf.len();
+        let mut done = 0;
+
+        self.check_eeprom_range(addr, total)?;
+
+        while done < total {
+            let len = self.max_eeprom_frame_len().min(total - done);
+
+            self.prepare(AccessLevel::Read).await?;
+            self.read_eeprom_frame(
+                self.eeprom_frame_addr(addr, done)?,
+                &mut buf[done..done + len],
+                &mut |n| progress(done + n, total),
+            )
+            .await?;
+
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the specified number of bytes from the device's EEPROM.
+    ///
+    /// See [`Interface::read_eeprom_slice`] for details.
+    pub async fn read_eeprom_vec(
+        &mut self,
+        addr: u16,
+        len: usize,
+        progress: impl FnMut(usize, usize),
+    ) -> Result<Vec<u8>, P::Error> {
+        let mut data = vec![0x00; len];
+
+        self.read_eeprom_slice(addr, &mut data, progress).await?;
+
+        Ok(data)
+    }
+}
+
+impl<P: Read + Write, S: CanConfigure> Interface<P, S> {
+    /// Configures the diagnostic frame chunk size.
+    ///
+    /// The chunk size can only be adjusted on newer devices.
+    ///
+    /// If the requested size is outside the supported range,
+    /// it is clamped by the device to the nearest supported boundary.
+    /// The supported range is device-specific,
+    /// but is typically between 4 and 128 bytes.
+    pub async fn set_chunk_size(&mut self, size: u8) -> Result<(), P::Error> {
+        self.prepare(AccessLevel::SmartHome).await?;
+        // Device responds with actual chunk size
+        self.chunk_size = self
+            .query(Request::new(Command::SetChunkSize, u16::from(size), 0x01))
+            .await?
+            .into();
+
+        Ok(())
+    }
+}
+
+impl<P: Read + Write, S: CanWrite> Interface<P, S> {
+    /// Writes data to the device's memory.
+    ///
+    /// Newer devices support writing up to 65535 bytes to a 32-bit memory address,
+    /// while older devices are limited to 255 bytes and 16-bit addresses.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the payload length exceeds 65535 bytes.
+    pub async fn write_memory<L: Into<Payload<N>>, const N: usize>(
+        &mut self,
+        addr: u32,
+        payload: L,
+    ) -> Result<(), P::Error> {
+        self.prepare_write().await?;
+        self.write_memory_unchecked(addr, payload).await
+    }
+
+    /// Writes data to the device's memory from the provided buffer.
+    ///
+    /// In contrast to [`Interface::write_memory`], the length is not limited.
+    /// The range is split into the largest frames the device allows,
+    /// see [`Interface::set_extended_addressing`].
+    /// The `progress` callback is invoked with the number of bytes
+    /// transferred so far and the total number of bytes after every chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the range exceeds the addressable memory.
+    pub async fn write_memory_slice(
+        &mut self,
+        addr: u32,
+        data: &[u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), P::Error> {
+        let total = data.len();
+        let mut done = 0;
+
+        self.check_memory_range(addr, total)?;
+
+        while done < total {
+            let frame_addr = addr + u32::try_from(done).map_err(|_| Error::InvalidArgument)?;
+            let len = self.max_memory_frame_len(frame_addr).min(total - done);
+
+            self.prepare_write().await?;
+            self.write_memory_frame(frame_addr, &data[done..done + len], &mut |n| {
+                progress(done + n, total);
+            })
+            .await?;
+
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    /// Writes data to the device's EEPROM.
+    ///
+    /// For older devices, the address must be specified in words, not bytes.
+    /// As an example, to write a byte at address `0x64`, provide the word address `0x32`.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the payload length exceeds 255 bytes.
+    pub async fn write_eeprom<L: Into<Payload<N>>, const N: usize>(
+        &mut self,
+        addr: u16,
+        payload: L,
+    ) -> Result<(), P::Error> {
+        self.prepare_write().await?;
+        self.write_eeprom_frame(addr, &payload.into().0, &mut |_| {})
+            .await
+    }
+
+    /// Writes data to the device's EEPROM from the provided buffer.
+    ///
+    /// In contrast to [`Interface::write_eeprom`], the length is not limited.
+    /// The range is split into frames of up to 255 bytes.
+    /// Frame addresses are calculated based on the EEPROM addressing mode,
+    /// see [`Interface::set_eeprom_addressing`].
+    /// The `progress` callback is invoked with the number of bytes
+    /// transferred so far and the total number of bytes after every chunk.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgument`] if the range exceeds the addressable EEPROM.
+    pub async fn write_eeprom_slice(
+        &mut self,
+        addr: u16,
+        data: &[u8],
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), P::Error> {
+        let total = data.len();
+        let mut done = 0;
+
+        self.check_eeprom_range(addr, total)?;
+
+        while done < total {
+            let len = self.max_eeprom_frame_len().min(total - done);
+
+            self.prepare_write().await?;
+            self.write_eeprom_frame(
+                self.eeprom_frame_addr(addr, done)?,
+                &data[done..done + len],
+                &mut |n| progress(done + n, total),
+            )
+            .await?;
+
+            done += len;
+        }
+
+        Ok(())
+    }
+
+    /// Halts the device's normal operation.
+    ///
+    /// Causes the device to enter an infinite loop.
+    pub async fn halt(&mut self) -> Result<(), P::Error> {
+        self.prepare_write().await?;
+        self.request(Request::new(Command::Halt, 0x0000, 0x00))
+            .await
+    }
+
+    /// Switches the device and the port to the fastest supported communication mode.
+    ///
+    /// Before calling this function, the interface must be unlocked using [`Interface::unlock`].
+    /// Newer devices are switched to their maximum supported baud rate,
+    /// while older devices are switched to 9600 baud.
+    /// After reconfiguring the port, the interface is unlocked again and,
+    /// on newer devices, the chunk size is raised to the largest supported size.
+    ///
+    /// The port is assumed to be configured for 2400 baud when the interface is created.
+    /// If any step fails, the device and the port are switched back to 2400 baud.
+    /// Returns the negotiated baud rate.
+    ///
+    /// If the device stops responding after switching the baud rate, the negotiation only
+    /// falls back to 2400 baud if response timeouts are enabled via [`Interface::enable_timeouts`].
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidState`] if the interface has not been unlocked using [`Interface::unlock`].
+    pub async fn negotiate_fast_mode(&mut self) -> Result<BaudRate, P::Error>
+    where
+        P: ConfigurableBaudRate,
+    {
+        let seq = self.unlock_seq.ok_or(Error::InvalidState)?;
+        let (rate, extended) = match self.query_max_baud_rate().await {
+            Ok(rate) => (rate, true),
+            // Older devices only support up to 9600 baud
+            Err(Error::InvalidCommand) => {
+                self.restore(seq).await?;
+
+                (BaudRate::Baud9600, false)
+            }
+            Err(err) => return Err(err),
+        };
+
+        if rate == self.baud_rate {
+            return Ok(rate);
+        }
+
+        match self.switch_baud_rate(rate, extended, seq).await {
+            Ok(rate) => {
+                debug!(
+                    "Negotiated {} baud with chunk size {}",
+                    rate.as_baud(),
+                    self.chunk_size
+                );
+
+                Ok(rate)
+            }
+            Err(err) => {
+                debug!("Failed to negotiate fast mode, falling back to 2400 baud: {err:?}");
+
+                self.fall_back(extended, seq).await?;
+
+                Ok(BaudRate::Baud2400)
+            }
+        }
+    }
+
+    /// Switches the device and the port to the specified baud rate.
+    async fn switch_baud_rate(
+        &mut self,
+        rate: BaudRate,
+        extended: bool,
+        seq: UnlockSequence,
+    ) -> Result<BaudRate, P::Error>
+    where
+        P: ConfigurableBaudRate,
+    {
+        // Make sure the port supports the baud rate before switching the device
+        self.port.set_baud_rate(rate)?;
+        self.port.set_baud_rate(self.baud_rate)?;
+
+        let rate = self.set_baud_rate_inner(rate).await?;
+
+        self.port.set_baud_rate(rate)?;
+        self.baud_rate = rate;
+        self.restore(seq).await?;
+
+        if extended {
+            // Device clamps the chunk size to its maximum
+            self.set_chunk_size(u8::MAX).await?;
+        }
+
+        Ok(rate)
+    }
+
+    /// Switches the device and the port back to 2400 baud.
+    async fn fall_back(&mut self, extended: bool, seq: UnlockSequence) -> Result<(), P::Error>
+    where
+        P: ConfigurableBaudRate,
+    {
+        // Device might still be using the faster baud rate
+        let res = async {
+            self.restore(seq).await?;
+            self.set_baud_rate_inner(BaudRate::Baud2400).await
+        }
+        .await;
+
+        if let Err(err) = res {
+            debug!("Failed to reset device baud rate: {err:?}");
+        }
+
+        self.port.set_baud_rate(BaudRate::Baud2400)?;
+        self.baud_rate = BaudRate::Baud2400;
+        self.restore(seq).await?;
+
+        if extended {
+            self.set_chunk_size(DEFAULT_CHUNK_SIZE).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<P: Read + Write, S: TypedState> Interface<P, S> {
+    /// Converts the interface back into the [`Dynamic`] state.
+    pub fn into_dynamic(self) -> Interface<P> {
+        self.into_state()
+    }
+
+    /// Locks the diagnostic interface.
+    ///
+    /// See [`Interface::lock`] for details.
+    pub async fn lock(mut self) -> Result<Interface<P, Locked>, P::Error> {
+        self.lock_inner().await?;
+
+        Ok(self.into_state())
+    }
+}
+
+impl<P: Read + Write> Interface<P, Locked> {
+    /// Unlocks full diagnostic access using the provided sequence.
+    ///
+    /// See [`Interface::unlock`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ReadOnly`] if the interface is in read-only mode, as full access
+    ///   cannot be unlocked. Use [`Interface::unlock_read_access`] instead.
+    pub async fn unlock(
+        mut self,
+        seq: UnlockSequence,
+    ) -> Result<Interface<P, FullAccess>, P::Error> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+
+        self.unlock_inner(seq).await?;
+
+        Ok(self.into_state())
+    }
+
+    /// Unlocks full diagnostic access by trying the keys from the provided database.
+    ///
+    /// See [`Interface::unlock_with_known_keys`] for details.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::ReadOnly`] if the interface is in read-only mode, as full access
+    ///   cannot be unlocked.
+    pub async fn unlock_with_known_keys(
+        mut self,
+        db: &KeyDatabase,
+    ) -> Result<(Interface<P, FullAccess>, KeyPair), P::Error> {
+        if self.read_only {
+            return Err(Error::ReadOnly);
+        }
+
+        let keys = self.unlock_with_known_keys_inner(db).await?;
+
+        Ok((self.into_state(), keys))
+    }
+
+    /// Unlocks read-only diagnostic access.
+    ///
+    /// See [`Interface::unlock_read_access`] for details.
+    pub async fn unlock_read_access(
+        mut self,
+        key: u16,
+    ) -> Result<Interface<P, ReadAccess>, P::Error> {
+        self.unlock_read_access_inner(key).await?;
+
+        Ok(self.into_state())
+    }
+
+    /// Unlocks access to the smart home functionality.
+    ///
+    /// See [`Interface::unlock_smart_home_access`] for details.
+    pub async fn unlock_smart_home_access(
+        mut self,
+    ) -> Result<Interface<P, SmartHomeAccess>, P::Error> {
+        self.unlock_smart_home_access_inner().await?;
+
+        Ok(self.into_state())
+    }
+}
+
+impl<P: Read + Write> Interface<P, ReadAccess> {
+    /// Unlocks full diagnostic access.
+    ///
+    /// See [`Interface::unlock_full_access`] for details.
+    pub async fn unlock_full_access(
+        mut self,
+        key: u16,
+    ) -> Result<Interface<P, FullAccess>, P::Error> {
+        self.unlock_full_access_inner(key).await?;
+
+        Ok(self.into_state())
+    }
+}
+
+impl<P: Read + Write, S: CanConfigure + TypedState> Interface<P, S> {
+    /// Sets the device's baud rate, returning the locked interface and the selected baud rate.
+    ///
+    /// See [`Interface::set_baud_rate`] for details.
+    pub async fn set_baud_rate(
+        mut self,
+        rate: BaudRate,
+    ) -> Result<(Interface<P, Locked>, BaudRate), P::Error> {
+        let rate = self.set_baud_rate_inner(rate).await?;
+
+        Ok((self.into_state(), rate))
+    }
+}
+
+impl<P: Read + Write> Interface<P, FullAccess> {
+    /// Jumps to a specified subroutine and waits for it to return.
+    ///
+    /// See [`Interface::jump_to_subroutine`] for details.
+    pub async fn jump_to_subroutine(mut self, addr: u32) -> Result<Interface<P, Locked>, P::Error> {
+        self.jump_to_subroutine_inner(addr).await?;
+
+        Ok(self.into_state())
+    }
+
+    /// Resets the device's microcontroller.
+    ///
+    /// See [`Interface::reset`] for details.
+    pub async fn reset(mut self) -> Result<Interface<P, Locked>, P::Error> {
+        self.reset_inner().await?;
+
+        Ok(self.into_state())
+    }
+}
+
+/// Computes an 8-bit wrapping sum (modulo 256) over the provided data.
+fn compute_checksum(data: &[u8]) -> u8 {
+    data.iter().map(|&x| Wrapping(x)).sum::<Wrapping<_>>().0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::Simulator;
+    use alloc::{collections::vec_deque::VecDeque, rc::Rc};
+    use core::{cell::Cell, convert::Infallible};
+    use embedded_io_async::ErrorKind;
+    use log::LevelFilter;
+
+    pub fn init_logger() {
+        let _ = env_logger::builder()
+            .filter_level(LevelFilter::max())
+            .is_test(true)
+            .try_init();
+    }
+
+    /// Port that does not support baud rates above 9600 baud.
+    struct LimitedPort<'a>(&'a mut Simulator);
+
+    impl ErrorType for LimitedPort<'_> {
+        type Error = ErrorKind;
+    }
+
+    impl Read for LimitedPort<'_> {
+        async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, ErrorKind> {
+            Ok(self.0.read(buf).await.unwrap())
+        }
+    }
+
+    impl Write for LimitedPort<'_> {
+        async fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, ErrorKind> {
+            Ok(self.0.write(buf).await.unwrap())
+        }
+
+        async fn flush(&mut self) -> core::result::Result<(), ErrorKind> {
+            self.0.flush().await.unwrap();
+
+            Ok(())
+        }
+    }
+
+    impl ConfigurableBaudRate for LimitedPort<'_> {
+        fn set_baud_rate(&mut self, rate: BaudRate) -> core::result::Result<(), ErrorKind> {
+            if rate.as_baud() > 9600 {
+                return Err(ErrorKind::Unsupported);
+            }
+
+            self.0.set_baud_rate(rate).unwrap();
+
+            Ok(())
+        }
+    }
+
+    /// Port that accepts all data but never responds.
+    struct SilentPort;
+
+    impl ErrorType for SilentPort {
+        type Error = Infallible;
+    }
+
+    impl Read for SilentPort {
+        async fn read(&mut self, _buf: &mut [u8]) -> core::result::Result<usize, Infallible> {
+            core::future::pending().await
+        }
+    }
+
+    impl Write for SilentPort {
+        fn write(
+            &mut self,
+            buf: &[u8],
+        ) -> impl Future<Output = core::result::Result<usize, Infallible>> {
+            core::future::ready(Ok(buf.len()))
+        }
+
+        fn flush(&mut self) -> impl Future<Output = core::result::Result<(), Infallible>> {
+            core::future::ready(Ok(()))
+        }
+    }
+
+    /// Delay implementation using the Tokio runtime.
+    struct TokioDelay;
+
+    impl DelayNs for TokioDelay {
+        async fn delay_ns(&mut self, ns: u32) {
+            tokio::time::sleep(Duration::from_nanos(ns.into())).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn lock() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.lock().await?;
+
+        assert_eq!(
+            deque,
+            [0x10, 0x00, 0x00, 0x00, 0x10],
+            "deque contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_software_id() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00, 0x75, 0x02, 0x77]);
+        let mut intf = Interface::new(&mut deque);
+        let id = intf.query_software_id().await?;
+
+        assert_eq!(
+            deque,
+            [0x11, 0x00, 0x00, 0x02, 0x13, 0x00],
+            "deque contents should be correct"
+        );
+
+        assert_eq!(id, 629, "software ID should be correct");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unlock_read_access() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.unlock_read_access(0xabcd).await?;
+
+        assert_eq!(
+            deque,
+            [0x20, 0xcd, 0xab, 0x00, 0x98],
+            "deque contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unlock_smart_home_access() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.unlock_smart_home_access().await?;
+
+        assert_eq!(
+            deque,
+            [0x21, 0x00, 0x00, 0x00, 0x21],
+            "deque contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_memory() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([
+            0x00, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad, 0x8b,
+        ]);
+        let mut intf = Interface::new(&mut deque);
+        let data: [u8; 10] = intf.read_memory(0xabcd).await?;
+
+        assert_eq!(
+            deque,
+            [0x30, 0xcd, 0xab, 0x0a, 0xb2, 0x00, 0x00, 0x00],
+            "deque contents should be correct"
+        );
+
+        assert_eq!(
+            data,
+            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
+            "memory contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_memory_extended() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([
+            0x00, 0x00, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad,
+            0x8b,
+        ]);
+        let mut intf = Interface::new(&mut deque);
+        let data: [u8; 10] = intf.read_memory(0x1234_abcd).await?;
+
+        assert_eq!(
+            deque,
+            [
+                0x37, 0x34, 0x12, 0x00, 0x7d, 0x30, 0xcd, 0xab, 0x0a, 0xb2, 0x00, 0x00, 0x00
+            ],
+            "deque contents should be correct"
+        );
+
+        assert_eq!(
+            data,
+            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
+            "memory contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_eeprom() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([
+            0x00, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad, 0x8b,
+        ]);
+        let mut intf = Interface::new(&mut deque);
+        let data: [u8; 10] = intf.read_eeprom(0xabcd).await?;
+
+        assert_eq!(
+            deque,
+            [0x31, 0xcd, 0xab, 0x0a, 0xb3, 0x00, 0x00, 0x00],
+            "deque contents should be correct"
+        );
+
+        assert_eq!(
+            data,
+            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
+            "EEPROM contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_max_baud_rate() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00, 0x80, 0x03, 0x83]);
+        let mut intf = Interface::new(&mut deque);
+        let rate = intf.query_max_baud_rate().await?;
+
+        assert_eq!(
+            deque,
+            [0x38, 0x00, 0x00, 0x02, 0x3a, 0x00],
+            "deque contents should be correct"
+        );
+
+        assert_eq!(rate, BaudRate::Baud38400, "baud rate should be correct");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unlock_full_access() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.unlock_full_access(0xabcd).await?;
+
+        assert_eq!(
+            deque,
+            [0x32, 0xcd, 0xab, 0x00, 0xaa],
+            "deque contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_memory() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.write_memory(
+            0xabcd,
+            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
+        )
+        .await?;
+
+        assert_eq!(
+            deque,
+            [
+                0x40, 0xcd, 0xab, 0x0a, 0xc2, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99,
+                0x00, 0xde, 0xad, 0x8b
+            ],
+            "deque contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_memory_extended() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00, 0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.write_memory(
+            0x1234_abcd,
+            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
+        )
+        .await?;
+
+        assert_eq!(
+            deque,
+            [
+                0x37, 0x34, 0x12, 0x00, 0x7d, 0x40, 0xcd, 0xab, 0x0a, 0xc2, 0x11, 0x22, 0x33, 0x44,
+                0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad, 0x8b
+            ],
+            "deque contents should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_eeprom() -> Result<(), Infallible> {
+        init_logger();
 
-            self.read(chunk).await?;
-            self.read(&mut checksum).await?;
+        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00]);
+        let mut intf = Interface::new(&mut deque);
 
-            if checksum[0] != Self::compute_checksum(chunk) {
-                return Err(Error::IncorrectChecksum);
-            }
+        intf.write_eeprom(
+            0xabcd,
+            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
+        )
+        .await?;
 
-            // Acknowledge reception of chunk
-            // Sending other response codes here aborts the transfer
-            self.write(&[ResponseCode::Success as u8]).await?;
-        }
+        assert_eq!(
+            deque,
+            [
+                0x41, 0xcd, 0xab, 0x0a, 0xc3, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99,
+                0x00, 0xde, 0xad, 0x8b
+            ],
+            "deque contents should be correct"
+        );
 
-        Ok(payload)
+        Ok(())
     }
 
-    /// Computes an 8-bit wrapping sum (modulo 256) over the provided data.
-    fn compute_checksum(data: &[u8]) -> u8 {
-        data.iter().map(|&x| Wrapping(x)).sum::<Wrapping<_>>().0
-    }
+    #[tokio::test]
+    async fn jump_to_subroutine() -> Result<(), Infallible> {
+        init_logger();
 
-    /// Reads data from the port into the provided buffer.
-    async fn read(&mut self, buf: &mut [u8]) -> Result<(), P::Error> {
-        self.port.read_exact(buf).await?;
-        trace!("Read from port: {buf:02x?}");
+        let mut deque = VecDeque::from([0x00, 0x00]);
+        let mut intf = Interface::new(&mut deque);
 
-        Ok(())
-    }
+        intf.jump_to_subroutine(0xabcd).await?;
 
-    /// Writes the provided buffer to the port.
-    async fn write(&mut self, buf: &[u8]) -> Result<(), P::Error> {
-        trace!("Write to port: {buf:02x?}");
-        self.port.write_all(buf).await?;
+        assert_eq!(
+            deque,
+            [0x42, 0xcd, 0xab, 0x00, 0xba],
+            "deque contents should be correct"
+        );
 
         Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use alloc::{boxed::Box, collections::vec_deque::VecDeque};
-    use core::convert::Infallible;
-    use log::LevelFilter;
-
-    pub fn init_logger() {
-        let _ = env_logger::builder()
-            .filter_level(LevelFilter::max())
-            .is_test(true)
-            .try_init();
-    }
 
     #[tokio::test]
-    async fn lock() -> Result<(), Infallible> {
+    async fn jump_to_subroutine_extended() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00]);
+        let mut deque = VecDeque::from([0x00, 0x00, 0x00]);
         let mut intf = Interface::new(&mut deque);
 
-        intf.lock().await?;
+        intf.jump_to_subroutine(0x1234_abcd).await?;
 
         assert_eq!(
             deque,
-            [0x10, 0x00, 0x00, 0x00, 0x10],
+            [0x37, 0x34, 0x12, 0x00, 0x7d, 0x42, 0xcd, 0xab, 0x00, 0xba],
             "deque contents should be correct"
         );
 
@@ -851,36 +2615,35 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn query_software_id() -> Result<(), Infallible> {
+    async fn halt() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x75, 0x02, 0x77]);
+        let mut deque = VecDeque::from([0x00]);
         let mut intf = Interface::new(&mut deque);
-        let id = intf.query_software_id().await?;
+
+        intf.halt().await?;
 
         assert_eq!(
             deque,
-            [0x11, 0x00, 0x00, 0x02, 0x13, 0x00],
+            [0x45, 0x00, 0x00, 0x00, 0x45],
             "deque contents should be correct"
         );
 
-        assert_eq!(id, 629, "software ID should be correct");
-
         Ok(())
     }
 
     #[tokio::test]
-    async fn unlock_read_access() -> Result<(), Infallible> {
+    async fn set_baud_rate_2400() -> Result<(), Infallible> {
         init_logger();
 
         let mut deque = VecDeque::from([0x00]);
         let mut intf = Interface::new(&mut deque);
 
-        intf.unlock_read_access(0xabcd).await?;
+        intf.set_baud_rate(BaudRate::Baud2400).await?;
 
         assert_eq!(
             deque,
-            [0x20, 0xcd, 0xab, 0x00, 0x98],
+            [0x46, 0x00, 0x00, 0x00, 0x46],
             "deque contents should be correct"
         );
 
@@ -888,17 +2651,17 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn unlock_smart_home_access() -> Result<(), Infallible> {
+    async fn set_baud_rate_9600() -> Result<(), Infallible> {
         init_logger();
 
         let mut deque = VecDeque::from([0x00]);
         let mut intf = Interface::new(&mut deque);
 
-        intf.unlock_smart_home_access().await?;
+        intf.set_baud_rate(BaudRate::Baud9600).await?;
 
         assert_eq!(
             deque,
-            [0x21, 0x00, 0x00, 0x00, 0x21],
+            [0x47, 0x00, 0x00, 0x00, 0x47],
             "deque contents should be correct"
         );
 
@@ -906,18 +2669,24 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn read_memory() -> Result<(), Infallible> {
+    async fn set_chunk_size() -> Result<(), Infallible> {
         init_logger();
 
         let mut deque = VecDeque::from([
-            0x00, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad, 0x8b,
+            0x00, 0x80, 0x80, 0x00, 0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad,
+            0x35,
         ]);
         let mut intf = Interface::new(&mut deque);
+
+        intf.set_chunk_size(128).await?;
+
         let data: [u8; 10] = intf.read_memory(0xabcd).await?;
 
         assert_eq!(
             deque,
-            [0x30, 0xcd, 0xab, 0x0a, 0xb2, 0x00, 0x00, 0x00],
+            [
+                0x4a, 0x80, 0x0, 0x1, 0xcb, 0x00, 0x30, 0xcd, 0xab, 0x0a, 0xb2, 0x00
+            ],
             "deque contents should be correct"
         );
 
@@ -931,358 +2700,703 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn read_memory_extended() -> Result<(), Infallible> {
+    async fn set_baud_rate() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([
-            0x00, 0x00, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad,
-            0x8b,
-        ]);
+        let mut deque = VecDeque::from([0x00, 0x02, 0x02]);
         let mut intf = Interface::new(&mut deque);
-        let data: [u8; 10] = intf.read_memory(0x1234_abcd).await?;
+
+        let rate = intf.set_baud_rate(BaudRate::Baud19200).await?;
 
         assert_eq!(
             deque,
-            [
-                0x37, 0x34, 0x12, 0x00, 0x7d, 0x30, 0xcd, 0xab, 0x0a, 0xb2, 0x00, 0x00, 0x00
-            ],
+            [0x4b, 0x02, 0x00, 0x01, 0x4e, 0x00],
             "deque contents should be correct"
         );
+        assert_eq!(rate, BaudRate::Baud19200, "baud rate should be correct");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reset() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x00]);
+        let mut intf = Interface::new(&mut deque);
+
+        intf.reset().await?;
 
         assert_eq!(
-            data,
-            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
-            "memory contents should be correct"
+            deque,
+            [0x4e, 0x00, 0x00, 0x00, 0x4e],
+            "deque contents should be correct"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn read_eeprom() -> Result<(), Infallible> {
+    async fn send_smart_home_request() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([
-            0x00, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad, 0x8b,
-        ]);
+        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00, 0x00]);
         let mut intf = Interface::new(&mut deque);
-        let data: [u8; 10] = intf.read_eeprom(0xabcd).await?;
+        let resp: [u8; 2] = intf
+            .send_smart_home_request(0x0001, [0x00, 0x03].into())
+            .await?
+            .into();
+
+        assert_eq!(
+            deque,
+            [0x85, 0x01, 0x00, 0x02, 0x88, 0x00, 0x03, 0x03, 0x00],
+            "deque contents should be correct"
+        );
+
+        assert_eq!(resp, [0x00, 0x00], "response should be correct");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_invalid_argument() -> Result<(), Infallible> {
+        static DATA: [u8; 65536] = [0x00; _];
+
+        init_logger();
+
+        let mut deque = VecDeque::from([]);
+        let mut intf = Interface::new(&mut deque);
+        let res: Result<[u8; 65536], _> = Box::pin(intf.read_memory(0xabcd)).await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidArgument,
+            "result should be invalid argument error"
+        );
+
+        let res = Box::pin(intf.write_memory(0xabcd, DATA)).await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidArgument,
+            "result should be invalid argument error"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_incorrect_checksum() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x01, 0x00, 0x11, 0xff]);
+        let mut intf = Interface::new(&mut deque);
+        let res = intf.lock().await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::IncorrectChecksum,
+            "result should be incorrect checksum error"
+        );
+
+        let res: Result<u8, _> = intf.read_memory(0xabcd).await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::IncorrectChecksum,
+            "result should be incorrect checksum error"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_invalid_command() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut deque = VecDeque::from([0x02]);
+        let mut intf = Interface::new(&mut deque);
+        let res = intf.lock().await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidCommand,
+            "result should be invalid command error"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn session_restore() -> Result<(), Infallible> {
+        init_logger();
+
+        let time = Rc::new(Cell::new(Duration::ZERO));
+        let sim_time = time.clone();
+        let intf_time = time.clone();
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_clock(move || sim_time.get());
+        let mut intf = Interface::new(&mut sim);
+
+        intf.enable_session(move || intf_time.get());
+        intf.query_software_id().await?;
+        intf.unlock(UnlockSequence {
+            keys: KeyPair::new(0x43ea, 0x1f02),
+            rom_unlock: Some(RomUnlock {
+                addr: 0x02c2,
+                val: 0x01,
+            }),
+        })
+        .await?;
+
+        assert_eq!(
+            intf.access_level(),
+            AccessLevel::Full,
+            "access level should be correct"
+        );
+
+        // Device locks itself after 3 seconds of inactivity
+        time.set(Duration::from_secs(5));
+        intf.write_memory(0x0100, 0xabu8).await?;
+
+        let val: u8 = intf.read_memory(0x0100).await?;
+
+        assert_eq!(val, 0xab, "memory contents should be correct");
+
+        intf.lock().await?;
 
         assert_eq!(
-            deque,
-            [0x31, 0xcd, 0xab, 0x0a, 0xb3, 0x00, 0x00, 0x00],
-            "deque contents should be correct"
+            intf.access_level(),
+            AccessLevel::Locked,
+            "access level should be correct"
         );
 
-        assert_eq!(
-            data,
-            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
-            "EEPROM contents should be correct"
-        );
+        let val: u8 = intf.read_memory(0x02c2).await?;
+
+        assert_eq!(val, 0x01, "ROM readout protection should be disabled");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn query_max_baud_rate() -> Result<(), Infallible> {
+    async fn prefetch_memory() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x80, 0x03, 0x83]);
-        let mut intf = Interface::new(&mut deque);
-        let rate = intf.query_max_baud_rate().await?;
+        let mut sim =
+            Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, (0x00..=0xff).collect::<Vec<_>>());
+        let mut intf = Interface::new(&mut sim);
 
-        assert_eq!(
-            deque,
-            [0x38, 0x00, 0x00, 0x02, 0x3a, 0x00],
-            "deque contents should be correct"
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x43ea).await?;
+        intf.prefetch_memory([0x0040..0x0042, 0x0010..0x0012, 0x0014..0x0018])
+            .await?;
+
+        assert_eq!(intf.prefetched.len(), 2, "nearby ranges should be merged");
+
+        // Prefetched memory is served without communicating with the device
+        intf.lock().await?;
+
+        let val: u16 = intf.read_memory(0x0015).await?;
+
+        assert_eq!(val, 0x1615, "memory contents should be correct");
+        assert!(
+            intf.read_memory::<u8, 1>(0x0030).await.is_err(),
+            "memory outside prefetched ranges should be read from the device"
         );
 
-        assert_eq!(rate, BaudRate::Baud38400, "baud rate should be correct");
+        intf.clear_prefetched();
+
+        assert!(
+            intf.read_memory::<u8, 1>(0x0015).await.is_err(),
+            "memory should be read from the device after clearing"
+        );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn unlock_full_access() -> Result<(), Infallible> {
+    async fn keep_alive() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let time = Rc::new(Cell::new(Duration::ZERO));
+        let sim_time = time.clone();
+        let intf_time = time.clone();
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_clock(move || sim_time.get());
+        let mut intf = Interface::new(&mut sim);
+
+        intf.enable_session(move || intf_time.get());
+        intf.query_software_id().await?;
+        intf.unlock(UnlockSequence {
+            keys: KeyPair::new(0x43ea, 0x1f02),
+            rom_unlock: None,
+        })
+        .await?;
 
-        intf.unlock_full_access(0xabcd).await?;
+        for secs in 1..=10 {
+            time.set(Duration::from_millis(secs * 1500));
+            intf.keep_alive().await?;
+        }
 
-        assert_eq!(
-            deque,
-            [0x32, 0xcd, 0xab, 0x00, 0xaa],
-            "deque contents should be correct"
-        );
+        // Device should still be unlocked without restoring the session
+        intf.disable_session();
+        time.set(Duration::from_millis(15100));
+
+        let val: u8 = intf.read_memory(0x0000).await?;
+
+        assert_eq!(val, 0x00, "memory contents should be correct");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn write_memory() -> Result<(), Infallible> {
+    async fn keep_alive_prefetched() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00]);
-        let mut intf = Interface::new(&mut deque);
-
-        intf.write_memory(
-            0xabcd,
-            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
-        )
+        let time = Rc::new(Cell::new(Duration::ZERO));
+        let sim_time = time.clone();
+        let intf_time = time.clone();
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_clock(move || sim_time.get());
+        let mut intf = Interface::new(&mut sim);
+
+        intf.enable_session(move || intf_time.get());
+        intf.query_software_id().await?;
+        intf.unlock(UnlockSequence {
+            keys: KeyPair::new(0x43ea, 0x1f02),
+            rom_unlock: None,
+        })
         .await?;
+        intf.prefetch_memory([0x0000..0x0010]).await?;
 
-        assert_eq!(
-            deque,
-            [
-                0x40, 0xcd, 0xab, 0x0a, 0xc2, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99,
-                0x00, 0xde, 0xad, 0x8b
-            ],
-            "deque contents should be correct"
-        );
+        for secs in 1..=10 {
+            time.set(Duration::from_millis(secs * 1500));
+            intf.keep_alive().await?;
+        }
+
+        // Keep-alive reads must not be served from prefetched memory
+        intf.disable_session();
+        time.set(Duration::from_millis(15100));
+
+        let val: u8 = intf.read_memory(0x0100).await?;
+
+        assert_eq!(val, 0x00, "memory contents should be correct");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn write_memory_extended() -> Result<(), Infallible> {
+    async fn negotiate_fast_mode() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00, 0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let mut sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_extended_commands(BaudRate::Baud57600, 64);
 
-        intf.write_memory(
-            0x1234_abcd,
-            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
-        )
-        .await?;
+        sim.write_memory(0x03fe, &[0x12, 0x34]);
+
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.query_software_id().await?;
+            intf.unlock(UnlockSequence {
+                keys: KeyPair::new(0x43ea, 0x1f02),
+                rom_unlock: None,
+            })
+            .await?;
+
+            let rate = intf.negotiate_fast_mode().await?;
+            let data: [u8; 0x0400] = intf.read_memory(0x0000).await?;
+
+            assert_eq!(rate, BaudRate::Baud57600, "baud rate should be correct");
+            assert_eq!(
+                intf.access_level(),
+                AccessLevel::Full,
+                "access level should be correct"
+            );
+            assert_eq!(
+                data[0x03fe..],
+                [0x12, 0x34],
+                "memory contents should be correct"
+            );
+        }
 
         assert_eq!(
-            deque,
-            [
-                0x37, 0x34, 0x12, 0x00, 0x7d, 0x40, 0xcd, 0xab, 0x0a, 0xc2, 0x11, 0x22, 0x33, 0x44,
-                0xaa, 0xab, 0xcd, 0xef, 0x99, 0x00, 0xde, 0xad, 0x8b
-            ],
-            "deque contents should be correct"
+            sim.baud_rate(),
+            BaudRate::Baud57600,
+            "device baud rate should be correct"
         );
+        assert_eq!(sim.chunk_size(), 64, "device chunk size should be correct");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn write_eeprom() -> Result<(), Infallible> {
+    async fn negotiate_fast_mode_older_device() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x0400]);
 
-        intf.write_eeprom(
-            0xabcd,
-            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
-        )
-        .await?;
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.query_software_id().await?;
+            intf.unlock(UnlockSequence {
+                keys: KeyPair::new(0x43ea, 0x1f02),
+                rom_unlock: None,
+            })
+            .await?;
+
+            let rate = intf.negotiate_fast_mode().await?;
+            let _: u8 = intf.read_memory(0x0000).await?;
+
+            assert_eq!(rate, BaudRate::Baud9600, "baud rate should be correct");
+        }
 
         assert_eq!(
-            deque,
-            [
-                0x41, 0xcd, 0xab, 0x0a, 0xc3, 0x11, 0x22, 0x33, 0x44, 0xaa, 0xab, 0xcd, 0xef, 0x99,
-                0x00, 0xde, 0xad, 0x8b
-            ],
-            "deque contents should be correct"
+            sim.baud_rate(),
+            BaudRate::Baud9600,
+            "device baud rate should be correct"
+        );
+        assert_eq!(
+            sim.chunk_size(),
+            DEFAULT_CHUNK_SIZE,
+            "device chunk size should be correct"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn jump_to_subroutine() -> Result<(), Infallible> {
+    async fn negotiate_fast_mode_fallback() -> Result<(), ErrorKind> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let mut sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_extended_commands(BaudRate::Baud115200, 128);
 
-        intf.jump_to_subroutine(0xabcd).await?;
+        {
+            let mut intf = Interface::new(LimitedPort(&mut sim));
+
+            intf.query_software_id().await?;
+            intf.unlock(UnlockSequence {
+                keys: KeyPair::new(0x43ea, 0x1f02),
+                rom_unlock: None,
+            })
+            .await?;
+
+            let rate = intf.negotiate_fast_mode().await?;
+            let _: u8 = intf.read_memory(0x0000).await?;
+
+            assert_eq!(rate, BaudRate::Baud2400, "baud rate should be correct");
+        }
 
         assert_eq!(
-            deque,
-            [0x42, 0xcd, 0xab, 0x00, 0xba],
-            "deque contents should be correct"
+            sim.baud_rate(),
+            BaudRate::Baud2400,
+            "device baud rate should be correct"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn jump_to_subroutine_extended() -> Result<(), Infallible> {
+    async fn error_invalid_state() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x00, 0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02);
+        let mut intf = Interface::new(&mut sim);
 
-        intf.jump_to_subroutine(0x1234_abcd).await?;
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x43ea).await?;
+        intf.unlock_full_access(0x1f02).await?;
+
+        let res = intf.negotiate_fast_mode().await;
 
         assert_eq!(
-            deque,
-            [0x37, 0x34, 0x12, 0x00, 0x7d, 0x42, 0xcd, 0xab, 0x00, 0xba],
-            "deque contents should be correct"
+            res.unwrap_err(),
+            Error::InvalidState,
+            "result should be invalid state error"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn halt() -> Result<(), Infallible> {
+    async fn typed_access_levels() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let mut sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_extended_commands(BaudRate::Baud38400, 64);
 
-        intf.halt().await?;
+        {
+            let mut intf = Interface::new(&mut sim).into_typed();
+
+            intf.query_software_id().await?;
+
+            let mut intf = intf.unlock_read_access(0x43ea).await?;
+            let _: u8 = intf.read_memory(0x0000).await?;
+
+            let mut intf = intf.unlock_full_access(0x1f02).await?;
+
+            intf.write_memory(0x0000, 0x12u8).await?;
+            intf.set_chunk_size(16).await?;
 
+            let mut intf = intf.lock().await?;
+
+            intf.query_software_id().await?;
+
+            let intf = intf.unlock_smart_home_access().await?;
+            let (intf, rate) = intf.set_baud_rate(BaudRate::Baud19200).await?;
+
+            assert_eq!(rate, BaudRate::Baud19200, "baud rate should be correct");
+            assert_eq!(
+                intf.access_level(),
+                AccessLevel::Locked,
+                "access level should be locked"
+            );
+
+            let intf = intf.into_dynamic();
+
+            assert_eq!(
+                intf.access_level(),
+                AccessLevel::Locked,
+                "access level should be preserved"
+            );
+        }
+
+        assert_eq!(sim.chunk_size(), 16, "chunk size should be correct");
         assert_eq!(
-            deque,
-            [0x45, 0x00, 0x00, 0x00, 0x45],
-            "deque contents should be correct"
+            sim.baud_rate(),
+            BaudRate::Baud19200,
+            "baud rate should be correct"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn set_baud_rate_2400() -> Result<(), Infallible> {
+    async fn read_only() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00]);
-        let mut intf = Interface::new(&mut deque);
-
-        intf.set_baud_rate(BaudRate::Baud2400).await?;
+        let mut sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0x0000, [0x00; 0x0400])
+            .with_extended_commands(BaudRate::Baud38400, 64);
+
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.set_read_only(true);
+            intf.query_software_id().await?;
+            intf.unlock_smart_home_access().await?;
+
+            // Link configuration does not modify the device
+            intf.set_chunk_size(16).await?;
+
+            assert_eq!(
+                intf.set_baud_rate(BaudRate::Baud19200).await?,
+                BaudRate::Baud19200,
+                "baud rate should be correct"
+            );
+            assert_eq!(
+                intf.write_memory(0x0000, 0x12u8).await.unwrap_err(),
+                Error::ReadOnly,
+                "write should fail with read-only error"
+            );
+
+            let res = intf
+                .into_typed()
+                .unlock(UnlockSequence {
+                    keys: KeyPair::new(0x43ea, 0x1f02),
+                    rom_unlock: None,
+                })
+                .await;
+
+            assert_eq!(
+                res.unwrap_err(),
+                Error::ReadOnly,
+                "typed unlock should fail with read-only error"
+            );
+        }
 
-        assert_eq!(
-            deque,
-            [0x46, 0x00, 0x00, 0x00, 0x46],
-            "deque contents should be correct"
-        );
+        assert_eq!(sim.chunk_size(), 16, "chunk size should be correct");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn set_baud_rate_9600() -> Result<(), Infallible> {
+    async fn unlock_with_known_keys() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let db = KeyDatabase::builtin();
 
-        intf.set_baud_rate(BaudRate::Baud9600).await?;
+        // Unknown software ID sharing the keys of a known device
+        let mut sim = Simulator::new(1234, 0x8542, 0x6567).with_ram(0x0000, [0x00; 0x10]);
+        let mut intf = Interface::new(&mut sim);
 
         assert_eq!(
-            deque,
-            [0x47, 0x00, 0x00, 0x00, 0x47],
-            "deque contents should be correct"
+            intf.unlock_with_known_keys(&db).await?,
+            KeyPair::new(0x8542, 0x6567),
+            "keys should be correct"
+        );
+        assert_eq!(
+            intf.access_level(),
+            AccessLevel::Full,
+            "access level should be full"
         );
 
-        Ok(())
-    }
+        intf.write_memory(0x0000, 0x12u8).await?;
 
-    #[tokio::test]
-    async fn set_chunk_size() -> Result<(), Infallible> {
-        init_logger();
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x0400]);
+        let (_, keys) = Interface::new(&mut sim)
+            .into_typed()
+            .unlock_with_known_keys(&db)
+            .await?;
 
-        let mut deque = VecDeque::from([
-            0x00, 0x80, 0x80, 0x00, 0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad,
-            0x35,
-        ]);
-        let mut intf = Interface::new(&mut deque);
+        assert_eq!(keys, KeyPair::new(0x43ea, 0x1f02), "keys should be correct");
 
-        intf.set_chunk_size(128).await?;
+        let mut val = [0x00];
 
-        let data: [u8; 10] = intf.read_memory(0xabcd).await?;
+        sim.read_memory(0x02c2, &mut val);
+
+        assert_eq!(val, [0x01], "ROM readout protection should be disabled");
+
+        // Full access key is verified using the ROM readout protection
+        let mut sim = Simulator::new(629, 0x43ea, 0x0001).with_ram(0x0000, [0x00; 0x0400]);
 
         assert_eq!(
-            deque,
-            [
-                0x4a, 0x80, 0x0, 0x1, 0xcb, 0x00, 0x30, 0xcd, 0xab, 0x0a, 0xb2, 0x00
-            ],
-            "deque contents should be correct"
+            Interface::new(&mut sim)
+                .unlock_with_known_keys(&db)
+                .await
+                .unwrap_err(),
+            Error::KeysNotFound,
+            "unlock should fail"
         );
 
+        let mut sim = Simulator::new(1234, 0x0000, 0x0000).with_ram(0x0000, [0x00; 0x10]);
+
         assert_eq!(
-            data,
-            [0x11, 0x22, 0x33, 0x44, 0xab, 0xcd, 0xef, 0x99, 0xde, 0xad],
-            "memory contents should be correct"
+            Interface::new(&mut sim)
+                .unlock_with_known_keys(&db)
+                .await
+                .unwrap_err(),
+            Error::KeysNotFound,
+            "unlock should fail"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn set_baud_rate() -> Result<(), Infallible> {
+    async fn read_write_memory_slice() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x02, 0x02]);
-        let mut intf = Interface::new(&mut deque);
+        let data: Vec<u8> = (0..=u8::MAX).cycle().take(0x0300).collect();
+        let mut sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0xff00, vec![0x00; 0x0400])
+            .with_extended_commands(BaudRate::Baud38400, 64);
+        let mut intf = Interface::new(&mut sim);
+        let mut progress = Vec::new();
+
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x43ea).await?;
+        intf.unlock_full_access(0x1f02).await?;
+        intf.set_extended_addressing(true);
+        intf.write_memory_slice(0xff80, &data, |done, total| {
+            progress.push((done, total));
+        })
+        .await?;
 
-        intf.set_baud_rate(BaudRate::Baud19200).await?;
+        let read = intf.read_memory_vec(0xff80, data.len(), |_, _| {}).await?;
 
+        assert_eq!(read, data, "memory contents should be correct");
         assert_eq!(
-            deque,
-            [0x4b, 0x02, 0x00, 0x01, 0x4e, 0x00],
-            "deque contents should be correct"
+            progress.last(),
+            Some(&(0x0300, 0x0300)),
+            "progress should be complete"
+        );
+        assert!(
+            progress.is_sorted(),
+            "progress should increase monotonically"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn reset() -> Result<(), Infallible> {
+    async fn read_write_eeprom_slice() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00]);
-        let mut intf = Interface::new(&mut deque);
+        let data: Vec<u8> = (0..=u8::MAX).cycle().take(0x0201).collect();
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+            .with_eeprom(vec![0x00; 0x0800], EepromAddressing::Word);
 
-        intf.reset().await?;
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.query_software_id().await?;
+            intf.unlock_read_access(0x43ea).await?;
+            intf.unlock_full_access(0x1f02).await?;
+            intf.write_eeprom_slice(0x0010, &data, |_, _| {}).await?;
+
+            let read = intf.read_eeprom_vec(0x0010, data.len(), |_, _| {}).await?;
+
+            assert_eq!(read, data, "EEPROM contents should be correct");
+        }
 
         assert_eq!(
-            deque,
-            [0x4e, 0x00, 0x00, 0x00, 0x4e],
-            "deque contents should be correct"
+            sim.eeprom()[0x0020..0x0221],
+            data,
+            "simulated EEPROM contents should be correct"
         );
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn send_smart_home_request() -> Result<(), Infallible> {
+    async fn eeprom_range_end() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x00, 0x00, 0x00, 0x00, 0x00]);
-        let mut intf = Interface::new(&mut deque);
-        let resp: [u8; 2] = intf
-            .send_smart_home_request(0x0001, [0x00, 0x03].into())
-            .await?
-            .into();
+        let data: Vec<u8> = (0..0x0f).collect();
+        let mut sim = Simulator::new(629, 0x43ea, 0x1f02)
+            .with_eeprom(vec![0x00; 0x0001_0000], EepromAddressing::Byte);
+        let mut intf = Interface::new(&mut sim);
+
+        intf.set_eeprom_addressing(EepromAddressing::Byte);
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x43ea).await?;
+        intf.unlock_full_access(0x1f02).await?;
+
+        // An odd number of bytes up to the end of the byte-addressed EEPROM
+        intf.write_eeprom_slice(0xfff1, &data, |_, _| {}).await?;
 
         assert_eq!(
-            deque,
-            [0x85, 0x01, 0x00, 0x02, 0x88, 0x00, 0x03, 0x03, 0x00],
-            "deque contents should be correct"
+            intf.read_eeprom_vec(0xfff1, data.len(), |_, _| {}).await?,
+            data,
+            "EEPROM contents should be correct"
+        );
+        assert_eq!(
+            intf.read_eeprom_vec(0xfff1, data.len() + 1, |_, _| {})
+                .await
+                .unwrap_err(),
+            Error::InvalidArgument,
+            "result should be invalid argument error"
         );
-
-        assert_eq!(resp, [0x00, 0x00], "response should be correct");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn error_invalid_argument() -> Result<(), Infallible> {
-        static DATA: [u8; 65536] = [0x00; _];
-
+    async fn error_memory_range() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([]);
+        let mut deque = VecDeque::new();
         let mut intf = Interface::new(&mut deque);
-        let res: Result<[u8; 65536], _> = Box::pin(intf.read_memory(0xabcd)).await;
+        let res = intf.read_memory_vec(0xfff0, 0x20, |_, _| {}).await;
 
         assert_eq!(
             res.unwrap_err(),
@@ -1290,54 +3404,67 @@ mod tests {
             "result should be invalid argument error"
         );
 
-        let res = Box::pin(intf.write_memory(0xabcd, DATA)).await;
+        let res = intf
+            .write_eeprom_slice(0xfff0, &[0x00; 0x40], |_, _| {})
+            .await;
 
         assert_eq!(
             res.unwrap_err(),
             Error::InvalidArgument,
             "result should be invalid argument error"
         );
+        assert!(deque.is_empty(), "deque should be empty");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn error_incorrect_checksum() -> Result<(), Infallible> {
+    async fn retry_incorrect_checksum() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x01, 0x00, 0x11, 0xff]);
+        let mut deque =
+            VecDeque::from([0x01, 0x00, 0x00, 0x75, 0x02, 0xff, 0x00, 0x75, 0x02, 0x77]);
         let mut intf = Interface::new(&mut deque);
-        let res = intf.lock().await;
 
-        assert_eq!(
-            res.unwrap_err(),
-            Error::IncorrectChecksum,
-            "result should be incorrect checksum error"
-        );
+        intf.set_max_retries(1);
+        intf.lock().await?;
 
-        let res: Result<u8, _> = intf.read_memory(0xabcd).await;
+        let id = intf.query_software_id().await?;
 
         assert_eq!(
-            res.unwrap_err(),
-            Error::IncorrectChecksum,
-            "result should be incorrect checksum error"
+            deque,
+            [
+                0x10, 0x00, 0x00, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x10, 0x11, 0x00, 0x00, 0x02,
+                0x13, 0x01, 0x11, 0x00, 0x00, 0x02, 0x13, 0x00
+            ],
+            "deque contents should be correct"
         );
+        assert_eq!(id, 629, "software ID should be correct");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn error_invalid_command() -> Result<(), Infallible> {
+    async fn error_timeout() -> Result<(), Infallible> {
         init_logger();
 
-        let mut deque = VecDeque::from([0x02]);
-        let mut intf = Interface::new(&mut deque);
-        let res = intf.lock().await;
+        let mut port = SilentPort;
+        let mut intf = Interface::new(&mut port);
+
+        intf.enable_timeouts(
+            TokioDelay,
+            Timeouts {
+                frame: Duration::from_millis(10),
+                byte: Duration::from_millis(10),
+            },
+        );
+
+        let res = intf.query_software_id().await;
 
         assert_eq!(
             res.unwrap_err(),
-            Error::InvalidCommand,
-            "result should be invalid command error"
+            Error::Timeout,
+            "result should be timeout error"
         );
 
         Ok(())
@@ -1,10 +1,13 @@
 //! Native asynchronous serial port support for [`Interface`](crate::Interface).
 //!
 //! Uses the [`serial2-tokio`](https://crates.io/crates/serial2-tokio) crate.
+//! Timeouts are provided by the [`tokio`](https://crates.io/crates/tokio) runtime, see [`Delay`].
 
 extern crate std;
 
-use crate::Error;
+use crate::{BaudRate, ConfigurableBaudRate, Error};
+use core::time::Duration;
+use embedded_hal_async::delay::DelayNs;
 use embedded_io_adapters::tokio_1::FromTokio;
 use embedded_io_async::ErrorType;
 use serial2_tokio::{Parity, SerialPort, Settings};
@@ -16,6 +19,26 @@ pub type Port = FromTokio<SerialPort>;
 /// Port-specific error type to be used as `E` for the generic [`Error<E>`] type.
 pub type PortError = <Port as ErrorType>::Error;
 
+/// Delay implementation using the timer of the [`tokio`](https://crates.io/crates/tokio) runtime.
+///
+/// Can be passed to [`Interface::enable_timeouts`](crate::Interface::enable_timeouts).
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Delay;
+
+impl DelayNs for Delay {
+    async fn delay_ns(&mut self, ns: u32) {
+        tokio::time::sleep(Duration::from_nanos(ns.into())).await;
+    }
+
+    async fn delay_us(&mut self, us: u32) {
+        tokio::time::sleep(Duration::from_micros(us.into())).await;
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        tokio::time::sleep(Duration::from_millis(ms.into())).await;
+    }
+}
+
 /// Opens a native serial port at the given path.
 ///
 /// Returns a [`Port`] that can be passed to [`Interface::new`](crate::Interface::new).
@@ -32,3 +55,16 @@ pub fn open(path: &str) -> Result<Port, Error<std::io::Error>> {
 
     Ok(FromTokio::new(port))
 }
+
+impl ConfigurableBaudRate for Port {
+    fn set_baud_rate(&mut self, rate: BaudRate) -> Result<(), Self::Error> {
+        let port = self.inner_mut();
+        let mut settings = port.get_configuration()?;
+
+        settings.set_baud_rate(rate.as_baud())?;
+        port.set_configuration(&settings)?;
+
+        // Discard any data received with the previous configuration
+        port.discard_buffers()
+    }
+}
@@ -0,0 +1,952 @@
+//! In-memory device simulator.
+//!
+//! Provides a [`Simulator`] that implements the device side of the diagnostic protocol.
+//! The simulator implements [`Read`] and [`Write`], so it can be used as a port
+//! for an [`Interface`](crate::Interface) or passed to [`device::connect`](crate::device::connect)
+//! to test tools and applications without an actual appliance.
+//!
+//! The simulated device answers software ID queries, checks the unlock keys,
+//! verifies the checksums of all received chunks and serves memory and EEPROM
+//! accesses from configurable RAM, ROM and EEPROM images.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{Interface, simulator::Simulator};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::Result<(), core::convert::Infallible> {
+//! let mut sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x12, 0x34]);
+//! let mut intf = Interface::new(&mut sim);
+//!
+//! assert_eq!(intf.query_software_id().await?, 629);
+//!
+//! intf.unlock_read_access(0x43ea).await?;
+//!
+//! let data: [u8; 2] = intf.read_memory(0x0000).await?;
+//!
+//! assert_eq!(data, [0x12, 0x34]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    BaudRate, Command, ConfigurableBaudRate, DEFAULT_CHUNK_SIZE, EepromAddressing, ResponseCode,
+    compute_checksum,
+    time::{Clock, LOCK_TIMEOUT},
+};
+use alloc::{boxed::Box, collections::vec_deque::VecDeque, vec, vec::Vec};
+use core::{convert::Infallible, future, time::Duration};
+use embedded_io_async::{ErrorType, Read, Write};
+use log::trace;
+
+/// Value returned when reading unmapped memory.
+const UNMAPPED_VALUE: u8 = 0xff;
+
+/// Diagnostic access level of the simulated device.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum Access {
+    Locked,
+    SmartHome,
+    Read,
+    Full,
+}
+
+/// Contiguous memory region of the simulated device.
+#[derive(Debug)]
+struct Region {
+    addr: u32,
+    data: Vec<u8>,
+    writable: bool,
+}
+
+impl Region {
+    fn offset(&self, addr: u32) -> Option<usize> {
+        let offset = usize::try_from(addr.checked_sub(self.addr)?).ok()?;
+
+        (offset < self.data.len()).then_some(offset)
+    }
+}
+
+/// Frame that is being received from the client.
+#[derive(Copy, Clone, Debug)]
+enum Frame {
+    Request,
+    WriteMemory(u32),
+    WriteEeprom(usize),
+}
+
+/// Operation performed after a response has been acknowledged by the client.
+#[derive(Copy, Clone, Debug)]
+enum Completion {
+    None,
+    SetChunkSize(u8),
+    SetBaudRate(BaudRate),
+}
+
+/// Protocol state of the simulated device.
+#[derive(Debug)]
+enum State {
+    /// Receiving the chunks of a frame.
+    Receiving {
+        frame: Frame,
+        len: usize,
+        data: Vec<u8>,
+        chunk: Vec<u8>,
+    },
+    /// Sending the chunks of a response, waiting for the client's acknowledgement.
+    Sending {
+        data: Vec<u8>,
+        pos: usize,
+        completion: Completion,
+    },
+}
+
+impl State {
+    fn request() -> Self {
+        Self::Receiving {
+            frame: Frame::Request,
+            len: 4,
+            data: Vec::new(),
+            chunk: Vec::new(),
+        }
+    }
+}
+
+/// Simulated appliance implementing the device side of the diagnostic protocol.
+///
+/// The simulator is configured with a software ID, the read and full access keys
+/// as well as optional RAM, ROM and EEPROM images.
+/// Reading from unmapped memory returns `0xff`, while writes to ROM
+/// or unmapped memory are acknowledged but ignored.
+///
+/// By default, only the commands supported by older devices are accepted.
+/// Newer devices can be simulated using [`Simulator::with_extended_commands`].
+/// The inactivity timeout of a real device can be simulated using [`Simulator::with_clock`].
+///
+/// The simulator implements [`ConfigurableBaudRate`] to model the client's port.
+/// Data written while the port's baud rate differs from the device's baud rate is discarded.
+///
+/// Data written to the simulator is processed immediately.
+/// Reading from the simulator returns the device's pending response bytes.
+/// If no response is pending, the read returns zero bytes, which the
+/// [`Interface`](crate::Interface) reports as an unexpected end-of-file.
+#[derive(Debug)]
+pub struct Simulator {
+    software_id: u16,
+    read_key: u16,
+    full_key: u16,
+    regions: Vec<Region>,
+    eeprom: Vec<u8>,
+    eeprom_addressing: EepromAddressing,
+    extended: Option<(BaudRate, u8)>,
+    access: Access,
+    software_id_queried: bool,
+    extension: Option<(u16, u8)>,
+    chunk_size: u8,
+    baud_rate: BaudRate,
+    port_baud_rate: BaudRate,
+    halted: bool,
+    state: State,
+    output: VecDeque<u8>,
+    clock: Option<Box<dyn Clock>>,
+    last_activity: Duration,
+}
+
+impl Simulator {
+    /// Constructs a new simulator with the specified software ID and access keys.
+    ///
+    /// The simulated device has no memory and an empty, word-addressed EEPROM.
+    #[must_use]
+    pub fn new(software_id: u16, read_key: u16, full_key: u16) -> Self {
+        Self {
+            software_id,
+            read_key,
+            full_key,
+            regions: Vec::new(),
+            eeprom: Vec::new(),
+            eeprom_addressing: EepromAddressing::Word,
+            extended: None,
+            access: Access::Locked,
+            software_id_queried: false,
+            extension: None,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            baud_rate: BaudRate::Baud2400,
+            port_baud_rate: BaudRate::Baud2400,
+            halted: false,
+            state: State::request(),
+            output: VecDeque::new(),
+            clock: None,
+            last_activity: Duration::ZERO,
+        }
+    }
+
+    /// Maps a writable RAM image at the specified address.
+    #[must_use]
+    pub fn with_ram(mut self, addr: u32, data: impl Into<Vec<u8>>) -> Self {
+        self.regions.push(Region {
+            addr,
+            data: data.into(),
+            writable: true,
+        });
+
+        self
+    }
+
+    /// Maps a read-only ROM image at the specified address.
+    #[must_use]
+    pub fn with_rom(mut self, addr: u32, data: impl Into<Vec<u8>>) -> Self {
+        self.regions.push(Region {
+            addr,
+            data: data.into(),
+            writable: false,
+        });
+
+        self
+    }
+
+    /// Sets the EEPROM image and its addressing mode.
+    #[must_use]
+    pub fn with_eeprom(mut self, data: impl Into<Vec<u8>>, addressing: EepromAddressing) -> Self {
+        self.eeprom = data.into();
+        self.eeprom_addressing = addressing;
+
+        self
+    }
+
+    /// Enables the commands that are only available on newer devices.
+    ///
+    /// This includes extended addresses, baud rates above 9600 baud,
+    /// adjustable chunk sizes, resets and smart home access.
+    /// The chunk size requested by the client is clamped to the range
+    /// from 4 bytes to `max_chunk_size`.
+    #[must_use]
+    pub fn with_extended_commands(mut self, max_baud_rate: BaudRate, max_chunk_size: u8) -> Self {
+        self.extended = Some((max_baud_rate, max_chunk_size.max(DEFAULT_CHUNK_SIZE)));
+
+        self
+    }
+
+    /// Enables the inactivity timeout using the provided clock.
+    ///
+    /// The simulated device locks itself if no data has been
+    /// received for 3 seconds, just like a real device.
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.last_activity = clock.now();
+        self.clock = Some(Box::new(clock));
+
+        self
+    }
+
+    /// Returns the software ID of the simulated device.
+    #[must_use]
+    pub fn software_id(&self) -> u16 {
+        self.software_id
+    }
+
+    /// Reads the simulated device's memory into the provided buffer.
+    pub fn read_memory(&self, addr: u32, buf: &mut [u8]) {
+        for (byte_addr, byte) in (addr..).zip(buf.iter_mut()) {
+            *byte = self
+                .regions
+                .iter()
+                .find_map(|region| Some(region.data[region.offset(byte_addr)?]))
+                .unwrap_or(UNMAPPED_VALUE);
+        }
+    }
+
+    /// Writes the provided data to the simulated device's memory.
+    ///
+    /// In contrast to diagnostic write commands, this also modifies ROM regions.
+    /// Data written to unmapped addresses is discarded.
+    pub fn write_memory(&mut self, addr: u32, data: &[u8]) {
+        for (byte_addr, &byte) in (addr..).zip(data) {
+            if let Some(dst) = self.byte_mut(byte_addr, true) {
+                *dst = byte;
+            }
+        }
+    }
+
+    /// Returns the contents of the simulated device's EEPROM.
+    #[must_use]
+    pub fn eeprom(&self) -> &[u8] {
+        &self.eeprom
+    }
+
+    /// Returns the mutable contents of the simulated device's EEPROM.
+    pub fn eeprom_mut(&mut self) -> &mut [u8] {
+        &mut self.eeprom
+    }
+
+    /// Returns the current chunk size of the simulated device.
+    #[must_use]
+    pub fn chunk_size(&self) -> u8 {
+        self.chunk_size
+    }
+
+    /// Returns the current baud rate of the simulated device.
+    #[must_use]
+    pub fn baud_rate(&self) -> BaudRate {
+        self.baud_rate
+    }
+
+    /// Returns whether the simulated device was halted.
+    #[must_use]
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Locks the simulated device's diagnostic interface.
+    ///
+    /// This behaves like a real device after 3 seconds of inactivity.
+    /// Any incomplete frame is discarded.
+    pub fn lock(&mut self) {
+        self.access = Access::Locked;
+        self.software_id_queried = false;
+        self.extension = None;
+        self.state = State::request();
+    }
+
+    /// Processes a single byte received from the client.
+    fn process(&mut self, byte: u8) {
+        match &mut self.state {
+            State::Receiving {
+                len, data, chunk, ..
+            } => {
+                let chunk_len = (*len - data.len()).min(self.chunk_size.into());
+
+                if chunk.len() < chunk_len {
+                    chunk.push(byte);
+
+                    return;
+                }
+
+                if byte != compute_checksum(chunk) {
+                    trace!("Simulator received chunk with incorrect checksum: {chunk:02x?}");
+
+                    self.respond(ResponseCode::IncorrectChecksum);
+                    self.state = State::request();
+
+                    return;
+                }
+
+                data.append(chunk);
+
+                if data.len() < *len {
+                    self.respond(ResponseCode::Success);
+
+                    return;
+                }
+
+                let State::Receiving { frame, data, .. } =
+                    core::mem::replace(&mut self.state, State::request())
+                else {
+                    unreachable!();
+                };
+
+                self.handle_frame(frame, &data);
+            }
+            State::Sending { data, pos, .. } => {
+                if byte != ResponseCode::Success as u8 {
+                    trace!("Simulator received response code {byte:02x}, aborting transfer");
+
+                    self.state = State::request();
+
+                    return;
+                }
+
+                *pos += usize::from(self.chunk_size).min(data.len() - *pos);
+
+                if *pos < data.len() {
+                    self.send_chunk();
+
+                    return;
+                }
+
+                let State::Sending { completion, .. } =
+                    core::mem::replace(&mut self.state, State::request())
+                else {
+                    unreachable!();
+                };
+
+                self.complete(completion);
+            }
+        }
+    }
+
+    /// Handles a completely received frame.
+    fn handle_frame(&mut self, frame: Frame, data: &[u8]) {
+        match frame {
+            Frame::Request => {
+                let param = u16::from_le_bytes([data[1], data[2]]);
+
+                if self.handle_request(data[0], param, data[3]).is_err() {
+                    trace!("Simulator rejected request: {data:02x?}");
+
+                    self.respond(ResponseCode::InvalidCommand);
+                    self.state = State::request();
+                }
+            }
+            Frame::WriteMemory(addr) => {
+                for (byte_addr, &byte) in (addr..).zip(data) {
+                    if let Some(dst) = self.byte_mut(byte_addr, false) {
+                        *dst = byte;
+                    }
+                }
+
+                self.respond(ResponseCode::Success);
+            }
+            Frame::WriteEeprom(offset) => {
+                for (dst, &src) in self.eeprom.iter_mut().skip(offset).zip(data) {
+                    *dst = src;
+                }
+
+                self.respond(ResponseCode::Success);
+            }
+        }
+    }
+
+    /// Handles a request, returning an error if the command is not permitted.
+    fn handle_request(&mut self, cmd: u8, param: u16, len: u8) -> Result<(), ()> {
+        let (ext_param, ext_len) = self.extension.take().unwrap_or_default();
+        let addr = (u32::from(ext_param) << 16) | u32::from(param);
+        let ext_len = (usize::from(ext_len) << 8) | usize::from(len);
+        let cmd = Command::from_repr(cmd).ok_or(())?;
+
+        trace!("Simulator handling command {cmd:?} with parameter {param:04x} and length {len}");
+
+        match cmd {
+            Command::Lock => {
+                self.lock();
+                self.respond(ResponseCode::Success);
+            }
+            Command::QuerySoftwareId => {
+                self.software_id_queried = true;
+                self.respond(ResponseCode::Success);
+                self.send(self.software_id.to_le_bytes().into(), Completion::None);
+            }
+            Command::UnlockReadAccess => {
+                require(self.software_id_queried)?;
+
+                // Incorrect keys are acknowledged, but do not unlock the interface
+                if param == self.read_key {
+                    self.access = Access::Read;
+                }
+
+                self.respond(ResponseCode::Success);
+            }
+            Command::UnlockSmartHomeAccess => {
+                require(self.extended.is_some() && self.software_id_queried)?;
+
+                if self.access == Access::Locked {
+                    self.access = Access::SmartHome;
+                }
+
+                self.respond(ResponseCode::Success);
+            }
+            Command::ReadMemory => {
+                require(self.can_read())?;
+
+                let mut data = vec![0x00; ext_len];
+
+                self.read_memory(addr, &mut data);
+                self.respond(ResponseCode::Success);
+                self.send(data, Completion::None);
+            }
+            Command::ReadEeprom => {
+                require(self.can_read())?;
+
+                let offset = self.eeprom_offset(param);
+                let data = (offset..offset + usize::from(len))
+                    .map(|i| self.eeprom.get(i).copied().unwrap_or(UNMAPPED_VALUE))
+                    .collect();
+
+                self.respond(ResponseCode::Success);
+                self.send(data, Completion::None);
+            }
+            Command::UnlockFullAccess => {
+                require(self.can_read())?;
+
+                if param == self.full_key {
+                    self.access = Access::Full;
+                }
+
+                self.respond(ResponseCode::Success);
+            }
+            Command::WriteMemory => {
+                require(self.access == Access::Full)?;
+                self.respond(ResponseCode::Success);
+                self.receive(ext_len, Frame::WriteMemory(addr));
+            }
+            Command::WriteEeprom => {
+                require(self.access == Access::Full)?;
+                self.respond(ResponseCode::Success);
+                self.receive(
+                    usize::from(len),
+                    Frame::WriteEeprom(self.eeprom_offset(param)),
+                );
+            }
+            _ => self.handle_control_request(cmd, param, len)?,
+        }
+
+        Ok(())
+    }
+
+    /// Handles a control request, returning an error if the command is not permitted.
+    fn handle_control_request(&mut self, cmd: Command, param: u16, len: u8) -> Result<(), ()> {
+        match cmd {
+            Command::ExtendAddress => {
+                require(self.extended.is_some() && self.access != Access::Locked)?;
+                self.extension = Some((param, len));
+                self.respond(ResponseCode::Success);
+            }
+            Command::QueryMaxBaudRate => {
+                let (max_rate, _) = self.extended.ok_or(())?;
+
+                require(self.access != Access::Locked)?;
+                self.respond(ResponseCode::Success);
+                self.send(vec![0x00, max_rate as u8], Completion::None);
+            }
+            Command::JumpToSubroutine => {
+                require(self.access == Access::Full)?;
+                self.respond(ResponseCode::Success);
+
+                // Subroutine returns immediately
+                self.output.push_back(0x00);
+                self.lock();
+            }
+            Command::Halt => {
+                require(self.access == Access::Full)?;
+                self.halted = true;
+                self.respond(ResponseCode::Success);
+            }
+            Command::SetBaudRate2400 | Command::SetBaudRate9600 => {
+                require(self.access == Access::Full)?;
+                self.respond(ResponseCode::Success);
+                self.complete(Completion::SetBaudRate(
+                    if cmd == Command::SetBaudRate2400 {
+                        BaudRate::Baud2400
+                    } else {
+                        BaudRate::Baud9600
+                    },
+                ));
+            }
+            Command::SetChunkSize => {
+                let (_, max_size) = self.extended.ok_or(())?;
+
+                require(matches!(self.access, Access::SmartHome | Access::Full))?;
+
+                let size = u8::try_from(param)
+                    .unwrap_or(u8::MAX)
+                    .clamp(DEFAULT_CHUNK_SIZE, max_size);
+
+                self.respond(ResponseCode::Success);
+                self.send(vec![size], Completion::SetChunkSize(size));
+            }
+            Command::SetBaudRate => {
+                let (max_rate, _) = self.extended.ok_or(())?;
+
+                require(matches!(self.access, Access::SmartHome | Access::Full))?;
+
+                let rate = u8::try_from(param)
+                    .ok()
+                    .and_then(BaudRate::from_repr)
+                    .ok_or(())?;
+                let rate = if (rate as u8) > (max_rate as u8) {
+                    max_rate
+                } else {
+                    rate
+                };
+
+                self.respond(ResponseCode::Success);
+                self.send(vec![rate as u8], Completion::SetBaudRate(rate));
+            }
+            Command::Reset => {
+                require(self.extended.is_some() && self.access == Access::Full)?;
+                self.respond(ResponseCode::Success);
+                self.lock();
+                self.chunk_size = DEFAULT_CHUNK_SIZE;
+                self.baud_rate = BaudRate::Baud2400;
+                self.halted = false;
+            }
+            // Smart home requests are not simulated
+            _ => return Err(()),
+        }
+
+        Ok(())
+    }
+
+    /// Returns a mutable reference to the byte at the specified address.
+    ///
+    /// Bytes in ROM regions are only returned if `include_rom` is set.
+    fn byte_mut(&mut self, addr: u32, include_rom: bool) -> Option<&mut u8> {
+        self.regions
+            .iter_mut()
+            .filter(|region| include_rom || region.writable)
+            .find_map(|region| {
+                let offset = region.offset(addr)?;
+
+                Some(&mut region.data[offset])
+            })
+    }
+
+    /// Returns whether the current access level permits reading.
+    fn can_read(&self) -> bool {
+        matches!(self.access, Access::Read | Access::Full)
+    }
+
+    /// Converts an EEPROM address into a byte offset.
+    fn eeprom_offset(&self, addr: u16) -> usize {
+        match self.eeprom_addressing {
+            EepromAddressing::Byte => usize::from(addr),
+            EepromAddressing::Word => usize::from(addr) * 2,
+        }
+    }
+
+    /// Queues a response code for the client.
+    fn respond(&mut self, code: ResponseCode) {
+        self.output.push_back(code as u8);
+    }
+
+    /// Starts receiving a frame with the specified length from the client.
+    fn receive(&mut self, len: usize, frame: Frame) {
+        if len > 0 {
+            self.state = State::Receiving {
+                frame,
+                len,
+                data: Vec::new(),
+                chunk: Vec::new(),
+            };
+        }
+    }
+
+    /// Starts sending a response to the client.
+    fn send(&mut self, data: Vec<u8>, completion: Completion) {
+        if data.is_empty() {
+            self.complete(completion);
+        } else {
+            self.state = State::Sending {
+                data,
+                pos: 0,
+                completion,
+            };
+            self.send_chunk();
+        }
+    }
+
+    /// Queues the next chunk of the current response, followed by its checksum.
+    fn send_chunk(&mut self) {
+        if let State::Sending { data, pos, .. } = &self.state {
+            let end = (*pos + usize::from(self.chunk_size)).min(data.len());
+            let chunk = &data[*pos..end];
+
+            self.output.extend(chunk);
+            self.output.push_back(compute_checksum(chunk));
+        }
+    }
+
+    /// Performs an operation after a response has been acknowledged.
+    fn complete(&mut self, completion: Completion) {
+        match completion {
+            Completion::None => {}
+            Completion::SetChunkSize(size) => self.chunk_size = size,
+            Completion::SetBaudRate(rate) => {
+                self.baud_rate = rate;
+                self.lock();
+            }
+        }
+    }
+}
+
+/// Returns an error if the specified condition is not met.
+fn require(cond: bool) -> Result<(), ()> {
+    if cond { Ok(()) } else { Err(()) }
+}
+
+impl ErrorType for Simulator {
+    type Error = Infallible;
+}
+
+impl Read for Simulator {
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>> {
+        let len = buf.len().min(self.output.len());
+
+        for (dst, src) in buf.iter_mut().zip(self.output.drain(..len)) {
+            *dst = src;
+        }
+
+        future::ready(Ok(len))
+    }
+}
+
+impl Write for Simulator {
+    fn write(&mut self, buf: &[u8]) -> impl Future<Output = Result<usize, Self::Error>> {
+        if let Some(now) = self.clock.as_ref().map(|clock| clock.now()) {
+            if now.saturating_sub(self.last_activity) >= LOCK_TIMEOUT {
+                trace!("Simulator locked due to inactivity");
+
+                self.lock();
+            }
+
+            self.last_activity = now;
+        }
+
+        if self.port_baud_rate != self.baud_rate {
+            trace!("Simulator discarded data received at incorrect baud rate");
+
+            return future::ready(Ok(buf.len()));
+        }
+
+        for &byte in buf {
+            self.process(byte);
+        }
+
+        future::ready(Ok(buf.len()))
+    }
+
+    fn flush(&mut self) -> impl Future<Output = Result<(), Self::Error>> {
+        future::ready(Ok(()))
+    }
+}
+
+impl ConfigurableBaudRate for Simulator {
+    fn set_baud_rate(&mut self, rate: BaudRate) -> Result<(), Self::Error> {
+        self.port_baud_rate = rate;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Error, Interface,
+        device::{self, DeviceKind, Result, Value, id629},
+        tests::init_logger,
+    };
+
+    fn washing_machine() -> Simulator {
+        Simulator::new(629, 0x43ea, 0x1f02)
+            .with_ram(0x0000, vec![0x00; 0x0400])
+            .with_rom(0x1000, vec![0x00; 0xf000])
+    }
+
+    #[tokio::test]
+    async fn connect_to_device() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = washing_machine();
+
+        sim.write_memory(0x00cd, &[0x02]);
+
+        {
+            let mut dev = device::connect(&mut sim).await?;
+            let prop = dev
+                .properties()
+                .iter()
+                .find(|prop| prop.id == "operating_state")
+                .unwrap();
+
+            assert_eq!(dev.software_id(), 629, "software ID should be correct");
+            assert_eq!(
+                dev.kind(),
+                DeviceKind::WashingMachine,
+                "device kind should be correct"
+            );
+            assert_eq!(
+                dev.query_property(prop).await?,
+                Value::from_enum(id629::OperatingState::ProgramRunning),
+                "property value should be correct"
+            );
+        }
+
+        let mut protection = [0x00];
+
+        sim.read_memory(0x02c2, &mut protection);
+
+        assert_eq!(
+            protection,
+            [0x01],
+            "ROM readout protection should be disabled"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_write_memory() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = washing_machine();
+
+        sim.write_memory(0x1234, &[0xab, 0xcd]);
+
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.query_software_id().await?;
+            intf.unlock_read_access(0x43ea).await?;
+            intf.unlock_full_access(0x1f02).await?;
+            intf.write_memory(0x0100, [0x11, 0x22, 0x33, 0x44, 0x55, 0x66])
+                .await?;
+
+            // ROM is not writable
+            intf.write_memory(0x1234, 0x00u8).await?;
+
+            let ram: [u8; 6] = intf.read_memory(0x0100).await?;
+            let rom: u16 = intf.read_memory(0x1234).await?;
+            let unmapped: u8 = intf.read_memory(0x0800).await?;
+
+            assert_eq!(
+                ram,
+                [0x11, 0x22, 0x33, 0x44, 0x55, 0x66],
+                "RAM contents should be correct"
+            );
+            assert_eq!(rom, 0xcdab, "ROM contents should be correct");
+            assert_eq!(unmapped, 0xff, "unmapped memory should be correct");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_write_eeprom() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = washing_machine().with_eeprom(
+            [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77],
+            EepromAddressing::Word,
+        );
+
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.query_software_id().await?;
+            intf.unlock_read_access(0x43ea).await?;
+            intf.unlock_full_access(0x1f02).await?;
+            intf.write_eeprom(0x0003, [0xaa, 0xbb]).await?;
+
+            let data: [u8; 4] = intf.read_eeprom(0x0001).await?;
+
+            assert_eq!(
+                data,
+                [0x22, 0x33, 0x44, 0x55],
+                "EEPROM contents should be correct"
+            );
+        }
+
+        assert_eq!(
+            sim.eeprom(),
+            [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0xaa, 0xbb],
+            "EEPROM contents should be modified"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn extended_commands() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = Simulator::new(2088, 0x43ea, 0x1f02)
+            .with_ram(0x0001_0000, [0x00; 0x0200])
+            .with_extended_commands(BaudRate::Baud38400, 64);
+
+        sim.write_memory(0x0001_01fe, &[0x12, 0x34]);
+
+        {
+            let mut intf = Interface::new(&mut sim);
+
+            intf.query_software_id().await?;
+            intf.unlock_read_access(0x43ea).await?;
+
+            assert_eq!(
+                intf.query_max_baud_rate().await?,
+                BaudRate::Baud38400,
+                "maximum baud rate should be correct"
+            );
+
+            intf.unlock_full_access(0x1f02).await?;
+            intf.set_chunk_size(128).await?;
+
+            let data: [u8; 0x200] = intf.read_memory(0x0001_0000).await?;
+
+            assert_eq!(
+                data[0x1fe..],
+                [0x12, 0x34],
+                "memory contents should be correct"
+            );
+        }
+
+        assert_eq!(sim.chunk_size(), 64, "chunk size should be clamped");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_invalid_command() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = washing_machine();
+        let mut intf = Interface::new(&mut sim);
+        let res: crate::Result<u8, _> = intf.read_memory(0x0000).await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidCommand,
+            "result should be invalid command error"
+        );
+
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x1234).await?;
+
+        let res: crate::Result<u8, _> = intf.read_memory(0x0000).await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidCommand,
+            "result should be invalid command error"
+        );
+
+        let res = intf.query_max_baud_rate().await;
+
+        assert_eq!(
+            res.unwrap_err(),
+            Error::InvalidCommand,
+            "result should be invalid command error"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_incorrect_checksum() -> Result<(), Infallible> {
+        init_logger();
+
+        let mut sim = washing_machine();
+        let mut resp = [0xff];
+
+        sim.write_all(&[0x11, 0x00, 0x00, 0x02, 0xff])
+            .await
+            .unwrap();
+        sim.read_exact(&mut resp).await.unwrap();
+
+        assert_eq!(
+            resp,
+            [ResponseCode::IncorrectChecksum as u8],
+            "response code should be correct"
+        );
+
+        let mut intf = Interface::new(&mut sim);
+
+        assert_eq!(
+            intf.query_software_id().await?,
+            629,
+            "software ID should be correct"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! Typestate access levels for the diagnostic [`Interface`].
+//!
+//! By default, an [`Interface`] tracks its access level at runtime using the [`Dynamic`] state.
+//! All commands are available, and sending a command without the required access level
+//! results in [`Error::InvalidCommand`](crate::Error::InvalidCommand) from the device.
+//!
+//! Alternatively, [`Interface::into_typed`] converts the interface into the [`Locked`] state,
+//! which encodes the access level in the type. Each state only exposes the commands
+//! permitted at its access level:
+//!
+//! | State               | Commands                                                      |
+//! |---------------------|---------------------------------------------------------------|
+//! | [`Locked`]          | software ID query, unlocking                                  |
+//! | [`ReadAccess`]      | memory and EEPROM reads, smart home requests                  |
+//! | [`SmartHomeAccess`] | baud rate and chunk size configuration, smart home requests   |
+//! | [`FullAccess`]      | all commands                                                  |
+//!
+//! Unlocking consumes the interface and returns it in the new state.
+//! Commands that reset the device's access level, like [`Interface::lock`],
+//! return the interface in the [`Locked`] state.
+//! If a transition fails, the interface is dropped, as the device's access level is unknown.
+//! A new interface can be created using the same port.
+//!
+//! # Examples
+//!
+//! ```
+//! use freemdu::{Interface, simulator::Simulator};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::Result<(), core::convert::Infallible> {
+//! let mut sim = Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x12, 0x34]);
+//! let mut intf = Interface::new(&mut sim).into_typed();
+//!
+//! intf.query_software_id().await?;
+//!
+//! let mut intf = intf.unlock_read_access(0x43ea).await?;
+//! let val: u16 = intf.read_memory(0x0000).await?;
+//! let mut intf = intf.unlock_full_access(0x1f02).await?;
+//!
+//! intf.write_memory(0x0000, 0x5678u16).await?;
+//!
+//! let intf = intf.lock().await?;
+//! # assert_eq!(val, 0x3412);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Calling a command without the required access level fails to compile:
+//!
+//! ```compile_fail
+//! # use freemdu::{Interface, simulator::Simulator};
+//! # async fn example(mut sim: Simulator) -> freemdu::Result<(), core::convert::Infallible> {
+//! let mut intf = Interface::new(&mut sim).into_typed();
+//!
+//! intf.write_memory(0x0000, 0x5678u16).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(doc)]
+use crate::Interface;
+
+/// Access level tracked at runtime, with all commands available.
+#[derive(Debug)]
+pub struct Dynamic;
+
+/// Locked interface, only permitting the unlock sequence.
+#[derive(Debug)]
+pub struct Locked;
+
+/// Read-only diagnostic access.
+#[derive(Debug)]
+pub struct ReadAccess;
+
+/// Smart home access.
+#[derive(Debug)]
+pub struct SmartHomeAccess;
+
+/// Full diagnostic access.
+#[derive(Debug)]
+pub struct FullAccess;
+
+/// Access state of an [`Interface`].
+///
+/// This trait is sealed and cannot be implemented outside of this crate.
+pub trait AccessState: private::Sealed {}
+
+/// Access state whose access level is encoded in the type.
+pub trait TypedState: AccessState {}
+
+/// Access state permitting baud rate queries and smart home requests.
+pub trait CanQuery: AccessState {}
+
+/// Access state permitting memory and EEPROM reads.
+pub trait CanRead: CanQuery {}
+
+/// Access state permitting baud rate and chunk size configuration.
+pub trait CanConfigure: CanQuery {}
+
+/// Access state permitting all commands.
+pub trait CanWrite: CanRead + CanConfigure {}
+
+impl AccessState for Dynamic {}
+impl AccessState for Locked {}
+impl AccessState for ReadAccess {}
+impl AccessState for SmartHomeAccess {}
+impl AccessState for FullAccess {}
+
+impl TypedState for Locked {}
+impl TypedState for ReadAccess {}
+impl TypedState for SmartHomeAccess {}
+impl TypedState for FullAccess {}
+
+impl CanQuery for Dynamic {}
+impl CanQuery for ReadAccess {}
+impl CanQuery for SmartHomeAccess {}
+impl CanQuery for FullAccess {}
+
+impl CanRead for Dynamic {}
+impl CanRead for ReadAccess {}
+impl CanRead for FullAccess {}
+
+impl CanConfigure for Dynamic {}
+impl CanConfigure for SmartHomeAccess {}
+impl CanConfigure for FullAccess {}
+
+impl CanWrite for Dynamic {}
+impl CanWrite for FullAccess {}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for super::Dynamic {}
+    impl Sealed for super::Locked {}
+    impl Sealed for super::ReadAccess {}
+    impl Sealed for super::SmartHomeAccess {}
+    impl Sealed for super::FullAccess {}
+}
@@ -0,0 +1,96 @@
+//! Runtime-agnostic time abstractions.
+//!
+//! The diagnostic protocol itself has no notion of time, but the interface
+//! automatically locks itself after 3 seconds of inactivity.
+//! The [`Clock`] trait allows an [`Interface`](crate::Interface) to track
+//! the time of the last command independently of the async runtime in use.
+//!
+//! Response timeouts are implemented on top of the
+//! [`DelayNs`] trait from `embedded-hal-async`, see [`Timeouts`].
+
+use alloc::boxed::Box;
+use core::{
+    fmt::{Debug, Formatter},
+    time::Duration,
+};
+use embedded_hal_async::delay::DelayNs;
+
+/// Inactivity period after which a device locks its diagnostic interface.
+pub(crate) const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Monotonic clock used for tracking elapsed time.
+///
+/// The clock's epoch is arbitrary, but the returned time must never decrease.
+/// The trait is implemented for all closures returning a [`Duration`],
+/// which makes it easy to wrap the clock of the async runtime in use.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Instant;
+///
+/// let start = Instant::now();
+/// let clock = move || start.elapsed();
+/// # let _: &dyn freemdu::time::Clock = &clock;
+/// ```
+pub trait Clock {
+    /// Returns the time elapsed since the clock's epoch.
+    fn now(&self) -> Duration;
+}
+
+impl<F: Fn() -> Duration> Clock for F {
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+impl Debug for dyn Clock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Clock").field("now", &self.now()).finish()
+    }
+}
+
+/// Response timeouts applied by an [`Interface`](crate::Interface).
+///
+/// Timeouts are enabled using [`Interface::enable_timeouts`](crate::Interface::enable_timeouts).
+/// If the device does not respond in time, the operation fails with
+/// [`Error::Timeout`](crate::Error::Timeout) instead of waiting indefinitely.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Timeouts {
+    /// Maximum time to wait for the first byte of a response.
+    pub frame: Duration,
+    /// Maximum time to wait for each subsequent byte of a response.
+    pub byte: Duration,
+}
+
+impl Default for Timeouts {
+    /// Returns timeouts suitable for all supported baud rates.
+    fn default() -> Self {
+        Self {
+            frame: Duration::from_millis(500),
+            byte: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Object-safe wrapper around [`DelayNs`].
+#[async_trait::async_trait(?Send)]
+pub(crate) trait Timer {
+    /// Waits for the specified duration.
+    async fn delay(&mut self, dur: Duration);
+}
+
+#[async_trait::async_trait(?Send)]
+impl<D: DelayNs> Timer for D {
+    async fn delay(&mut self, dur: Duration) {
+        let us = dur.as_micros().try_into().unwrap_or(u32::MAX);
+
+        self.delay_us(us).await;
+    }
+}
+
+impl Debug for dyn Timer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Timer").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,368 @@
+//! Memory watcher for reverse engineering device properties.
+//!
+//! Supporting a new software ID requires finding the memory addresses that track
+//! properties like the program phase or the water level. A [`MemoryWatcher`] polls
+//! a set of address ranges at a fixed rate and emits a [`Change`] for every byte
+//! that differs from the previous poll.
+//!
+//! Bytes that change constantly, like timers, can be excluded using
+//! [`MemoryWatcher::ignore`] or detected automatically using [`MemoryWatcher::ignore_changing`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use freemdu::{serial::Delay, watcher::MemoryWatcher};
+//! use futures_util::StreamExt;
+//! use std::{pin::pin, time::{Duration, Instant}};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> freemdu::device::Result<(), freemdu::serial::PortError> {
+//! let mut port = freemdu::serial::open("/dev/ttyACM0")?;
+//! let mut dev = freemdu::device::connect(&mut port).await?;
+//! let start = Instant::now();
+//! let watcher = MemoryWatcher::new(Duration::from_secs(1))
+//!     .watch(0x0000..0x0200)
+//!     .ignore_changing(5);
+//! let mut changes = pin!(watcher.stream(dev.interface(), Delay, move || start.elapsed()));
+//!
+//! while let Some(change) = changes.next().await {
+//!     let change = change?;
+//!
+//!     println!("{:04x}: {:02x} -> {:02x}", change.addr, change.old, change.new);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    Interface, Result,
+    state::CanRead,
+    time::{Clock, Timer},
+};
+use alloc::{
+    collections::{btree_set::BTreeSet, vec_deque::VecDeque},
+    vec,
+    vec::Vec,
+};
+use core::{ops::Range, time::Duration};
+use embedded_hal_async::delay::DelayNs;
+use embedded_io_async::{Read, Write};
+use futures_util::{Stream, stream};
+use log::trace;
+
+/// Change of a single byte in the device's memory.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Change {
+    /// Memory address of the byte.
+    pub addr: u32,
+    /// Value read during the previous poll.
+    pub old: u8,
+    /// Value read during the current poll.
+    pub new: u8,
+    /// Time of the current poll, as returned by the watcher's clock.
+    pub time: Duration,
+}
+
+/// Watcher that polls memory ranges and reports changes.
+#[derive(Clone, Debug)]
+pub struct MemoryWatcher {
+    interval: Duration,
+    ranges: Vec<Range<u32>>,
+    ignored: Vec<Range<u32>>,
+    calibration_polls: u32,
+}
+
+impl MemoryWatcher {
+    /// Constructs a new watcher polling at the specified interval.
+    ///
+    /// If a poll takes longer than the interval, the next poll starts immediately.
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            ranges: Vec::new(),
+            ignored: Vec::new(),
+            calibration_polls: 0,
+        }
+    }
+
+    /// Adds a memory range to poll.
+    #[must_use]
+    pub fn watch(mut self, range: Range<u32>) -> Self {
+        self.ranges.push(range);
+        self
+    }
+
+    /// Ignores all changes within the specified memory range.
+    #[must_use]
+    pub fn ignore(mut self, range: Range<u32>) -> Self {
+        self.ignored.push(range);
+        self
+    }
+
+    /// Ignores all bytes that change during the first polls after the initial one.
+    ///
+    /// The device should be left alone during these polls,
+    /// so that only bytes changing by themselves, like timers, are ignored.
+    #[must_use]
+    pub fn ignore_changing(mut self, polls: u32) -> Self {
+        self.calibration_polls = polls;
+        self
+    }
+
+    /// Returns a stream of changes, polling memory using the provided interface.
+    ///
+    /// The first poll only records the initial memory contents.
+    /// The delay is used to wait between polls and the clock provides the change timestamps.
+    /// Failed polls are reported as errors, after which polling continues.
+    pub fn stream<'a, P: Read + Write, S: CanRead>(
+        self,
+        intf: &'a mut Interface<P, S>,
+        delay: impl DelayNs + 'a,
+        clock: impl Clock + 'a,
+    ) -> impl Stream<Item = Result<Change, P::Error>> + 'a {
+        let poller = Poller {
+            intf,
+            delay,
+            clock,
+            snapshots: vec![None; self.ranges.len()],
+            watcher: self,
+            noisy: BTreeSet::new(),
+            pending: VecDeque::new(),
+            polls: 0,
+            next_poll: None,
+        };
+
+        stream::unfold(poller, async |mut poller| {
+            let change = poller.next().await;
+
+            Some((change, poller))
+        })
+    }
+}
+
+/// State of an active memory watcher stream.
+struct Poller<'a, P, S, D, C> {
+    intf: &'a mut Interface<P, S>,
+    delay: D,
+    clock: C,
+    watcher: MemoryWatcher,
+    snapshots: Vec<Option<Vec<u8>>>,
+    noisy: BTreeSet<u32>,
+    pending: VecDeque<Change>,
+    polls: u32,
+    next_poll: Option<Duration>,
+}
+
+impl<P: Read + Write, S: CanRead, D: DelayNs, C: Clock> Poller<'_, P, S, D, C> {
+    /// Polls memory until a change is detected.
+    async fn next(&mut self) -> Result<Change, P::Error> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Ok(change);
+            }
+
+            self.poll().await?;
+        }
+    }
+
+    /// Waits for the next poll and compares all ranges with their previous contents.
+    async fn poll(&mut self) -> Result<(), P::Error> {
+        if let Some(next_poll) = self.next_poll {
+            let wait = next_poll.saturating_sub(self.clock.now());
+
+            if !wait.is_zero() {
+                self.delay.delay(wait).await;
+            }
+        }
+
+        let time = self.clock.now();
+        let calibrating = self.polls > 0 && self.polls <= self.watcher.calibration_polls;
+
+        self.next_poll = Some(time + self.watcher.interval);
+        self.polls += 1;
+
+        for (range, snapshot) in self.watcher.ranges.iter().zip(&mut self.snapshots) {
+            let len = range.end.saturating_sub(range.start) as usize;
+            let mut data = vec![0x00; len];
+
+            self.intf
+                .read_memory_slice(range.start, &mut data, |_, _| {})
+                .await?;
+
+            let Some(prev) = snapshot.replace(data.clone()) else {
+                continue;
+            };
+
+            for ((addr, &old), &new) in range.clone().zip(&prev).zip(&data) {
+                if old == new
+                    || self.noisy.contains(&addr)
+                    || self.watcher.ignored.iter().any(|r| r.contains(&addr))
+                {
+                    continue;
+                }
+
+                if calibrating {
+                    trace!("Ignoring changing byte at {addr:04x}");
+                    self.noisy.insert(addr);
+                } else {
+                    self.pending.push_back(Change {
+                        addr,
+                        old,
+                        new,
+                        time,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{simulator::Simulator, tests::init_logger};
+    use alloc::rc::Rc;
+    use core::{cell::Cell, convert::Infallible, pin::pin};
+    use embedded_io_async::ErrorType;
+    use futures_util::StreamExt;
+
+    /// Port that updates the simulated memory with the number of elapsed ticks.
+    ///
+    /// Byte 0 changes every tick, byte 1 every second tick and byte 2 never.
+    struct TickingPort {
+        sim: Simulator,
+        ticks: Rc<Cell<u8>>,
+    }
+
+    impl ErrorType for TickingPort {
+        type Error = Infallible;
+    }
+
+    impl Read for TickingPort {
+        async fn read(&mut self, buf: &mut [u8]) -> core::result::Result<usize, Infallible> {
+            self.sim.read(buf).await
+        }
+    }
+
+    impl Write for TickingPort {
+        async fn write(&mut self, buf: &[u8]) -> core::result::Result<usize, Infallible> {
+            let ticks = self.ticks.get();
+
+            self.sim.write_memory(0x0000, &[ticks, ticks / 2]);
+            self.sim.write(buf).await
+        }
+
+        async fn flush(&mut self) -> core::result::Result<(), Infallible> {
+            self.sim.flush().await
+        }
+    }
+
+    /// Delay that advances the ticks instead of waiting.
+    struct TickingDelay(Rc<Cell<u8>>);
+
+    impl DelayNs for TickingDelay {
+        async fn delay_ns(&mut self, _ns: u32) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn watch_changes() -> Result<(), Infallible> {
+        init_logger();
+
+        let ticks = Rc::new(Cell::new(0));
+        let port = TickingPort {
+            sim: Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x10]),
+            ticks: ticks.clone(),
+        };
+        let mut intf = Interface::new(port);
+
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x43ea).await?;
+
+        let clock_ticks = ticks.clone();
+        let watcher = MemoryWatcher::new(Duration::from_secs(1))
+            .watch(0x0000..0x0003)
+            .ignore(0x0000..0x0001);
+        let mut changes = pin!(
+            watcher.stream(&mut intf, TickingDelay(ticks.clone()), move || {
+                Duration::from_secs(clock_ticks.get().into())
+            })
+        );
+
+        assert_eq!(
+            changes.next().await,
+            Some(Ok(Change {
+                addr: 0x0001,
+                old: 0x00,
+                new: 0x01,
+                time: Duration::from_secs(2),
+            })),
+            "change should be correct"
+        );
+        assert_eq!(
+            changes.next().await,
+            Some(Ok(Change {
+                addr: 0x0001,
+                old: 0x01,
+                new: 0x02,
+                time: Duration::from_secs(4),
+            })),
+            "change should be correct"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ignore_changing() -> Result<(), Infallible> {
+        init_logger();
+
+        let ticks = Rc::new(Cell::new(0));
+        let port = TickingPort {
+            sim: Simulator::new(629, 0x43ea, 0x1f02).with_ram(0x0000, [0x00; 0x10]),
+            ticks: ticks.clone(),
+        };
+        let mut intf = Interface::new(port);
+
+        intf.query_software_id().await?;
+        intf.unlock_read_access(0x43ea).await?;
+
+        // Byte 1 does not change during the single calibration poll
+        let clock_ticks = ticks.clone();
+        let watcher = MemoryWatcher::new(Duration::from_secs(1))
+            .watch(0x0000..0x0003)
+            .ignore_changing(1);
+        let changes: Vec<_> = watcher
+            .stream(&mut intf, TickingDelay(ticks.clone()), move || {
+                Duration::from_secs(clock_ticks.get().into())
+            })
+            .take(2)
+            .collect()
+            .await;
+
+        assert_eq!(
+            changes,
+            [
+                Ok(Change {
+                    addr: 0x0001,
+                    old: 0x00,
+                    new: 0x01,
+                    time: Duration::from_secs(2),
+                }),
+                Ok(Change {
+                    addr: 0x0001,
+                    old: 0x01,
+                    new: 0x02,
+                    time: Duration::from_secs(4),
+                }),
+            ],
+            "changes should be correct"
+        );
+
+        Ok(())
+    }
+}
@@ -10,7 +10,11 @@ use crate::{
 };
 use anyhow::{Context, Result};
 use clap::Parser;
-use freemdu::serial::{self, Port};
+use freemdu::{
+    KeyPair,
+    device::{ConnectOptions, definition::Definition, generic::GenericConfig},
+    serial::{self, Port},
+};
 use futures::{StreamExt, future::FutureExt};
 use ratatui::{
     DefaultTerminal,
@@ -21,12 +25,33 @@ use ratatui::{
     text::Line,
     widgets::{Block, BorderType, Borders, Padding, StatefulWidget, Widget},
 };
+use std::{fs, path::PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
 struct Args {
     /// Serial port path (e.g. /dev/ttyACM0)
     serial_port: String,
+    /// Diagnostic keys for unsupported devices (e.g. 43ea:1f02)
+    #[arg(long, value_parser = parse_keys)]
+    keys: Option<KeyPair>,
+    /// Device definition file, can be specified multiple times
+    #[arg(long = "definition", value_name = "FILE")]
+    definitions: Vec<PathBuf>,
+    /// Never write to the device, disabling all actions
+    #[arg(long)]
+    read_only: bool,
+}
+
+fn parse_keys(arg: &str) -> Result<KeyPair> {
+    let (read, full) = arg
+        .split_once(':')
+        .context("Keys must be separated by a colon")?;
+
+    Ok(KeyPair::new(
+        u16::from_str_radix(read, 16)?,
+        u16::from_str_radix(full, 16)?,
+    ))
 }
 
 #[derive(Default, Debug)]
@@ -36,9 +61,16 @@ struct App {
 }
 
 impl App {
-    async fn run(&mut self, port: Port, term: &mut DefaultTerminal) -> Result<()> {
+    async fn run(
+        &mut self,
+        port: Port,
+        options: ConnectOptions,
+        config: GenericConfig,
+        defs: Vec<Definition>,
+        term: &mut DefaultTerminal,
+    ) -> Result<()> {
         let mut events = EventStream::new();
-        let mut rx = Worker::start(port);
+        let mut rx = Worker::start(port, options, config, defs);
 
         while !self.should_exit {
             // Draw terminal widgets
@@ -56,9 +88,7 @@ impl App {
             tokio::select! {
                 Some(evt) = events.next().fuse() => self
                     .handle_event(&evt?).context("Failed to handle event")?,
-                Some(resp) = rx.recv() => self
-                    .handle_worker_response(resp)
-                    .context("Failed to handle worker response")?,
+                Some(resp) = rx.recv() => self.handle_worker_response(resp),
             }
         }
 
@@ -89,7 +119,7 @@ impl App {
         Ok(())
     }
 
-    fn handle_worker_response(&mut self, resp: Response) -> Result<()> {
+    fn handle_worker_response(&mut self, resp: Response) {
         match resp {
             Response::DeviceConnected {
                 software_id,
@@ -97,17 +127,15 @@ impl App {
                 actions,
                 tx,
             } => {
-                self.session = Some(Session::create(software_id, kind, actions, tx)?);
+                self.session = Some(Session::create(software_id, kind, actions, tx));
             }
             Response::DeviceDisconnected => self.session = None,
             _ => {
                 if let Some(sess) = &mut self.session {
-                    sess.handle_worker_response(resp)?;
+                    sess.handle_worker_response(resp);
                 }
             }
         }
-
-        Ok(())
     }
 }
 
@@ -156,8 +184,24 @@ async fn main() -> Result<()> {
 
     let args = Args::parse();
     let port = serial::open(&args.serial_port).context("Failed to open serial port")?;
+    let config = match args.keys {
+        Some(keys) => GenericConfig::new().with_keys(keys),
+        None => GenericConfig::new(),
+    };
+    let defs = args
+        .definitions
+        .iter()
+        .map(|path| {
+            fs::read_to_string(path)?
+                .parse()
+                .with_context(|| format!("Failed to parse definition {}", path.display()))
+        })
+        .collect::<Result<_>>()?;
+    let options = ConnectOptions::new().with_read_only(args.read_only);
     let mut term = ratatui::init();
-    let res = App::default().run(port, &mut term).await;
+    let res = App::default()
+        .run(port, options, config, defs, &mut term)
+        .await;
 
     ratatui::restore();
 
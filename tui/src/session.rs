@@ -34,8 +34,8 @@ impl Session {
         kind: DeviceKind,
         actions: &'static [Action],
         tx: UnboundedSender<Request>,
-    ) -> Result<Self> {
-        let mut sess = Session {
+    ) -> Self {
+        Session {
             software_id,
             kind,
             tables: vec![
@@ -60,11 +60,7 @@ impl Session {
             popup: None,
             update_counter: 0,
             tx,
-        };
-
-        sess.schedule_prop_update()?;
-
-        Ok(sess)
+        }
     }
 
     pub fn handle_event(&mut self, event: &Event) -> Result<bool> {
@@ -101,14 +97,14 @@ impl Session {
         }
     }
 
-    pub fn handle_worker_response(&mut self, resp: Response) -> Result<()> {
+    pub fn handle_worker_response(&mut self, resp: Response) {
         match resp {
             Response::PropertiesQueried(kind, data) => {
                 if let Some((_, table)) = self.tables.iter_mut().find(|(k, _)| k == &kind) {
                     table.update(data);
                 }
 
-                self.schedule_prop_update()?;
+                self.update_counter += 1;
             }
             Response::InvalidActionArgument(action) => {
                 self.popup = Some(Popup::InvalidActionArgument(action));
@@ -118,27 +114,6 @@ impl Session {
             }
             _ => {}
         }
-
-        Ok(())
-    }
-
-    fn schedule_prop_update(&mut self) -> Result<()> {
-        // Select next property kind to update
-        let kind = match self.update_counter {
-            0 => PropertyKind::General,
-            1 => PropertyKind::Fault,
-            2 => PropertyKind::Operation,
-            3 => PropertyKind::Io,
-            cnt if cnt % 90 == 0 => PropertyKind::General,
-            cnt if cnt % 30 == 0 => PropertyKind::Fault,
-            cnt if cnt % 3 == 0 => PropertyKind::Operation,
-            _ => PropertyKind::Io,
-        };
-
-        self.tx.send(Request::QueryProperties(kind))?;
-        self.update_counter += 1;
-
-        Ok(())
     }
 
     fn render_tables(&self, area: Rect, buf: &mut Buffer) {
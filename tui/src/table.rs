@@ -7,6 +7,7 @@ use ratatui::{
     text::Line,
     widgets::{Block, BorderType, LineGauge, Padding, Paragraph, Widget, Wrap},
 };
+use std::fmt::Display;
 
 #[derive(Debug)]
 enum Cell {
@@ -89,12 +90,15 @@ impl PropertyTable {
                     Some(Cell::Text("No".to_string()))
                 }
             }
-            Value::Number(num) => {
-                if let Some(unit) = prop.unit {
-                    Some(Cell::Text(format!("{num} {unit}")))
-                } else {
-                    Some(Cell::Text(num.to_string()))
-                }
+            Value::Number(num) => Some(Cell::Text(Self::with_unit(prop, num))),
+            Value::Integer(num) => Some(Cell::Text(Self::with_unit(prop, num))),
+            &Value::Decimal { value, scale } => {
+                let num = f64::from(value) / 10_f64.powi(scale.into());
+
+                Some(Cell::Text(Self::with_unit(
+                    prop,
+                    format!("{num:.*}", usize::from(scale)),
+                )))
             }
             &Value::Sensor(current, target) => {
                 let txt = if let Some(unit) = prop.unit {
@@ -112,6 +116,8 @@ impl PropertyTable {
                 Some(Cell::Gauge(txt, ratio))
             }
             Value::String(string) => Some(Cell::Text(string.clone())),
+            Value::Enum { value, .. } => Some(Cell::Text((*value).to_string())),
+            Value::Flags { set, .. } => Some(Cell::Text(set.join(" | "))),
             Value::Duration(dur) => {
                 let total_mins = dur.as_secs() / 60;
                 let hours = total_mins / 60;
@@ -147,6 +153,14 @@ impl PropertyTable {
         }
     }
 
+    fn with_unit(prop: &Property, val: impl Display) -> String {
+        if let Some(unit) = prop.unit {
+            format!("{val} {unit}")
+        } else {
+            val.to_string()
+        }
+    }
+
     fn row_height_out_of_bounds(row: Rect, area: Rect) -> bool {
         row.y + row.height > area.y + area.height
     }
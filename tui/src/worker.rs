@@ -1,26 +1,55 @@
 use anyhow::{Context, Result};
 use freemdu::{
-    device::{self, Action, DeviceKind, Error, Property, PropertyKind, Value},
-    serial::Port,
+    Interface,
+    device::{
+        self, Action, ConnectOptions, DeviceKind, DeviceRegistry, Error, Property, PropertyKind,
+        Value,
+        cycles::CycleTracker,
+        definition::Definition,
+        faults::FaultLog,
+        generic::GenericConfig,
+        watch::{PropertyChange, PropertyWatcher, StopHandle},
+    },
+    serial::{Delay, Port},
+    time::Timeouts,
 };
+use futures::StreamExt;
+use jiff::{Timestamp, tz::TimeZone};
 use log::debug;
+use std::{
+    mem,
+    pin::pin,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::{
     sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
     task,
-    time::{self, Duration},
+    time::{self, Duration, Instant},
 };
 
-// Timeout for device operations (e.g. connection)
-const DEVICE_TIMEOUT: Duration = Duration::from_secs(1);
+// Number of retries for corrupted diagnostic frames
+const DEVICE_MAX_RETRIES: u8 = 2;
 
 // Delay between device connection attempts
 const DEVICE_CONNECT_INTERVAL: Duration = Duration::from_secs(4);
 
+// Interval for polling operation and input/output properties,
+// which also keeps the diagnostic session alive
+const PROPERTY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Interval for polling fault properties
+const FAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// Interval for polling general properties
+const GENERAL_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// Interval for updating the remaining time estimates
+const ESTIMATE_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
 type Device<'a> = Box<dyn device::Device<&'a mut Port> + 'a>;
 
 #[derive(Debug)]
 pub enum Request {
-    QueryProperties(PropertyKind),
     TriggerAction(&'static Action, Option<String>),
 }
 
@@ -41,25 +70,52 @@ pub enum Response {
 pub struct Worker<'a> {
     dev: Device<'a>,
     tx: &'a UnboundedSender<Response>,
+    state: State,
 }
 
 impl Worker<'_> {
-    pub fn start(mut port: Port) -> UnboundedReceiver<Response> {
+    pub fn start(
+        mut port: Port,
+        options: ConnectOptions,
+        config: GenericConfig,
+        defs: Vec<Definition>,
+    ) -> UnboundedReceiver<Response> {
         let (tx, rx) = mpsc::unbounded_channel();
 
         task::spawn_local(async move {
             loop {
-                // Automatically reconnect in case of failure
-                match time::timeout(DEVICE_TIMEOUT, device::connect(&mut port)).await {
-                    Ok(Ok(dev)) => {
-                        let mut worker = Worker { dev, tx: &tx };
+                let mut intf = Interface::new(&mut port);
+                let mut registry = DeviceRegistry::new()
+                    .with_options(options.clone())
+                    .with_fallback(config.clone());
+
+                intf.enable_timeouts(Delay, Timeouts::default());
+                intf.set_max_retries(DEVICE_MAX_RETRIES);
+
+                for def in &defs {
+                    registry.register(def.software_ids().iter().copied(), def.clone());
+                }
+
+                // Automatically reconnect in case of failure,
+                // unsupported devices are accessed as generic devices
+                match registry.connect_with(intf).await {
+                    Ok(mut dev) => {
+                        let start = Instant::now();
+
+                        // Transparently restore the session if the device locks itself
+                        dev.interface().enable_session(move || start.elapsed());
+
+                        let mut worker = Worker {
+                            state: State::new(dev.properties()),
+                            dev,
+                            tx: &tx,
+                        };
 
                         if let Err(err) = worker.run().await {
                             debug!("Error running device worker: {err:#}");
                         }
                     }
-                    Ok(Err(err)) => debug!("Error connecting to device: {err:#}"),
-                    Err(_) => debug!("Device connection timed out"),
+                    Err(err) => debug!("Error connecting to device: {err:#}"),
                 }
 
                 time::sleep(DEVICE_CONNECT_INTERVAL).await;
@@ -72,24 +128,35 @@ impl Worker<'_> {
     async fn run(&mut self) -> Result<()> {
         let (dev_tx, mut dev_rx) = mpsc::unbounded_channel();
 
+        // Start the fault log with the history kept by the device, if any,
+        // the session continues without it if the history cannot be read
+        match self.dev.query_fault_history().await {
+            Ok(history) => self.state.log.extend_history(history),
+            Err(Error::Unavailable) => {}
+            Err(err) => debug!("Error querying fault history: {err}"),
+        }
+
         self.tx.send(Response::DeviceConnected {
             software_id: self.dev.software_id(),
             kind: self.dev.kind(),
-            actions: self.dev.actions(),
+            // Actions always fail in read-only mode
+            actions: if self.dev.interface().is_read_only() {
+                &[]
+            } else {
+                self.dev.actions()
+            },
             tx: dev_tx,
         })?;
 
         // Handle incoming commands from device channel
-        while let Some(cmd) = dev_rx.recv().await {
-            let res = match cmd {
-                Request::QueryProperties(kind) => self
-                    .query_properties(kind)
-                    .await
-                    .context("Failed to query properties"),
-                Request::TriggerAction(action, param) => self
+        loop {
+            let res = match self.watch(&mut dev_rx).await {
+                Ok(Some(Request::TriggerAction(action, param))) => self
                     .trigger_action(action, param.as_deref())
                     .await
                     .context("Failed to trigger action"),
+                Ok(None) => break,
+                Err(err) => Err(err.context("Failed to watch properties")),
             };
 
             if res.is_err() {
@@ -102,27 +169,59 @@ impl Worker<'_> {
         Ok(())
     }
 
-    async fn query_properties(&mut self, kind: PropertyKind) -> Result<()> {
-        let mut data = Vec::new();
+    // Watch properties until a request needs exclusive device access
+    async fn watch(&mut self, rx: &mut UnboundedReceiver<Request>) -> Result<Option<Request>> {
+        let props = self.dev.properties();
+        let with_kind = |kind| props.iter().filter(move |prop| prop.kind == kind);
+        let stop = StopHandle::new();
+        let watcher = PropertyWatcher::new(PROPERTY_POLL_INTERVAL)
+            .watch(with_kind(PropertyKind::Operation).chain(with_kind(PropertyKind::Io)))
+            .watch_every(with_kind(PropertyKind::Fault), FAULT_POLL_INTERVAL)
+            .watch_every(with_kind(PropertyKind::General), GENERAL_POLL_INTERVAL)
+            .stop_on(stop.clone());
+        // Timestamps are based on the system time to provide the estimated end as a calendar time
+        let mut changes = pin!(watcher.stream(&mut *self.dev, Delay, system_time));
+        let mut estimates = time::interval(ESTIMATE_UPDATE_INTERVAL);
+        let mut pending = None;
 
-        for prop in self
-            .dev
-            .properties()
-            .iter()
-            .filter(|prop| prop.kind == kind)
-        {
-            let val = time::timeout(DEVICE_TIMEOUT, self.dev.query_property(prop)).await??;
+        loop {
+            tokio::select! {
+                change = changes.next() => match change {
+                    Some(Ok(change)) => {
+                        let kind = change.property.kind;
 
-            data.push((prop, val));
+                        self.state.update(change);
+                        self.tx.send(self.state.properties(kind))?;
+                    }
+                    // Skip properties unavailable in read-only mode
+                    Some(Err(Error::Unavailable)) => {}
+                    Some(Err(err)) => return Err(err.into()),
+                    // The stream ends between two polls once stopped,
+                    // or right away if there are no properties to watch
+                    None => {
+                        return Ok(match pending {
+                            Some(req) => Some(req),
+                            None => rx.recv().await,
+                        });
+                    }
+                },
+                req = rx.recv(), if pending.is_none() => match req {
+                    Some(req) => {
+                        // Let the watcher finish the current poll
+                        pending = Some(req);
+                        stop.stop();
+                    }
+                    None => return Ok(None),
+                },
+                _ = estimates.tick() => {
+                    self.tx.send(self.state.properties(PropertyKind::Operation))?;
+                }
+            }
         }
-
-        self.tx.send(Response::PropertiesQueried(kind, data))?;
-
-        Ok(())
     }
 
     async fn trigger_action(&mut self, action: &'static Action, param: Option<&str>) -> Result<()> {
-        match time::timeout(DEVICE_TIMEOUT, self.dev.trigger_action(action, param)).await? {
+        match self.dev.trigger_action(action, param).await {
             Err(Error::InvalidArgument) => self.tx.send(Response::InvalidActionArgument(action))?,
             Err(Error::InvalidState) => self.tx.send(Response::InvalidActionState(action))?,
             res => res?,
@@ -131,3 +230,98 @@ impl Worker<'_> {
         Ok(())
     }
 }
+
+// Latest property values and the history derived from their changes
+struct State {
+    props: &'static [Property],
+    tracker: CycleTracker,
+    log: FaultLog,
+    values: Vec<(&'static Property, Value)>,
+}
+
+impl State {
+    fn new(props: &'static [Property]) -> Self {
+        Self {
+            props,
+            tracker: CycleTracker::new(props),
+            log: FaultLog::new(props),
+            values: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, change: PropertyChange) {
+        let old = if let Some((_, val)) = self
+            .values
+            .iter_mut()
+            .find(|(prop, _)| *prop == change.property)
+        {
+            Some(mem::replace(val, change.new.clone()))
+        } else {
+            self.values.push((change.property, change.new.clone()));
+
+            None
+        };
+
+        // Initial values are reported again after the watcher has been restarted
+        if change.old.is_none() && old.as_ref() == Some(&change.new) {
+            return;
+        }
+
+        let change = PropertyChange { old, ..change };
+
+        if let Some(summary) = self.tracker.update(&change) {
+            debug!("Program run finished: {summary:?}");
+        }
+
+        self.log.update(&change);
+    }
+
+    fn properties(&self, kind: PropertyKind) -> Response {
+        let mut data: Vec<_> = self
+            .props
+            .iter()
+            .filter(|prop| prop.kind == kind)
+            .filter_map(|prop| self.values.iter().find(|(p, _)| *p == prop))
+            .map(|(prop, val)| match val {
+                // Add occurrence information recorded while the session was active
+                Value::Fault(fault) => {
+                    (*prop, Value::Fault(self.log.annotate(prop, fault.clone())))
+                }
+                val => (*prop, val.clone()),
+            })
+            .collect();
+
+        if kind == PropertyKind::Operation {
+            let now = system_time();
+
+            data.extend(
+                CycleTracker::estimate_properties()
+                    .iter()
+                    .filter_map(|prop| match self.tracker.query_estimate(prop, now)? {
+                        Value::Duration(end) if prop.id == "estimated_end" => {
+                            Some((prop, format_time(end)?.into()))
+                        }
+                        val => Some((prop, val)),
+                    }),
+            );
+        }
+
+        Response::PropertiesQueried(kind, data)
+    }
+}
+
+fn system_time() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+// Format a time since the Unix epoch as local time of day
+fn format_time(time: Duration) -> Option<String> {
+    let secs = time.as_secs().try_into().ok()?;
+    let time = Timestamp::from_second(secs)
+        .ok()?
+        .to_zoned(TimeZone::system());
+
+    Some(time.strftime("%H:%M").to_string())
+}